@@ -0,0 +1,564 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::Json;
+use crypto_service::coin_watch_service::{
+    coin_watch_client::CoinWatchClient,
+    models::{Coin, CoinMapRequest},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{
+    models::{
+        AlertFiring, AlertRule, AlertState, AlertWithState,
+        CreateAlertRequest,
+    },
+    notifier::Notifier,
+};
+use crate::{
+    api_client::quota::Priority, state::AppState,
+    webhooks::models::EventKind,
+};
+
+pub const DEFAULT_COOLDOWN_SECS: u64 = 60 * 60;
+/// Longest cooldown a rule may ask for.
+pub const MAX_COOLDOWN_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Provider named in outage events from the poller.
+const PROVIDER: &str = "live_coin_watch";
+
+/// Everything that survives a restart: the rules and whether each
+/// of them is currently triggered.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AlertBook {
+    next_id: u64,
+    rules: Vec<AlertRule>,
+    states: HashMap<String, AlertState>,
+    /// Bumped on every change, so snapshots can be told apart.
+    #[serde(skip)]
+    generation: u64,
+}
+
+/// The book serialized at a given generation, to be written once
+/// its lock is released.
+type Snapshot = (u64, Vec<u8>);
+
+/// Holds the alert rules, evaluates them against fresh quotes and
+/// hands firings to a [`Notifier`].
+///
+/// With a `path` the rules and their state are written to disk as
+/// JSON on every change and read back by [`AlertEngine::load`].
+/// The book is only locked to take a snapshot, not while writing it.
+#[derive(Debug, Clone)]
+pub struct AlertEngine {
+    book: Arc<RwLock<AlertBook>>,
+    path: Option<PathBuf>,
+    /// Generation last written to `path`, held while writing so an
+    /// older snapshot never replaces a newer one.
+    persisted: Arc<tokio::sync::Mutex<u64>>,
+    notifier: Arc<dyn Notifier>,
+}
+
+impl AlertEngine {
+    /// An engine that only keeps its rules in memory.
+    pub fn new(notifier: Arc<dyn Notifier>) -> Self {
+        Self {
+            book: Arc::default(),
+            path: None,
+            persisted: Arc::default(),
+            notifier,
+        }
+    }
+
+    /// Loads rules persisted at `path`, starting out empty if the
+    /// file doesn't exist yet.
+    pub fn load(
+        path: PathBuf,
+        notifier: Arc<dyn Notifier>,
+    ) -> io::Result<Self> {
+        let book = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e)
+                if e.kind() == io::ErrorKind::NotFound =>
+            {
+                AlertBook::default()
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            book: Arc::new(RwLock::new(book)),
+            path: Some(path),
+            persisted: Arc::default(),
+            notifier,
+        })
+    }
+
+    pub fn rules(&self) -> Vec<AlertWithState> {
+        let book =
+            self.book.read().expect("Alert lock poisoned");
+        book.rules
+            .iter()
+            .map(|rule| AlertWithState {
+                rule: rule.clone(),
+                state: book
+                    .states
+                    .get(&rule.id)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    pub async fn add_rule(
+        &self,
+        request: CreateAlertRequest,
+    ) -> io::Result<AlertRule> {
+        let (rule, snapshot) = {
+            let mut book =
+                self.book.write().expect("Alert lock poisoned");
+            book.next_id += 1;
+            let rule = AlertRule {
+                id: book.next_id.to_string(),
+                code: request.code.to_uppercase(),
+                condition: request.condition,
+                cooldown_secs: request
+                    .cooldown_secs
+                    .unwrap_or(DEFAULT_COOLDOWN_SECS),
+            };
+            book.rules.push(rule.clone());
+            (rule, self.snapshot(&mut book)?)
+        };
+        self.save(snapshot).await?;
+        Ok(rule)
+    }
+
+    /// Returns whether a rule with `id` existed.
+    pub async fn remove_rule(
+        &self,
+        id: &str,
+    ) -> io::Result<bool> {
+        let snapshot = {
+            let mut book =
+                self.book.write().expect("Alert lock poisoned");
+            let count = book.rules.len();
+            book.rules.retain(|rule| rule.id != id);
+            book.states.remove(id);
+            if book.rules.len() == count {
+                return Ok(false);
+            }
+            self.snapshot(&mut book)?
+        };
+        self.save(snapshot).await?;
+        Ok(true)
+    }
+
+    /// Coin codes referenced by any rule, sorted and deduplicated.
+    pub fn watched_codes(&self) -> Vec<String> {
+        let book =
+            self.book.read().expect("Alert lock poisoned");
+        book.rules
+            .iter()
+            .map(|rule| rule.code.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Evaluates all rules against `coins`. A rule fires once its
+    /// condition holds, or as soon as its cooldown is over if it
+    /// starts to hold within it, and is re-armed once the condition
+    /// stops holding. Rules for coins missing from `coins` are
+    /// skipped.
+    pub async fn evaluate(
+        &self,
+        coins: &[Coin],
+        now: i64,
+    ) -> Vec<AlertFiring> {
+        let (firings, snapshot) =
+            self.evaluate_book(coins, now);
+        let saved = match snapshot {
+            Ok(snapshot) => self.save(snapshot).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            println!(
+                "Failed to persist alert state: {e}"
+            );
+        }
+        firings
+    }
+
+    /// The firings of [`AlertEngine::evaluate`], and a snapshot of
+    /// the book if any rule changed state.
+    fn evaluate_book(
+        &self,
+        coins: &[Coin],
+        now: i64,
+    ) -> (Vec<AlertFiring>, io::Result<Option<Snapshot>>) {
+        let mut book =
+            self.book.write().expect("Alert lock poisoned");
+        let AlertBook { rules, states, .. } = &mut *book;
+        let mut firings = vec![];
+        let mut changed = false;
+
+        for rule in rules.iter() {
+            let Some(coin) = coins.iter().find(|coin| {
+                coin.code.as_ref() == Some(&rule.code)
+            }) else {
+                continue;
+            };
+            let state =
+                states.entry(rule.id.clone()).or_default();
+            let met = rule.condition.is_met(coin);
+
+            let was_triggered = state.triggered;
+
+            if !met {
+                state.triggered = false;
+            } else if !state.triggered {
+                let cooldown_ms = i64::try_from(
+                    rule.cooldown_secs.saturating_mul(1000),
+                )
+                .unwrap_or(i64::MAX);
+                let cooled_down = state
+                    .last_fired_at
+                    .map_or(true, |last| {
+                        now.saturating_sub(last) >= cooldown_ms
+                    });
+                // Left untriggered during the cooldown, so it
+                // fires on a later evaluation if still met.
+                if cooled_down {
+                    state.last_fired_at = Some(now);
+                    state.triggered = true;
+                    firings.push(AlertFiring {
+                        rule_id: rule.id.clone(),
+                        code: rule.code.clone(),
+                        condition: rule.condition.clone(),
+                        rate: coin.rate,
+                        delta: coin.delta.clone(),
+                        fired_at: now,
+                    });
+                }
+            }
+            changed |= state.triggered != was_triggered;
+        }
+
+        let snapshot = match changed {
+            true => self.snapshot(&mut book),
+            false => Ok(None),
+        };
+        (firings, snapshot)
+    }
+
+    pub async fn notify(&self, firing: &AlertFiring) {
+        self.notifier.notify(firing).await
+    }
+
+    /// Moves `book` to a new generation and serializes it, `None`
+    /// when rules are only kept in memory.
+    fn snapshot(
+        &self,
+        book: &mut AlertBook,
+    ) -> io::Result<Option<Snapshot>> {
+        if self.path.is_none() {
+            return Ok(None);
+        }
+        book.generation += 1;
+        Ok(Some((
+            book.generation,
+            serde_json::to_vec_pretty(book)?,
+        )))
+    }
+
+    /// Writes `snapshot` unless a newer one already was.
+    async fn save(
+        &self,
+        snapshot: Option<Snapshot>,
+    ) -> io::Result<()> {
+        let (Some(path), Some((generation, bytes))) =
+            (&self.path, snapshot)
+        else {
+            return Ok(());
+        };
+        let mut persisted = self.persisted.lock().await;
+        if *persisted >= generation {
+            return Ok(());
+        }
+        // Write to a temporary file first so a crash mid-write
+        // can't leave a truncated file behind.
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, bytes).await?;
+        tokio::fs::rename(temporary, path).await?;
+        *persisted = generation;
+        Ok(())
+    }
+}
+
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Polls `/coins/map` for every coin with an alert rule once per
+/// `interval` and notifies about the rules that fire.
+///
+/// Publishes a webhook event when Live Coin Watch starts failing
+/// and another once it recovers, rather than one per failed poll.
+pub async fn run_alert_poller(
+    state: AppState,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut provider_down = false;
+    loop {
+        ticker.tick().await;
+        let codes = state.alert_engine.watched_codes();
+        if codes.is_empty() {
+            continue;
+        }
+
+        let coins = state
+            .api_client
+            .post_with_priority::<Vec<Coin>, CoinWatchClient, CoinMapRequest>(
+                state.coin_watch_client.clone(),
+                "/coins/map",
+                CoinMapRequest::new(codes),
+                Priority::Critical,
+            )
+            .await;
+
+        match coins {
+            Ok((_, Json(coins))) => {
+                if provider_down {
+                    provider_down = false;
+                    state.webhooks.publish(
+                        EventKind::ProviderRecovered,
+                        json!({ "provider": PROVIDER }),
+                    );
+                }
+                for firing in state
+                    .alert_engine
+                    .evaluate(&coins, now_millis())
+                    .await
+                {
+                    state.alert_engine.notify(&firing).await;
+                }
+            }
+            Err((status, Json(message))) => {
+                println!(
+                    "Failed to poll prices for alerts: {status} {message}"
+                );
+                if !provider_down {
+                    provider_down = true;
+                    state.webhooks.publish(
+                        EventKind::ProviderOutage,
+                        json!({
+                            "provider": PROVIDER,
+                            "status": status.as_u16(),
+                            "message": message,
+                        }),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_service::coin_watch_service::models::Delta;
+
+    use super::*;
+    use crate::alerts::{
+        models::AlertCondition, notifier::LogNotifier,
+    };
+
+    fn engine() -> AlertEngine {
+        AlertEngine::new(Arc::new(LogNotifier))
+    }
+
+    fn above(price: f64) -> CreateAlertRequest {
+        CreateAlertRequest {
+            code: "btc".into(),
+            condition: AlertCondition::PriceAbove { price },
+            cooldown_secs: Some(60),
+        }
+    }
+
+    fn btc(rate: f64) -> Vec<Coin> {
+        vec![Coin::new(
+            Some("BTC".into()),
+            Some(rate),
+            None,
+            None,
+            Delta::new(None, None, None, None, None, None),
+        )]
+    }
+
+    #[tokio::test]
+    async fn add_rule_normalizes_code() {
+        let engine = engine();
+        assert_eq!(
+            engine.add_rule(above(1.0)).await.unwrap().code,
+            "BTC"
+        );
+        assert_eq!(engine.watched_codes(), vec!["BTC"]);
+    }
+
+    #[tokio::test]
+    async fn remove_rule() {
+        let engine = engine();
+        let rule = engine.add_rule(above(1.0)).await.unwrap();
+        assert!(engine.remove_rule(&rule.id).await.unwrap());
+        assert!(!engine.remove_rule(&rule.id).await.unwrap());
+        assert!(engine.rules().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fires_once_while_condition_holds() {
+        let engine = engine();
+        engine.add_rule(above(70000.0)).await.unwrap();
+
+        assert_eq!(
+            engine.evaluate(&btc(71000.0), 0).await.len(),
+            1
+        );
+        assert!(engine
+            .evaluate(&btc(72000.0), 600_000)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn re_arms_after_condition_stops_holding() {
+        let engine = engine();
+        engine.add_rule(above(70000.0)).await.unwrap();
+
+        engine.evaluate(&btc(71000.0), 0).await;
+        engine.evaluate(&btc(69000.0), 120_000).await;
+        assert_eq!(
+            engine.evaluate(&btc(71000.0), 180_000).await.len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn cooldown_suppresses_flapping() {
+        let engine = engine();
+        engine.add_rule(above(70000.0)).await.unwrap();
+
+        engine.evaluate(&btc(71000.0), 0).await;
+        engine.evaluate(&btc(69000.0), 10_000).await;
+        assert!(engine
+            .evaluate(&btc(71000.0), 20_000)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn fires_after_cooldown_if_still_met() {
+        let engine = engine();
+        engine.add_rule(above(70000.0)).await.unwrap();
+
+        engine.evaluate(&btc(71000.0), 0).await;
+        engine.evaluate(&btc(69000.0), 10_000).await;
+        assert!(engine
+            .evaluate(&btc(71000.0), 20_000)
+            .await
+            .is_empty());
+        assert_eq!(
+            engine.evaluate(&btc(71500.0), 60_000).await.len(),
+            1
+        );
+        assert!(engine
+            .evaluate(&btc(72000.0), 180_000)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn huge_cooldown_does_not_overflow() {
+        let engine = engine();
+        engine
+            .add_rule(CreateAlertRequest {
+                cooldown_secs: Some(u64::MAX),
+                ..above(70000.0)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(engine.evaluate(&btc(71000.0), 0).await.len(), 1);
+        engine.evaluate(&btc(69000.0), 1_000).await;
+        assert!(engine
+            .evaluate(&btc(71000.0), 2_000)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn state_survives_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "crypto-service-alerts-{}.json",
+            now_millis()
+        ));
+        let engine = AlertEngine::load(
+            path.clone(),
+            Arc::new(LogNotifier),
+        )
+        .unwrap();
+        engine.add_rule(above(70000.0)).await.unwrap();
+        engine.evaluate(&btc(71000.0), 0).await;
+
+        let restarted = AlertEngine::load(
+            path.clone(),
+            Arc::new(LogNotifier),
+        )
+        .unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(restarted.rules().len(), 1);
+        assert!(restarted.rules()[0].state.triggered);
+        assert!(restarted
+            .evaluate(&btc(72000.0), 1_000)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn older_snapshot_does_not_replace_newer() {
+        let path = std::env::temp_dir().join(format!(
+            "crypto-service-alerts-order-{}.json",
+            now_millis()
+        ));
+        let engine = AlertEngine::load(
+            path.clone(),
+            Arc::new(LogNotifier),
+        )
+        .unwrap();
+        let (older, newer) = {
+            let mut book = engine.book.write().unwrap();
+            book.next_id = 1;
+            let older = engine.snapshot(&mut book).unwrap();
+            book.next_id = 2;
+            (older, engine.snapshot(&mut book).unwrap())
+        };
+        engine.save(newer).await.unwrap();
+        engine.save(older).await.unwrap();
+
+        let restarted = AlertEngine::load(
+            path.clone(),
+            Arc::new(LogNotifier),
+        )
+        .unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(restarted.book.read().unwrap().next_id, 2);
+    }
+}
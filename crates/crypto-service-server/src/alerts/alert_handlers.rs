@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use super::{
+    alert_engine::MAX_COOLDOWN_SECS,
+    models::{
+        AlertCondition, AlertRule, AlertWithState,
+        CreateAlertRequest,
+    },
+};
+use crate::state::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/v1/alerts",
+    tag = "alerts",
+    responses(
+        (status = 200, description = "Alert rules with their state", body = Vec<AlertWithState>),
+    )
+)]
+pub async fn list_alerts(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<AlertWithState>>) {
+    (StatusCode::OK, Json(state.alert_engine.rules()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/alerts",
+    tag = "alerts",
+    request_body = CreateAlertRequest,
+    responses(
+        (status = 201, description = "The created rule", body = AlertRule),
+        (status = 422, description = "Blank code, non-finite threshold or a cooldown over a year", body = String),
+    )
+)]
+pub async fn create_alert(
+    State(state): State<AppState>,
+    Json(body): Json<CreateAlertRequest>,
+) -> Result<
+    (StatusCode, Json<AlertRule>),
+    (StatusCode, Json<String>),
+> {
+    let threshold = match body.condition {
+        AlertCondition::PriceAbove { price }
+        | AlertCondition::PriceBelow { price } => price,
+        AlertCondition::PercentChange {
+            percent, ..
+        } => percent,
+    };
+    if body.code.trim().is_empty() || !threshold.is_finite()
+    {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("An alert needs a coin code and a finite threshold".into()),
+        ));
+    }
+    if body
+        .cooldown_secs
+        .is_some_and(|secs| secs > MAX_COOLDOWN_SECS)
+    {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(format!(
+                "A cooldown can be at most {MAX_COOLDOWN_SECS} seconds"
+            )),
+        ));
+    }
+
+    state
+        .alert_engine
+        .add_rule(body)
+        .await
+        .map(|rule| (StatusCode::CREATED, Json(rule)))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(e.to_string()),
+            )
+        })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/alerts/{id}",
+    tag = "alerts",
+    params(("id" = String, Path, description = "Alert rule id")),
+    responses(
+        (status = 204, description = "The rule was removed"),
+        (status = 404, description = "No rule with that id", body = String),
+    )
+)]
+pub async fn delete_alert(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<String>)> {
+    match state.alert_engine.remove_rule(&id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(format!("No alert with id '{id}'")),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(e.to_string()),
+        )),
+    }
+}
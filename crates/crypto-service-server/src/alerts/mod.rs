@@ -0,0 +1,4 @@
+pub mod alert_engine;
+pub mod alert_handlers;
+pub mod models;
+pub mod notifier;
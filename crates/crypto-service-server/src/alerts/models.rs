@@ -0,0 +1,200 @@
+use crypto_service::coin_watch_service::models::{
+    Coin, Delta,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Window of a Live Coin Watch [`Delta`] field.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeltaWindow {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl DeltaWindow {
+    /// Change over the window in percent. Live Coin Watch reports
+    /// deltas as ratios, e.g. `1.05` for a 5% increase.
+    pub fn percent_change(
+        &self,
+        delta: &Delta,
+    ) -> Option<f64> {
+        let ratio = match self {
+            DeltaWindow::Hour => delta.hour,
+            DeltaWindow::Day => delta.day,
+            DeltaWindow::Week => delta.week,
+            DeltaWindow::Month => delta.month,
+            DeltaWindow::Quarter => delta.quarter,
+            DeltaWindow::Year => delta.year,
+        }?;
+        Some((ratio - 1.0) * 100.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    PriceAbove {
+        price: f64,
+    },
+    PriceBelow {
+        price: f64,
+    },
+    /// Moved by at least `percent` over `window`. A negative
+    /// `percent` watches for drops, e.g. `-5` for "-5% in 1h".
+    PercentChange {
+        window: DeltaWindow,
+        percent: f64,
+    },
+}
+
+impl AlertCondition {
+    pub fn is_met(&self, coin: &Coin) -> bool {
+        match self {
+            AlertCondition::PriceAbove { price } => {
+                coin.rate.is_some_and(|rate| rate > *price)
+            }
+            AlertCondition::PriceBelow { price } => {
+                coin.rate.is_some_and(|rate| rate < *price)
+            }
+            AlertCondition::PercentChange {
+                window,
+                percent,
+            } => window
+                .percent_change(&coin.delta)
+                .is_some_and(|change| {
+                    match *percent < 0.0 {
+                        true => change <= *percent,
+                        false => change >= *percent,
+                    }
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct AlertRule {
+    pub id: String,
+    pub code: String,
+    pub condition: AlertCondition,
+    /// Minimum time between two firings of this rule.
+    pub cooldown_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CreateAlertRequest {
+    pub code: String,
+    pub condition: AlertCondition,
+    pub cooldown_secs: Option<u64>,
+}
+
+/// Whether a rule fired and its condition has held since, so that
+/// it fires once while the condition holds instead of on every
+/// poll.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct AlertState {
+    pub triggered: bool,
+    pub last_fired_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct AlertWithState {
+    #[serde(flatten)]
+    pub rule: AlertRule,
+    pub state: AlertState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertFiring {
+    pub rule_id: String,
+    pub code: String,
+    pub condition: AlertCondition,
+    pub rate: Option<f64>,
+    pub delta: Delta,
+    /// Unix milliseconds.
+    pub fired_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(rate: f64, hour: f64) -> Coin {
+        Coin::new(
+            Some("BTC".into()),
+            Some(rate),
+            None,
+            None,
+            Delta::new(
+                Some(hour),
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+        )
+    }
+
+    #[test]
+    fn price_above() {
+        let condition =
+            AlertCondition::PriceAbove { price: 70000.0 };
+        assert!(condition.is_met(&coin(70001.0, 1.0)));
+        assert!(!condition.is_met(&coin(69999.0, 1.0)));
+    }
+
+    #[test]
+    fn price_below() {
+        let condition =
+            AlertCondition::PriceBelow { price: 70000.0 };
+        assert!(condition.is_met(&coin(69999.0, 1.0)));
+    }
+
+    #[test]
+    fn percent_drop() {
+        let condition = AlertCondition::PercentChange {
+            window: DeltaWindow::Hour,
+            percent: -5.0,
+        };
+        assert!(condition.is_met(&coin(1.0, 0.94)));
+        assert!(!condition.is_met(&coin(1.0, 0.96)));
+        assert!(!condition.is_met(&coin(1.0, 1.06)));
+    }
+
+    #[test]
+    fn percent_rise() {
+        let condition = AlertCondition::PercentChange {
+            window: DeltaWindow::Hour,
+            percent: 5.0,
+        };
+        assert!(condition.is_met(&coin(1.0, 1.06)));
+    }
+
+    #[test]
+    fn missing_delta_never_met() {
+        let condition = AlertCondition::PercentChange {
+            window: DeltaWindow::Day,
+            percent: 5.0,
+        };
+        assert!(!condition.is_met(&coin(1.0, 1.5)));
+    }
+
+    #[test]
+    fn deserialize_condition() {
+        assert_eq!(
+            serde_json::from_str::<AlertCondition>(
+                r#"{"type":"percent_change","window":"hour","percent":-5}"#
+            )
+            .unwrap(),
+            AlertCondition::PercentChange {
+                window: DeltaWindow::Hour,
+                percent: -5.0
+            }
+        );
+    }
+}
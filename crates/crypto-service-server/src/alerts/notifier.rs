@@ -0,0 +1,41 @@
+use core::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::models::AlertFiring;
+
+/// Delivers alert firings somewhere, e.g. a log or a webhook.
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync {
+    async fn notify(&self, firing: &AlertFiring);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, firing: &AlertFiring) {
+        println!(
+            "Alert {} fired for {}: {:?} at rate {:?}",
+            firing.rule_id,
+            firing.code,
+            firing.condition,
+            firing.rate
+        );
+    }
+}
+
+/// Hands every firing to each of its notifiers in turn.
+#[derive(Debug, Clone, Default)]
+pub struct FanOutNotifier(pub Vec<Arc<dyn Notifier>>);
+
+#[async_trait]
+impl Notifier for FanOutNotifier {
+    async fn notify(&self, firing: &AlertFiring) {
+        for notifier in &self.0 {
+            notifier.notify(firing).await;
+        }
+    }
+}
@@ -1,82 +1,490 @@
-use std::{collections::HashMap, env};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use crypto_service::{
+    alphavantage_service::{
+        alpha_vantage_client::AlphaVantageClient,
+        models::{
+            AlphaVantageResponse, CurrencyExchangeRate,
+            CurrencyExchangeRateRequest,
+            CurrencyExchangeRateResponse,
+            DailySeriesRequest,
+            DigitalCurrencyDailyRequest,
+            DigitalCurrencySeries, FxDailyRequest,
+            FxSeries, GlobalQuoteRequest,
+            GlobalQuoteResponse, IntradaySeriesRequest,
+            StockQuote, StockSeries, SymbolMatch,
+            SymbolSearchRequest, SymbolSearchResponse,
+            TimeSeriesResponse, TopAndBottomTrades,
+            TopGainersLosersRequest,
+        },
+    },
+    client_trait::QueryItems,
+};
+use serde::{de::DeserializeOwned, Serialize};
 
-use axum::extract::{self, Query, State};
-use crypto_service::{alphavantage_service::models::TopAndBottomTrades, client_trait::QueryItems};
-use serde::{Deserialize, Serialize};
-
-use crate::state::AppState;
+use crate::{
+    api_client::{api_client::ApiClient, quota::Priority},
+    export::export_format::ExportFormat,
+    state::AppState,
+};
 
+/// Top gainers, losers and most actively traded US stocks, from
+/// the snapshot the trades feed keeps. Only fetched here before the
+/// feed's first refresh came through.
+#[utoipa::path(
+    get,
+    path = "/v1/stocks",
+    tag = "stocks",
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = TopAndBottomTrades),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
 pub async fn get_top_gainers_and_losers(
-    State(state): extract::State<AppState>,
-    Query(params): Query<GainersLosersParams>,
-) -> Result<
-    (axum::http::StatusCode, axum::Json<TopAndBottomTrades>),
-    (axum::http::StatusCode, axum::Json<String>),
-> {
-state
-.api_client
-.get(state.alpha_client, "", params)
-.await
+    State(state): State<AppState>,
+    format: ExportFormat,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    if let Some(trades) = state.trades_feed.latest() {
+        return Ok(format
+            .respond(TopAndBottomTrades::clone(&trades)));
+    }
+    let trades = fetch::<_, TopAndBottomTrades>(
+        &state.api_client,
+        &state.alpha_client,
+        TopGainersLosersRequest::default(),
+    )
+    .await?;
+    state.trades_feed.publish(trades.clone());
+    Ok(format.respond(trades))
+}
+
+/// Latest trading day of a stock such as `IBM`.
+#[utoipa::path(
+    get,
+    path = "/v1/stocks/quote",
+    tag = "stocks",
+    params(GlobalQuoteRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = StockQuote),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 404, description = "Unknown symbol", body = String),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn get_stock_quote(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<GlobalQuoteRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let symbol = request.symbol.to_uppercase();
+    fetch::<_, GlobalQuoteResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await?
+    .into_quote()
+    .map(|quote: StockQuote| format.respond(quote))
+    .ok_or((
+        StatusCode::NOT_FOUND,
+        Json(format!("No stock {symbol}")),
+    ))
+}
+
+/// Daily bars of a stock, oldest first.
+#[utoipa::path(
+    get,
+    path = "/v1/stocks/daily",
+    tag = "stocks",
+    params(DailySeriesRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = StockSeries),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 404, description = "Unknown symbol", body = String),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn get_stock_daily(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<DailySeriesRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch::<_, TimeSeriesResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await
+    .map(|series| {
+        let series: StockSeries = series.into_series();
+        format.respond(series)
+    })
+}
+
+/// Intraday bars of a stock, oldest first.
+#[utoipa::path(
+    get,
+    path = "/v1/stocks/intraday",
+    tag = "stocks",
+    params(IntradaySeriesRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = StockSeries),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 404, description = "Unknown symbol", body = String),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn get_stock_intraday(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<IntradaySeriesRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch::<_, TimeSeriesResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await
+    .map(|series| {
+        let series: StockSeries = series.into_series();
+        format.respond(series)
+    })
+}
 
+/// Tickers matching `keywords`, best match first.
+#[utoipa::path(
+    get,
+    path = "/v1/stocks/search",
+    tag = "stocks",
+    params(SymbolSearchRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<SymbolMatch>),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn search_stock_symbols(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<SymbolSearchRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch::<_, SymbolSearchResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await
+    .map(|search| {
+        let matches: Vec<SymbolMatch> =
+            search.into_matches();
+        format.respond(matches)
+    })
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct GainersLosersParams {
-    pub function: String,
-    pub key: String,
+/// Rate between two physical or digital currencies, such as `BTC`
+/// to `EUR`.
+#[utoipa::path(
+    get,
+    path = "/v1/alphavantage/exchange-rate",
+    tag = "alphavantage",
+    params(CurrencyExchangeRateRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = CurrencyExchangeRate),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 404, description = "Unknown currency", body = String),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn get_currency_exchange_rate(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<CurrencyExchangeRateRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let pair = format!(
+        "{} to {}",
+        request.from_currency.to_uppercase(),
+        request.to_currency.to_uppercase()
+    );
+    fetch::<_, CurrencyExchangeRateResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await?
+    .into_rate()
+    .map(|rate: CurrencyExchangeRate| format.respond(rate))
+    .ok_or((
+        StatusCode::NOT_FOUND,
+        Json(format!("No rate from {pair}")),
+    ))
 }
 
-impl QueryItems for GainersLosersParams {
-    type Query = String;
+/// Daily bars of a currency pair such as `EUR` to `USD`, oldest
+/// first.
+#[utoipa::path(
+    get,
+    path = "/v1/alphavantage/fx/daily",
+    tag = "alphavantage",
+    params(FxDailyRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = FxSeries),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 404, description = "Unknown currency", body = String),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn get_fx_daily(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<FxDailyRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch::<_, TimeSeriesResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await
+    .map(|series| {
+        let series: FxSeries = series.into_fx_series();
+        format.respond(series)
+    })
+}
+
+/// Daily bars of a coin priced in a physical currency, oldest
+/// first, to cross-check the coin sources with.
+#[utoipa::path(
+    get,
+    path = "/v1/alphavantage/digital-currency/daily",
+    tag = "alphavantage",
+    params(DigitalCurrencyDailyRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = DigitalCurrencySeries),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 404, description = "Unknown coin or market", body = String),
+        (status = 429, description = "Alpha Vantage calls are used up", body = String),
+        (status = 503, description = "No Alpha Vantage key configured", body = String),
+    )
+)]
+pub async fn get_digital_currency_daily(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<DigitalCurrencyDailyRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch::<_, TimeSeriesResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        request,
+    )
+    .await
+    .map(|series| {
+        let series: DigitalCurrencySeries =
+            series.into_digital_currency_series();
+        format.respond(series)
+    })
+}
+
+/// Calls without a key are refused here, like CoinAPI's. Alpha
+/// Vantage reports errors in a `200 OK` body, those become a `404`
+/// for invalid calls and a `429` once the key's calls are used up.
+pub async fn fetch<T, U>(
+    api_client: &ApiClient,
+    client: &AlphaVantageClient,
+    query: T,
+) -> Result<U, (StatusCode, Json<String>)>
+where
+    T: QueryItems<Query = String>
+        + std::fmt::Debug
+        + Serialize,
+    U: DeserializeOwned,
+{
+    fetch_with_priority(
+        api_client,
+        client,
+        query,
+        Priority::Normal,
+    )
+    .await
+}
+
+/// Like [`fetch`], but refused once the daily budget is too low
+/// for `priority`.
+pub async fn fetch_with_priority<T, U>(
+    api_client: &ApiClient,
+    client: &AlphaVantageClient,
+    query: T,
+    priority: Priority,
+) -> Result<U, (StatusCode, Json<String>)>
+where
+    T: QueryItems<Query = String>
+        + std::fmt::Debug
+        + Serialize,
+    U: DeserializeOwned,
+{
+    if !client.has_key() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(
+                "No Alpha Vantage key configured, set ALPHA_VANTAGE_KEY"
+                    .into(),
+            ),
+        ));
+    }
+    let (_, Json(response)) = api_client
+        .get_with_priority::<_, AlphaVantageResponse<U>, _>(
+            client.clone(),
+            "",
+            client.keyed(query),
+            priority,
+        )
+        .await?;
+    match response {
+        AlphaVantageResponse::Data(data) => Ok(data),
+        AlphaVantageResponse::Error { message } => {
+            Err((StatusCode::NOT_FOUND, Json(message)))
+        }
+        AlphaVantageResponse::Limited { message } => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(message),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{routing::get, Router};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        api_client::quota::BudgetSource, test_support,
+    };
+
+    /// Local stand-in for Alpha Vantage that answers the way it
+    /// does, with errors in a `200 OK`.
+    async fn client(key: &str) -> AlphaVantageClient {
+        let app = Router::new().route(
+            "/",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                if query["apikey"] == "spent" {
+                    return Json(json!({
+                        "Information": "Our standard API rate limit is 25 requests per day."
+                    }));
+                }
+                match query["symbol"].as_str() {
+                    "IBM" => Json(json!({
+                        "Global Quote": {
+                            "01. symbol": "IBM",
+                            "05. price": "190.9600",
+                            "10. change percent": "0.3428%"
+                        }
+                    })),
+                    "NOPE" => Json(json!({ "Global Quote": {} })),
+                    _ => Json(json!({
+                        "Error Message": "Invalid API call."
+                    })),
+                }
+            }),
+        );
+        let base_url = test_support::serve(app).await;
+
+        let mut client =
+            AlphaVantageClient::new_with_key(key.into());
+        client.base_url = base_url;
+        client
+    }
+
+    async fn quote(
+        key: &str,
+        symbol: &str,
+    ) -> Result<
+        GlobalQuoteResponse,
+        (StatusCode, Json<String>),
+    > {
+        fetch(
+            &ApiClient::new(),
+            &client(key).await,
+            GlobalQuoteRequest::new(symbol.into()),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn quote_of_known_symbol() {
+        let ibm = quote("TEST", "ibm")
+            .await
+            .unwrap()
+            .into_quote()
+            .unwrap();
+        assert_eq!(ibm.price, Some(190.96));
+        assert_eq!(ibm.change_percent, Some(0.3428));
+        assert!(quote("TEST", "nope")
+            .await
+            .unwrap()
+            .into_quote()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn errors_in_the_body_become_statuses() {
+        let (status, _) =
+            quote("TEST", "XYZ").await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, Json(message)) =
+            quote("spent", "IBM").await.unwrap_err();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(message.contains("25 requests"));
+
+        let (status, _) =
+            quote("", "IBM").await.unwrap_err();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn background_calls_leave_the_reserve_to_routes()
+    {
+        let api_client = ApiClient::new();
+        let client = client("TEST").await;
+        let now = crate::alerts::alert_engine::now_millis();
+        api_client.quota.track(
+            "Alpha Vantage",
+            &client.base_url,
+            25,
+            BudgetSource::Local,
+            now,
+        );
+        api_client.quota.sync(&client.base_url, 4, 25, now);
 
-    fn get_all_queries(&self) -> std::collections::HashMap<&str, Self::Query> {
-        let key = env::var("ALPHA_VANTAGE_KEY").expect("Failed to load Alpha key");
+        let (status, _) =
+            fetch_with_priority::<_, GlobalQuoteResponse>(
+                &api_client,
+                &client,
+                GlobalQuoteRequest::new("IBM".into()),
+                Priority::Background,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
 
-        let mut hash = HashMap::new();
-        hash.insert("function", "TOP_GAINERS_LOSERS".to_string());
-        hash.insert("apikey", key);
-        hash
+        assert!(fetch::<_, GlobalQuoteResponse>(
+            &api_client,
+            &client,
+            GlobalQuoteRequest::new("IBM".into()),
+        )
+        .await
+        .is_ok());
     }
 }
-// pub async fn get_asset_icons(
-//     State(state): extract::State<AppState>,
-//     Query(params): Query<AssetIconsParams>,
-// ) -> Result<
-//     (axum::http::StatusCode, axum::Json<Vec<AssetIcons>>),
-//     (axum::http::StatusCode, axum::Json<String>),
-// > {
-//     state
-//         .api_client
-//         .get(state.coinapi_client, "assets/icons/", params)
-//         .await
-// }
-
-// pub async fn get_symbols(
-//     State(state): extract::State<AppState>,
-//     Query(params): Query<SymbolsParams>,
-// ) -> Result<
-//     (axum::http::StatusCode, axum::Json<Vec<SymbolsResponse>>),
-//     (axum::http::StatusCode, axum::Json<String>),
-// > {
-//     state
-//         .api_client
-//         .get(state.coinapi_client, "symbols", params)
-//         .await
-// }
-
-// #[uniffi::export]
-// pub async fn get_symbols_binding(params: SymbolsParams) -> Vec<SymbolsResponse> {
-//     let binance_client: BinanceClient = BinanceClient::new();
-//     let coinapi_client: CoinApiClient = CoinApiClient::new();
-//     let api_client = ApiClient::new();
-
-//     let state = AppState::new(binance_client, coinapi_client, api_client);
-
-//     get_symbols(
-//         axum::extract::State(state),
-//         Query::from(axum::extract::Query(params)),
-//     )
-//     .await
-//     .map(|x| x.1 .0)
-//     .unwrap()
-// }
@@ -1,2 +1,2 @@
 pub mod alpha_handler;
-pub mod alpha_client;
\ No newline at end of file
+pub mod trades_feed;
@@ -0,0 +1,258 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use crypto_service::alphavantage_service::models::{
+    TopAndBottomTrades, TopGainersLosersRequest,
+};
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::watch;
+
+use super::alpha_handler::fetch_with_priority;
+use crate::{api_client::quota::Priority, state::AppState};
+
+/// Holds the latest `TOP_GAINERS_LOSERS` snapshot for every
+/// `/v1/stocks/stream` subscriber.
+#[derive(Debug, Clone)]
+pub struct TradesFeed {
+    latest: watch::Sender<Option<Arc<TopAndBottomTrades>>>,
+}
+
+impl Default for TradesFeed {
+    fn default() -> Self {
+        Self {
+            latest: watch::channel(None).0,
+        }
+    }
+}
+
+impl TradesFeed {
+    pub fn latest(
+        &self,
+    ) -> Option<Arc<TopAndBottomTrades>> {
+        self.latest.borrow().clone()
+    }
+
+    pub fn subscribe(
+        &self,
+    ) -> watch::Receiver<Option<Arc<TopAndBottomTrades>>>
+    {
+        self.latest.subscribe()
+    }
+
+    /// Replaces the snapshot if its `last_updated` differs,
+    /// returning whether subscribers were notified.
+    pub fn publish(
+        &self,
+        trades: TopAndBottomTrades,
+    ) -> bool {
+        self.latest.send_if_modified(|latest| {
+            if latest.as_ref().is_some_and(|latest| {
+                latest.last_updated == trades.last_updated
+            }) {
+                return false;
+            }
+            *latest = Some(Arc::new(trades));
+            true
+        })
+    }
+}
+
+/// Snapshots from `receiver`, starting with the current one unless
+/// its `last_updated` is `last_event_id`.
+pub fn snapshots(
+    receiver: watch::Receiver<
+        Option<Arc<TopAndBottomTrades>>,
+    >,
+    last_event_id: Option<String>,
+) -> impl Stream<Item = Arc<TopAndBottomTrades>> {
+    stream::unfold(
+        (receiver, last_event_id),
+        |(mut receiver, mut last_sent)| async move {
+            loop {
+                let latest =
+                    receiver.borrow_and_update().clone();
+                if let Some(trades) = latest {
+                    if last_sent.as_ref()
+                        != Some(&trades.last_updated)
+                    {
+                        last_sent = Some(
+                            trades.last_updated.clone(),
+                        );
+                        return Some((
+                            trades,
+                            (receiver, last_sent),
+                        ));
+                    }
+                }
+                receiver.changed().await.ok()?;
+            }
+        },
+    )
+}
+
+/// Pushes a `top_gainers_losers` event with the event id set to
+/// `last_updated` whenever Alpha Vantage publishes a new snapshot.
+/// Reconnecting clients that send `Last-Event-ID` only get the
+/// snapshot again if it changed in the meantime.
+pub async fn stream_top_gainers_and_losers(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|id| id.to_str().ok())
+        .map(str::to_string);
+
+    let events = snapshots(
+        state.trades_feed.subscribe(),
+        last_event_id,
+    )
+    .map(|trades| {
+        Ok(Event::default()
+            .id(trades.last_updated.clone())
+            .event("top_gainers_losers")
+            .json_data(&*trades)
+            .expect("Trades serialize to JSON"))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Fetches `TOP_GAINERS_LOSERS` once per `interval` and publishes
+/// it to the [`TradesFeed`], regardless of how many clients are
+/// subscribed. It runs at background priority, so it leaves part of
+/// the small Alpha Vantage budget to the routes clients call.
+pub async fn run_trades_feed(
+    state: AppState,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let trades =
+            fetch_with_priority::<_, TopAndBottomTrades>(
+                &state.api_client,
+                &state.alpha_client,
+                TopGainersLosersRequest::default(),
+                Priority::Background,
+            )
+            .await;
+
+        match trades {
+            Ok(trades) => {
+                state.trades_feed.publish(trades);
+            }
+            Err((status, Json(message))) => println!(
+                "Failed to refresh top gainers and losers: {status} {message}"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trades(last_updated: &str) -> TopAndBottomTrades {
+        TopAndBottomTrades {
+            metadata: String::new(),
+            last_updated: last_updated.into(),
+            top_gainers: vec![],
+            top_losers: vec![],
+            most_actively_traded: vec![],
+        }
+    }
+
+    async fn next(
+        stream: &mut (impl Stream<Item = Arc<TopAndBottomTrades>>
+                  + Unpin),
+    ) -> Option<String> {
+        tokio::time::timeout(
+            Duration::from_millis(50),
+            stream.next(),
+        )
+        .await
+        .ok()
+        .flatten()
+        .map(|trades| trades.last_updated.clone())
+    }
+
+    #[test]
+    fn publish_skips_unchanged_snapshot() {
+        let feed = TradesFeed::default();
+        assert!(feed.publish(trades("2024-03-28 16:15:59")));
+        assert!(
+            !feed.publish(trades("2024-03-28 16:15:59"))
+        );
+        assert!(feed.publish(trades("2024-03-29 16:15:59")));
+    }
+
+    #[tokio::test]
+    async fn stream_starts_with_current_snapshot() {
+        let feed = TradesFeed::default();
+        feed.publish(trades("a"));
+        let mut stream =
+            Box::pin(snapshots(feed.subscribe(), None));
+
+        assert_eq!(
+            next(&mut stream).await.as_deref(),
+            Some("a")
+        );
+        assert_eq!(next(&mut stream).await, None);
+
+        feed.publish(trades("b"));
+        assert_eq!(
+            next(&mut stream).await.as_deref(),
+            Some("b")
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_waits_for_first_snapshot() {
+        let feed = TradesFeed::default();
+        let mut stream =
+            Box::pin(snapshots(feed.subscribe(), None));
+        assert_eq!(next(&mut stream).await, None);
+
+        feed.publish(trades("a"));
+        assert_eq!(
+            next(&mut stream).await.as_deref(),
+            Some("a")
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_skips_already_seen_snapshot() {
+        let feed = TradesFeed::default();
+        feed.publish(trades("a"));
+        let mut stream = Box::pin(snapshots(
+            feed.subscribe(),
+            Some("a".into()),
+        ));
+        assert_eq!(next(&mut stream).await, None);
+
+        feed.publish(trades("b"));
+        assert_eq!(
+            next(&mut stream).await.as_deref(),
+            Some("b")
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_sends_newer_snapshot() {
+        let feed = TradesFeed::default();
+        feed.publish(trades("b"));
+        let mut stream = Box::pin(snapshots(
+            feed.subscribe(),
+            Some("a".into()),
+        ));
+        assert_eq!(
+            next(&mut stream).await.as_deref(),
+            Some("b")
+        );
+    }
+}
@@ -1,8 +1,13 @@
 use core::fmt::Debug;
 
+use super::quota::QuotaTracker;
+
 #[derive(Debug, Clone)]
 pub struct ApiClient {
     pub http_client: reqwest::Client,
+    /// Shared by all clones, so every caller draws on the same
+    /// daily budgets.
+    pub quota: QuotaTracker,
 }
 
 impl Default for ApiClient {
@@ -15,6 +20,7 @@ impl ApiClient {
     pub fn new() -> Self {
         Self {
             http_client: reqwest::Client::new(),
+            quota: QuotaTracker::default(),
         }
     }
 }
@@ -25,6 +31,6 @@ mod tests {
 
     #[test]
     fn new_api_client() {
-        let api_client = ApiClient::new();
+        let _api_client = ApiClient::new();
     }
 }
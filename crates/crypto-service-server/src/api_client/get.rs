@@ -1,5 +1,6 @@
 
-use super::api_client::ApiClient;
+use super::{api_client::ApiClient, quota::Priority};
+use crate::alerts::alert_engine::now_millis;
 use axum::http::StatusCode;
 use crypto_service::client_trait::{Client, QueryItems};
 use reqwest::{Request, Response};
@@ -18,13 +19,45 @@ impl ApiClient {
         T: QueryItems + std::fmt::Debug + Serialize,
         U: DeserializeOwned,
     {
+        self.get_with_priority(client_source, path, query, Priority::Normal)
+            .await
+    }
+
+    /// Like [`ApiClient::get`], but refused with
+    /// `429 Too Many Requests` when the upstream's daily budget is
+    /// too low for `priority`.
+    pub async fn get_with_priority<T, U, C: Client>(
+        &self,
+        client_source: C,
+        path: &str,
+        query: T,
+        priority: Priority,
+    ) -> Result<(StatusCode, axum::Json<U>), (StatusCode, axum::Json<String>)>
+    where
+        <T as QueryItems>::Query: Serialize,
+        T: QueryItems + std::fmt::Debug + Serialize,
+        U: DeserializeOwned,
+    {
+        let base_url = client_source.get_base_url();
+        self.permit(&base_url, priority)?;
         let request = self.counstruct_request(client_source, path, query)?;
 
         let response_bytes = self.execute_request(request).await?;
+        self.quota.record_call(&base_url, now_millis());
 
         self.deserialize_response(response_bytes).await
     }
 
+    pub(crate) fn permit(
+        &self,
+        base_url: &str,
+        priority: Priority,
+    ) -> Result<(), (StatusCode, axum::Json<String>)> {
+        self.quota
+            .permit(base_url, priority, now_millis())
+            .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, axum::Json(e.to_string())))
+    }
+
     pub async fn deserialize_response<U: DeserializeOwned>(
         &self,
         response_bytes: Response,
@@ -40,7 +73,6 @@ impl ApiClient {
         &self,
         request: Request,
     ) -> Result<Response, (StatusCode, axum::Json<String>)> {
-        println!("{:#?}", request);
         self.http_client
             .execute(request)
             .await
@@ -59,11 +91,12 @@ impl ApiClient {
     {
         let mut url = client_source.get_base_url();
         url.push_str(path);
+        let headers = Headers::try_from(client_source.get_headers())?;
 
         self.http_client
             .get(url)
-            .headers(Headers::from(client_source.get_headers()).0)
-            .query(&query)
+            .headers(headers.0)
+            .query(&query.get_all_queries())
             .build()
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, axum::Json(e.to_string())))
     }
@@ -71,75 +104,68 @@ impl ApiClient {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use axum::{extract::Query, routing::get, Router};
+    use crypto_service::binance_service::{
+        binance_client::BinanceClient,
+        models::{DepthRequest, OrderBook},
+    };
+    use reqwest::Method;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support;
 
-    // #[test]
-    // fn construct_request() {
-    //     let request = ApiClient::placeholder_binance_client_request();
-    //     assert_eq!(request.method(), Method::GET);
-    //     assert_eq!(request.headers().len(), 2);
-    //     assert_eq!(request.headers().get("x-mbx-apikey").unwrap(), "Bearer Key");
-    //     assert_eq!(
-    //         request.headers().get("content-type").unwrap(),
-    //         "application/x-www-form-urlencoded"
-    //     );
-    //     assert_eq!(request.url().path().to_string(), "/api/v3/path");
-    //     assert_eq!(request.url().query().unwrap(), "symbol=ETHBTC&limit=10");
-    // }
-
-    // #[tokio::test]
-    // #[cfg(not(tarpaulin))]
-    // async fn execute_request() {
-    //     let api_client = ApiClient::new();
-    //     let request = ApiClient::placeholder_binance_client_request();
-    //     let response = ApiClient::execute_request(&api_client, request)
-    //         .await
-    //         .expect("Failed 2");
-    //     assert_eq!(response.status(), 404);
-    // }
-
-    // #[tokio::test]
-    // async fn deserialize_response() {
-    //     let api_client = ApiClient::new();
-    //     let request = ApiClient::placeholder_binance_client_request();
-    //     let response = ApiClient::execute_request(&api_client, request)
-    //         .await
-    //         .expect("Failed");
-    //       let response = r#"
-    //     {
-    //         "asks": [
-    //           [
-    //             "0.05916000",
-    //             "8.77050000"
-    //           ]
-    //         ],
-    //         "bids": [
-    //           [
-    //             "0.05915000",
-    //             "19.49480000"
-    //           ]
-    //         ],
-    //         "lastUpdateId": 7038480085
-    //       }"#;
-    //     let deserialized_response = api_client.deserialize_response::<OrderBook>(response).await;
-    //     assert_eq!(deserialized_response.is_ok(), true);
-    // }
-
-    // #[test]
-    // fn get_orderbook() {
-    //     std::env::set_var("BINANCE_API_KEY", "TEST");
-
-    //     let api_client = ApiClient::new();
-    //     let binance_client = BinanceClient::new();
-    //     let orderbook_request = OrderBookRequest {
-    //         symbol: serde_json::Value::String("ETHBTC".to_string()),
-    //         limit: Some(serde_json::Value::Number(Number::from_str("10").unwrap())),
-    //     };
-
-    //     let response = api_client
-    //         .get::<OrderBookRequest, OrderBook, binance_client::BinanceClient>(
-    //             binance_client,
-    //             "depth",
-    //             orderbook_request,
-    //         );
-    // }
+    #[test]
+    fn construct_request() {
+        let request = ApiClient::new()
+            .counstruct_request(
+                BinanceClient::new_with_key("Key".into()),
+                "/depth",
+                DepthRequest::new("ethbtc".into(), Some(10)),
+            )
+            .unwrap();
+        assert_eq!(request.method(), Method::GET);
+        assert_eq!(request.headers().get("x-mbx-apikey").unwrap(), "Key");
+        assert_eq!(
+            request.headers().get("content-type").unwrap(),
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(request.url().path(), "/api/v3/depth");
+        let query: HashMap<_, _> =
+            request.url().query_pairs().into_owned().collect();
+        assert_eq!(query["symbol"], "ETHBTC");
+        assert_eq!(query["limit"], "10");
+    }
+
+    #[tokio::test]
+    async fn get_order_book() {
+        let app = Router::new().route(
+            "/depth",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                axum::Json(json!({
+                    "lastUpdateId": 7038480085u64,
+                    "asks": [["0.05916000", query["limit"]]],
+                    "bids": [["0.05915000", "19.49480000"]]
+                }))
+            }),
+        );
+        let base_url = test_support::serve(app).await;
+
+        let mut binance_client = BinanceClient::new_with_key(String::new());
+        binance_client.base_url = base_url;
+        let (status, axum::Json(order_book)) = ApiClient::new()
+            .get::<DepthRequest, OrderBook, BinanceClient>(
+                binance_client,
+                "/depth",
+                DepthRequest::new("ETHBTC".into(), Some(5)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(order_book.last_update_id, 7038480085);
+        assert_eq!(order_book.asks[0].quantity, 5.0);
+        assert_eq!(order_book.bids[0].price, 0.05915);
+    }
 }
@@ -1,3 +1,5 @@
+#[allow(clippy::module_inception)]
 pub mod api_client;
 pub mod get;
 pub mod post;
+pub mod quota;
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, str::FromStr};
 
-use super::api_client::{ApiClient};
+use super::{api_client::ApiClient, quota::Priority};
+use crate::alerts::alert_engine::now_millis;
 use axum::http::StatusCode;
 use crypto_service::client_trait::Client;
 use reqwest::{
@@ -19,6 +20,59 @@ impl ApiClient {
         (StatusCode, axum::Json<U>),
         (StatusCode, axum::Json<String>),
     >
+    where
+        U: DeserializeOwned,
+    {
+        self.post_with_priority(
+            client_source,
+            path,
+            body,
+            Priority::Normal,
+        )
+        .await
+    }
+
+    /// Like [`ApiClient::post`], but refused with
+    /// `429 Too Many Requests` when the upstream's daily budget is
+    /// too low for `priority`.
+    pub async fn post_with_priority<U, C: Client, R: Serialize>(
+        &self,
+        client_source: C,
+        path: &str,
+        body: R,
+        priority: Priority,
+    ) -> Result<
+        (StatusCode, axum::Json<U>),
+        (StatusCode, axum::Json<String>),
+    >
+    where
+        U: DeserializeOwned,
+    {
+        let base_url = client_source.get_base_url();
+        self.permit(&base_url, priority)?;
+        let request = self.counstruct_post_request(
+            client_source,
+            path,
+            body,
+        )?;
+        let response_bytes =
+            self.execute_request(request).await?;
+        self.quota.record_call(&base_url, now_millis());
+        self.deserialize_response::<U>(response_bytes).await
+    }
+
+    /// Like [`ApiClient::post`], but neither refused by nor counted
+    /// against the upstream's budget, for calls that cost nothing
+    /// such as Live Coin Watch `/credits`.
+    pub async fn post_unmetered<U, C: Client, R: Serialize>(
+        &self,
+        client_source: C,
+        path: &str,
+        body: R,
+    ) -> Result<
+        (StatusCode, axum::Json<U>),
+        (StatusCode, axum::Json<String>),
+    >
     where
         U: DeserializeOwned,
     {
@@ -29,7 +83,6 @@ impl ApiClient {
         )?;
         let response_bytes =
             self.execute_request(request).await?;
-        println!("{:#?}", response_bytes);
         self.deserialize_response::<U>(response_bytes).await
     }
 
@@ -42,11 +95,13 @@ impl ApiClient {
     {
         let mut url = client_source.get_base_url();
         url.push_str(path);
+        let headers =
+            Headers::try_from(client_source.get_headers())?;
 
         self.http_client
             .post(url)
             .json(&body)
-            .headers(Headers::from(client_source.get_headers()).0)
+            .headers(headers.0)
             .build()
             .map_err(|e| {
                 (
@@ -59,17 +114,63 @@ impl ApiClient {
 
 pub struct Headers(pub HeaderMap);
 
-impl From<HashMap<String, String>> for Headers {
-    fn from(value: HashMap<String, String>) -> Self {
+/// Upstream headers carry API keys, so every value is marked
+/// sensitive and left out of `Debug` output.
+impl TryFrom<HashMap<String, String>> for Headers {
+    type Error = (StatusCode, axum::Json<String>);
+
+    fn try_from(
+        value: HashMap<String, String>,
+    ) -> Result<Self, Self::Error> {
+        let invalid = |e: String| {
+            (StatusCode::INTERNAL_SERVER_ERROR, axum::Json(e))
+        };
         let mut map: HeaderMap<HeaderValue> =
             HeaderMap::new();
-        let _ = value.iter().map(|(k, v)| {
-            map.append(
-                HeaderName::from_str(k.as_str())
-                    .expect("msg"),
-                v.parse().expect("Unwrapping value failed"),
-            )
-        });
-        Headers(map)
+        for (k, v) in value {
+            let name = HeaderName::from_str(k.as_str())
+                .map_err(|e| {
+                    invalid(format!("Invalid header name {k}: {e}"))
+                })?;
+            let mut value = HeaderValue::from_str(&v)
+                .map_err(|e| {
+                    invalid(format!("Invalid value for header {k}: {e}"))
+                })?;
+            value.set_sensitive(true);
+            map.append(name, value);
+        }
+        Ok(Headers(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_from_map() {
+        let headers = Headers::try_from(HashMap::from([
+            ("x-api-key".to_string(), "key".to_string()),
+            (
+                "content-type".to_string(),
+                "application/json".to_string(),
+            ),
+        ]))
+        .unwrap();
+        assert_eq!(headers.0.len(), 2);
+        assert_eq!(headers.0["x-api-key"], "key");
+        assert!(headers.0["x-api-key"].is_sensitive());
+        assert!(!format!("{:?}", headers.0).contains("\"key\""));
+    }
+
+    #[test]
+    fn invalid_header_value_is_an_error() {
+        let Err((status, _)) = Headers::try_from(HashMap::from([(
+            "x-api-key".to_string(),
+            "key\n".to_string(),
+        )])) else {
+            panic!("a newline in a header value was accepted");
+        };
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
     }
 }
@@ -0,0 +1,327 @@
+use core::fmt;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// How much an upstream call matters, deciding how far into the
+/// daily budget it's allowed to dig.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Refreshes nobody is waiting for, e.g. aggregated lists or
+    /// backfills.
+    Background,
+    Normal,
+    /// Calls that keep alerts and budgets working.
+    Critical,
+}
+
+impl Priority {
+    /// Share of the daily limit kept in reserve for calls of a
+    /// higher priority.
+    fn reserve(&self) -> f64 {
+        match self {
+            Priority::Background => 0.2,
+            Priority::Normal => 0.05,
+            Priority::Critical => 0.0,
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetSource {
+    /// Synced from the upstream, e.g. Live Coin Watch `/credits`,
+    /// and counted down locally in between.
+    Upstream,
+    /// Only counted locally.
+    Local,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    ToSchema,
+)]
+pub struct Budget {
+    pub name: String,
+    pub base_url: String,
+    pub source: BudgetSource,
+    pub limit: u64,
+    pub remaining: u64,
+    /// Calls refused since the budget was tracked.
+    pub refused: u64,
+    /// Unix milliseconds, the next UTC midnight.
+    pub resets_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    pub name: String,
+    pub remaining: u64,
+    pub priority: Priority,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "Not enough {} credits left for a {:?} priority call ({} remaining today)",
+            self.name, self.priority, self.remaining
+        )
+    }
+}
+
+/// Daily call budgets per upstream, keyed by the `Client` base
+/// URL. Calls to upstreams without a budget are never refused.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaTracker {
+    budgets: Arc<RwLock<HashMap<String, Budget>>>,
+}
+
+impl QuotaTracker {
+    pub fn track(
+        &self,
+        name: &str,
+        base_url: &str,
+        daily_limit: u64,
+        source: BudgetSource,
+        now: i64,
+    ) {
+        self.budgets
+            .write()
+            .expect("Quota lock poisoned")
+            .insert(
+                base_url.to_string(),
+                Budget {
+                    name: name.to_string(),
+                    base_url: base_url.to_string(),
+                    source,
+                    limit: daily_limit,
+                    remaining: daily_limit,
+                    refused: 0,
+                    resets_at: next_utc_midnight(now),
+                },
+            );
+    }
+
+    /// Whether a call of `priority` to `base_url` may go ahead,
+    /// counting it as refused if not.
+    pub fn permit(
+        &self,
+        base_url: &str,
+        priority: Priority,
+        now: i64,
+    ) -> Result<(), QuotaExceeded> {
+        let mut budgets = self
+            .budgets
+            .write()
+            .expect("Quota lock poisoned");
+        let Some(budget) = budgets.get_mut(base_url) else {
+            return Ok(());
+        };
+        roll_over(budget, now);
+
+        let reserve = (budget.limit as f64
+            * priority.reserve())
+        .ceil() as u64;
+        if budget.remaining == 0
+            || budget.remaining < reserve
+        {
+            budget.refused += 1;
+            return Err(QuotaExceeded {
+                name: budget.name.clone(),
+                remaining: budget.remaining,
+                priority,
+            });
+        }
+        Ok(())
+    }
+
+    /// Counts a call that reached `base_url`.
+    pub fn record_call(&self, base_url: &str, now: i64) {
+        let mut budgets = self
+            .budgets
+            .write()
+            .expect("Quota lock poisoned");
+        if let Some(budget) = budgets.get_mut(base_url) {
+            roll_over(budget, now);
+            budget.remaining =
+                budget.remaining.saturating_sub(1);
+        }
+    }
+
+    /// Replaces the local count with the upstream's own.
+    pub fn sync(
+        &self,
+        base_url: &str,
+        remaining: u64,
+        limit: u64,
+        now: i64,
+    ) {
+        let mut budgets = self
+            .budgets
+            .write()
+            .expect("Quota lock poisoned");
+        if let Some(budget) = budgets.get_mut(base_url) {
+            budget.remaining = remaining;
+            budget.limit = limit;
+            budget.resets_at = next_utc_midnight(now);
+        }
+    }
+
+    /// All budgets, sorted by name.
+    pub fn budgets(&self) -> Vec<Budget> {
+        let mut budgets: Vec<Budget> = self
+            .budgets
+            .read()
+            .expect("Quota lock poisoned")
+            .values()
+            .cloned()
+            .collect();
+        budgets.sort_by(|a, b| a.name.cmp(&b.name));
+        budgets
+    }
+}
+
+fn next_utc_midnight(now: i64) -> i64 {
+    (now.div_euclid(DAY_MILLIS) + 1) * DAY_MILLIS
+}
+
+/// Refills `budget` once its day is over.
+fn roll_over(budget: &mut Budget, now: i64) {
+    if now >= budget.resets_at {
+        budget.remaining = budget.limit;
+        budget.resets_at = next_utc_midnight(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://api.example.com";
+
+    fn tracker(limit: u64) -> QuotaTracker {
+        let tracker = QuotaTracker::default();
+        tracker.track(
+            "example",
+            URL,
+            limit,
+            BudgetSource::Local,
+            0,
+        );
+        tracker
+    }
+
+    fn spend(tracker: &QuotaTracker, calls: u64) {
+        for _ in 0..calls {
+            tracker.record_call(URL, 1);
+        }
+    }
+
+    #[test]
+    fn untracked_upstream_is_permitted() {
+        assert!(QuotaTracker::default()
+            .permit(URL, Priority::Background, 0)
+            .is_ok());
+    }
+
+    #[test]
+    fn background_calls_keep_reserve() {
+        let tracker = tracker(100);
+        spend(&tracker, 80);
+        assert!(tracker
+            .permit(URL, Priority::Background, 1)
+            .is_ok());
+        spend(&tracker, 1);
+        assert!(tracker
+            .permit(URL, Priority::Background, 1)
+            .is_err());
+        assert!(tracker
+            .permit(URL, Priority::Normal, 1)
+            .is_ok());
+    }
+
+    #[test]
+    fn critical_calls_use_last_credit() {
+        let tracker = tracker(100);
+        spend(&tracker, 99);
+        assert!(tracker
+            .permit(URL, Priority::Normal, 1)
+            .is_err());
+        assert!(tracker
+            .permit(URL, Priority::Critical, 1)
+            .is_ok());
+        spend(&tracker, 1);
+        assert_eq!(
+            tracker.permit(URL, Priority::Critical, 1),
+            Err(QuotaExceeded {
+                name: "example".into(),
+                remaining: 0,
+                priority: Priority::Critical,
+            })
+        );
+        assert_eq!(tracker.budgets()[0].refused, 2);
+    }
+
+    #[test]
+    fn budget_resets_at_utc_midnight() {
+        let tracker = tracker(10);
+        spend(&tracker, 10);
+        assert!(tracker
+            .permit(URL, Priority::Critical, DAY_MILLIS - 1)
+            .is_err());
+        assert!(tracker
+            .permit(URL, Priority::Critical, DAY_MILLIS)
+            .is_ok());
+        assert_eq!(tracker.budgets()[0].remaining, 10);
+        assert_eq!(
+            tracker.budgets()[0].resets_at,
+            2 * DAY_MILLIS
+        );
+    }
+
+    #[test]
+    fn sync_replaces_local_count() {
+        let tracker = tracker(10);
+        spend(&tracker, 5);
+        tracker.sync(URL, 9000, 10000, 1);
+        let budget = &tracker.budgets()[0];
+        assert_eq!(
+            (budget.remaining, budget.limit),
+            (9000, 10000)
+        );
+    }
+}
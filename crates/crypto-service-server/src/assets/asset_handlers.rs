@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use crypto_service::asset_registry::{self, AssetIdentity};
+
+use super::{
+    models::AssetRefresh, refresh::refresh_registry,
+};
+use crate::state::AppState;
+
+/// Every known asset and what each provider calls it.
+#[utoipa::path(
+    get,
+    path = "/v1/assets",
+    tag = "coins",
+    responses(
+        (status = 200, body = Vec<AssetIdentity>),
+    )
+)]
+pub async fn list_assets() -> Json<Vec<AssetIdentity>> {
+    Json(asset_registry::shared().read().unwrap().assets())
+}
+
+/// What each provider calls one asset.
+#[utoipa::path(
+    get,
+    path = "/v1/assets/{code}",
+    tag = "coins",
+    params(
+        ("code" = String, Path, description = "Canonical id such as `BTC`"),
+    ),
+    responses(
+        (status = 200, body = AssetIdentity),
+        (status = 404, description = "Unknown asset"),
+    )
+)]
+pub async fn get_asset(
+    Path(code): Path<String>,
+) -> Result<Json<AssetIdentity>, (StatusCode, Json<String>)>
+{
+    asset_registry::shared()
+        .read()
+        .unwrap()
+        .get(&code)
+        .cloned()
+        .map(Json)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(format!(
+                "No asset {}",
+                code.to_uppercase()
+            )),
+        ))
+}
+
+/// Refreshes the registry from the provider listings right away.
+pub async fn refresh_assets(
+    State(state): State<AppState>,
+) -> Result<Json<AssetRefresh>, (StatusCode, Json<String>)>
+{
+    refresh_registry(
+        asset_registry::shared(),
+        &state.api_client,
+        &state.coingecko_client,
+        &state.binance_client,
+        &state.coinapi_client,
+    )
+    .await
+    .map(Json)
+}
@@ -0,0 +1,3 @@
+pub mod asset_handlers;
+pub mod models;
+pub mod refresh;
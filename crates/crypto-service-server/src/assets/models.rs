@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Identifiers a refresh of the asset registry added, by listing.
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, PartialEq,
+)]
+pub struct AssetRefresh {
+    pub coingecko: u32,
+    pub binance: u32,
+    pub coinapi: u32,
+    /// Listings that couldn't be fetched, CoinAPI's is skipped
+    /// without a key.
+    pub errors: Vec<String>,
+}
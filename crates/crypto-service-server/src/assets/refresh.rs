@@ -0,0 +1,248 @@
+use std::{sync::RwLock, time::Duration};
+
+use axum::{http::StatusCode, Json};
+use crypto_service::{
+    asset_registry::{self, AssetRegistry},
+    binance_service::{
+        binance_client::BinanceClient,
+        models::{ExchangeInfo, ExchangeInfoRequest},
+    },
+    coinapi_service::{
+        coinapi_client::CoinApiClient,
+        models::{AssetsRequest, CoinApiAsset},
+    },
+    coingecko_service::{
+        coingecko_client::CoinGeckoClient,
+        models::{Market, MarketsQuery, MAX_PER_PAGE},
+    },
+};
+
+use super::models::AssetRefresh;
+use crate::{
+    api_client::api_client::ApiClient,
+    coinapi::coinapi_handlers::fetch, state::AppState,
+};
+
+/// Fills `registry` in from CoinGecko's top coins, the pairs
+/// Binance trades and, with a key, CoinAPI's assets. Fails only
+/// when none of the listings could be fetched.
+pub async fn refresh_registry(
+    registry: &RwLock<AssetRegistry>,
+    api_client: &ApiClient,
+    coingecko_client: &CoinGeckoClient,
+    binance_client: &BinanceClient,
+    coinapi_client: &CoinApiClient,
+) -> Result<AssetRefresh, (StatusCode, Json<String>)> {
+    let (markets, exchange_info, coinapi_assets) = tokio::join!(
+        api_client.get::<MarketsQuery, Vec<Market>, CoinGeckoClient>(
+            coingecko_client.clone(),
+            "/coins/markets",
+            MarketsQuery::new(MAX_PER_PAGE),
+        ),
+        api_client.get::<ExchangeInfoRequest, ExchangeInfo, BinanceClient>(
+            binance_client.clone(),
+            "/exchangeInfo",
+            ExchangeInfoRequest::default(),
+        ),
+        async {
+            match coinapi_client.has_key() {
+                true => Some(
+                    fetch::<_, Vec<CoinApiAsset>>(
+                        api_client,
+                        coinapi_client,
+                        "/assets",
+                        AssetsRequest::default(),
+                    )
+                    .await,
+                ),
+                false => None,
+            }
+        },
+    );
+
+    let mut refresh = AssetRefresh::default();
+    let mut failures = vec![];
+    let mut registry = registry.write().unwrap();
+    match markets {
+        Ok((_, Json(markets))) => {
+            refresh.coingecko =
+                registry.merge_coingecko(&markets)
+        }
+        Err(error) => failures.push(("coingecko", error)),
+    }
+    match exchange_info {
+        Ok((_, Json(info))) => {
+            refresh.binance = registry.merge_binance(&info)
+        }
+        Err(error) => failures.push(("binance", error)),
+    }
+    match coinapi_assets {
+        Some(Ok(assets)) => {
+            refresh.coinapi =
+                registry.merge_coinapi(&assets)
+        }
+        Some(Err(error)) => {
+            failures.push(("coinapi", error))
+        }
+        None => {}
+    }
+    drop(registry);
+
+    let attempted = 2 + coinapi_client.has_key() as usize;
+    if failures.len() == attempted {
+        let (_, (status, _)) = &failures[0];
+        return Err((
+            *status,
+            Json(format!(
+                "Failed to refresh assets: {}",
+                messages(&failures).join("; ")
+            )),
+        ));
+    }
+    refresh.errors = messages(&failures);
+    Ok(refresh)
+}
+
+fn messages(
+    failures: &[(&str, (StatusCode, Json<String>))],
+) -> Vec<String> {
+    failures
+        .iter()
+        .map(|(listing, (status, Json(message)))| {
+            format!("{listing}: {status} {message}")
+        })
+        .collect()
+}
+
+/// Keeps the shared asset registry in line with the provider
+/// listings, so coins listed since startup map across providers.
+pub async fn run_asset_refresh(
+    state: AppState,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err((status, Json(message))) =
+            refresh_registry(
+                asset_registry::shared(),
+                &state.api_client,
+                &state.coingecko_client,
+                &state.binance_client,
+                &state.coinapi_client,
+            )
+            .await
+        {
+            println!("Failed to refresh assets: {status} {message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::get, Router};
+    use serde_json::json;
+
+    use crypto_service::coingecko_service::coingecko_client::CoinGeckoPlan;
+
+    use super::*;
+    use crate::test_support;
+
+    /// Local stand-in for CoinGecko and Binance, which only lists
+    /// `/coins/markets` when `markets` is set.
+    async fn base_url(markets: bool) -> String {
+        let mut app = Router::new().route(
+            "/exchangeInfo",
+            get(|| async {
+                Json(json!({
+                    "timezone": "UTC",
+                    "serverTime": 0,
+                    "symbols": [{
+                        "symbol": "XMRUSDT", "status": "TRADING",
+                        "baseAsset": "XMR", "quoteAsset": "USDT",
+                        "baseAssetPrecision": 8, "quoteAssetPrecision": 8
+                    }]
+                }))
+            }),
+        );
+        if markets {
+            app = app.route(
+                "/coins/markets",
+                get(|| async {
+                    Json(json!([
+                        {"id": "bitcoin", "symbol": "btc", "name": "Bitcoin"},
+                        {"id": "pepe", "symbol": "pepe", "name": "Pepe"}
+                    ]))
+                }),
+            );
+        }
+        test_support::serve(app).await
+    }
+
+    async fn refresh(
+        base_url: &str,
+        registry: &RwLock<AssetRegistry>,
+    ) -> Result<AssetRefresh, (StatusCode, Json<String>)>
+    {
+        let mut coingecko_client =
+            CoinGeckoClient::new_with_key(
+                String::new(),
+                CoinGeckoPlan::Public,
+            );
+        coingecko_client.base_url = base_url.into();
+        let mut binance_client =
+            BinanceClient::new_with_key(String::new());
+        binance_client.base_url = base_url.into();
+        refresh_registry(
+            registry,
+            &ApiClient::new(),
+            &coingecko_client,
+            &binance_client,
+            &CoinApiClient::new_with_key(String::new()),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn merges_every_listing() {
+        let registry =
+            RwLock::new(AssetRegistry::bundled());
+        let refresh =
+            refresh(&base_url(true).await, &registry)
+                .await
+                .unwrap();
+        assert_eq!(
+            refresh,
+            AssetRefresh {
+                coingecko: 1,
+                binance: 1,
+                coinapi: 0,
+                errors: vec![],
+            }
+        );
+        let registry = registry.read().unwrap();
+        assert_eq!(
+            registry.get("PEPE").unwrap().coingecko,
+            Some("pepe".into())
+        );
+        assert_eq!(
+            registry.get("XMR").unwrap().binance,
+            Some("XMRUSDT".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_listing_is_reported() {
+        let registry =
+            RwLock::new(AssetRegistry::bundled());
+        let refresh =
+            refresh(&base_url(false).await, &registry)
+                .await
+                .unwrap();
+        assert_eq!(refresh.binance, 1);
+        assert_eq!(refresh.errors.len(), 1);
+        assert!(
+            refresh.errors[0].starts_with("coingecko: ")
+        );
+    }
+}
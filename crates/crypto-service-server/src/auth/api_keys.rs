@@ -0,0 +1,510 @@
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rusqlite::params;
+use sha2::{Digest, Sha256};
+
+use super::{
+    models::{
+        ApiKey, ApiKeyWithUsage, ConfiguredKey,
+        CreateApiKeyRequest, CreatedApiKey, KeySource,
+        KeyUsage, RateLimit, Scope,
+    },
+    rate_limit::TokenBucket,
+};
+use crate::store::quote_store::QuoteStore;
+
+/// Hex encoded SHA-256 of `key`, the only form keys are kept in.
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// A new random key.
+pub fn generate_key() -> String {
+    format!(
+        "cs_{}",
+        hex::encode(rand::random::<[u8; 24]>())
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    MissingKey,
+    UnknownKey,
+    Forbidden(Scope),
+    /// Milliseconds until the key's bucket has a token again.
+    RateLimited(u64),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            AuthError::MissingKey => write!(
+                f,
+                "Missing API key, send it in the x-api-key header"
+            ),
+            AuthError::UnknownKey => write!(f, "Unknown API key"),
+            AuthError::Forbidden(scope) => write!(
+                f,
+                "API key lacks the '{}' scope",
+                scope.as_str()
+            ),
+            AuthError::RateLimited(_) => {
+                write!(f, "Rate limit exceeded")
+            }
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AuthError::MissingKey
+            | AuthError::UnknownKey => {
+                StatusCode::UNAUTHORIZED
+            }
+            AuthError::Forbidden(_) => {
+                StatusCode::FORBIDDEN
+            }
+            AuthError::RateLimited(_) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+        };
+        let mut response = (status, Json(self.to_string()))
+            .into_response();
+        if let AuthError::RateLimited(retry_after) = self {
+            let secs = retry_after.div_ceil(1000).max(1);
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, secs.into());
+        }
+        response
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: ApiKey,
+    bucket: TokenBucket,
+    usage: KeyUsage,
+}
+
+/// Inbound API keys by hash, with a token bucket and usage counters
+/// for each.
+///
+/// Keys come from the `API_KEYS_PATH` file or are created through
+/// the admin API and kept in the [`QuoteStore`]. Usage is counted
+/// in memory only.
+#[derive(Debug, Clone)]
+pub struct ApiKeys {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+    store: QuoteStore,
+}
+
+impl ApiKeys {
+    /// Keys from the JSON list of [`ConfiguredKey`]s at
+    /// `config_path`, if it exists, and the unrevoked keys in
+    /// `store`.
+    pub fn load(
+        config_path: impl AsRef<Path>,
+        store: QuoteStore,
+        now: i64,
+    ) -> anyhow::Result<Self> {
+        let configured: Vec<ConfiguredKey> =
+            match fs::read(config_path) {
+                Ok(bytes) => {
+                    serde_json::from_slice(&bytes)?
+                }
+                Err(e)
+                    if e.kind()
+                        == io::ErrorKind::NotFound =>
+                {
+                    vec![]
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+        let keys = Self {
+            entries: Arc::default(),
+            store,
+        };
+        for configured in configured {
+            keys.insert(
+                configured.key_sha256.to_lowercase(),
+                ApiKey {
+                    id: format!(
+                        "config-{}",
+                        configured.name
+                    ),
+                    name: configured.name,
+                    scopes: configured.scopes,
+                    rate_limit: configured.rate_limit,
+                    source: KeySource::Config,
+                    created_at: None,
+                },
+                now,
+            );
+        }
+        for (hash, key) in keys.stored_keys()? {
+            keys.insert(hash, key, now);
+        }
+        Ok(keys)
+    }
+
+    fn stored_keys(
+        &self,
+    ) -> rusqlite::Result<Vec<(String, ApiKey)>> {
+        let connection = self.store.connection();
+        let mut statement = connection.prepare_cached(
+            "SELECT id, name, key_hash, scopes,
+                    requests_per_minute, burst, created_at
+             FROM api_keys WHERE revoked_at IS NULL",
+        )?;
+        let keys = statement
+            .query_map([], |row| {
+                let scopes: String = row.get("scopes")?;
+                Ok((
+                    row.get("key_hash")?,
+                    ApiKey {
+                        id: row
+                            .get::<_, i64>("id")?
+                            .to_string(),
+                        name: row.get("name")?,
+                        scopes: scopes
+                            .split(',')
+                            .filter_map(Scope::parse)
+                            .collect(),
+                        rate_limit: RateLimit {
+                            requests_per_minute: row.get(
+                                "requests_per_minute",
+                            )?,
+                            burst: row.get("burst")?,
+                        },
+                        source: KeySource::Store,
+                        created_at: row
+                            .get("created_at")?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(keys)
+    }
+
+    fn insert(&self, hash: String, key: ApiKey, now: i64) {
+        self.entries
+            .lock()
+            .expect("Key lock poisoned")
+            .insert(
+                hash,
+                Entry {
+                    bucket: TokenBucket::new(
+                        key.rate_limit,
+                        now,
+                    ),
+                    key,
+                    usage: KeyUsage::default(),
+                },
+            );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries
+            .lock()
+            .expect("Key lock poisoned")
+            .is_empty()
+    }
+
+    /// Checks that `presented` is a known key allowed to call
+    /// `scope` and takes a token from its bucket, counting the
+    /// outcome.
+    pub fn authorize(
+        &self,
+        presented: Option<&str>,
+        scope: Scope,
+        now: i64,
+    ) -> Result<ApiKey, AuthError> {
+        let presented =
+            presented.ok_or(AuthError::MissingKey)?;
+        let mut entries =
+            self.entries.lock().expect("Key lock poisoned");
+        let entry = entries
+            .get_mut(&hash_key(presented))
+            .ok_or(AuthError::UnknownKey)?;
+
+        if !entry.key.allows(scope) {
+            entry.usage.forbidden += 1;
+            return Err(AuthError::Forbidden(scope));
+        }
+        if let Err(retry_after) = entry.bucket.try_take(now)
+        {
+            entry.usage.rate_limited += 1;
+            return Err(AuthError::RateLimited(
+                retry_after,
+            ));
+        }
+        entry.usage.requests += 1;
+        entry.usage.last_used_at = Some(now);
+        Ok(entry.key.clone())
+    }
+
+    /// Creates and stores a key, returning it in plain text.
+    pub fn create(
+        &self,
+        request: CreateApiKeyRequest,
+        now: i64,
+    ) -> rusqlite::Result<CreatedApiKey> {
+        let api_key = generate_key();
+        let hash = hash_key(&api_key);
+        let rate_limit =
+            request.rate_limit.unwrap_or_default();
+        let scopes: Vec<&str> = request
+            .scopes
+            .iter()
+            .map(Scope::as_str)
+            .collect();
+
+        let id = {
+            let connection = self.store.connection();
+            connection.execute(
+                "INSERT INTO api_keys (name, key_hash, scopes,
+                     requests_per_minute, burst, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    request.name,
+                    hash,
+                    scopes.join(","),
+                    rate_limit.requests_per_minute,
+                    rate_limit.burst,
+                    now,
+                ],
+            )?;
+            connection.last_insert_rowid()
+        };
+
+        let key = ApiKey {
+            id: id.to_string(),
+            name: request.name,
+            scopes: request.scopes,
+            rate_limit,
+            source: KeySource::Store,
+            created_at: Some(now),
+        };
+        self.insert(hash, key.clone(), now);
+        Ok(CreatedApiKey { key, api_key })
+    }
+
+    /// Revokes a stored key, returning whether there was one.
+    /// Configured keys are only removed from the config file.
+    pub fn revoke(
+        &self,
+        id: &str,
+        now: i64,
+    ) -> rusqlite::Result<bool> {
+        let Ok(row_id) = id.parse::<i64>() else {
+            return Ok(false);
+        };
+        let revoked = self.store.connection().execute(
+            "UPDATE api_keys SET revoked_at = ?2
+             WHERE id = ?1 AND revoked_at IS NULL",
+            params![row_id, now],
+        )?;
+        self.entries
+            .lock()
+            .expect("Key lock poisoned")
+            .retain(|_, entry| {
+                entry.key.source != KeySource::Store
+                    || entry.key.id != id
+            });
+        Ok(revoked == 1)
+    }
+
+    /// All keys with their usage, sorted by id.
+    pub fn keys(&self) -> Vec<ApiKeyWithUsage> {
+        let mut keys: Vec<ApiKeyWithUsage> = self
+            .entries
+            .lock()
+            .expect("Key lock poisoned")
+            .values()
+            .map(|entry| ApiKeyWithUsage {
+                key: entry.key.clone(),
+                usage: entry.usage.clone(),
+            })
+            .collect();
+        keys.sort_by(|a, b| a.key.id.cmp(&b.key.id));
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MISSING: &str = "/nonexistent/api_keys.json";
+
+    fn keys() -> ApiKeys {
+        ApiKeys::load(
+            MISSING,
+            QuoteStore::open_in_memory().unwrap(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn create(
+        keys: &ApiKeys,
+        scopes: Vec<Scope>,
+        burst: u32,
+    ) -> CreatedApiKey {
+        keys.create(
+            CreateApiKeyRequest {
+                name: "app".into(),
+                scopes,
+                rate_limit: Some(RateLimit {
+                    requests_per_minute: 60,
+                    burst,
+                }),
+            },
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn configured_keys_are_loaded_by_hash() {
+        let path = std::env::temp_dir().join(format!(
+            "crypto-service-keys-{}.json",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            format!(
+                r#"[{{"name": "ops", "key_sha256": "{}", "scopes": ["admin"]}}]"#,
+                hash_key("secret")
+            ),
+        )
+        .unwrap();
+        let keys = ApiKeys::load(
+            &path,
+            QuoteStore::open_in_memory().unwrap(),
+            0,
+        )
+        .unwrap();
+        let _ = fs::remove_file(&path);
+
+        let key = keys
+            .authorize(Some("secret"), Scope::Stocks, 0)
+            .unwrap();
+        assert_eq!(key.id, "config-ops");
+        assert_eq!(key.source, KeySource::Config);
+    }
+
+    #[test]
+    fn unknown_and_missing_keys_are_refused() {
+        let keys = keys();
+        assert_eq!(
+            keys.authorize(None, Scope::Coins, 0),
+            Err(AuthError::MissingKey)
+        );
+        assert_eq!(
+            keys.authorize(Some("nope"), Scope::Coins, 0),
+            Err(AuthError::UnknownKey)
+        );
+    }
+
+    #[test]
+    fn scopes_are_enforced() {
+        let keys = keys();
+        let created = create(&keys, vec![Scope::Coins], 10);
+        assert!(keys
+            .authorize(
+                Some(&created.api_key),
+                Scope::Coins,
+                0
+            )
+            .is_ok());
+        assert_eq!(
+            keys.authorize(
+                Some(&created.api_key),
+                Scope::Admin,
+                0
+            ),
+            Err(AuthError::Forbidden(Scope::Admin))
+        );
+    }
+
+    #[test]
+    fn rate_limit_and_usage_are_tracked() {
+        let keys = keys();
+        let created = create(&keys, vec![Scope::Coins], 2);
+        let key = Some(created.api_key.as_str());
+        keys.authorize(key, Scope::Coins, 5).unwrap();
+        keys.authorize(key, Scope::Coins, 5).unwrap();
+        assert_eq!(
+            keys.authorize(key, Scope::Coins, 5),
+            Err(AuthError::RateLimited(1000))
+        );
+        let _ = keys.authorize(key, Scope::Stocks, 5);
+
+        assert_eq!(
+            keys.keys()[0].usage,
+            KeyUsage {
+                requests: 2,
+                rate_limited: 1,
+                forbidden: 1,
+                last_used_at: Some(5),
+            }
+        );
+    }
+
+    #[test]
+    fn created_keys_survive_reload_until_revoked() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        let keys = ApiKeys::load(MISSING, store.clone(), 0)
+            .unwrap();
+        let created =
+            create(&keys, vec![Scope::Stocks], 10);
+        assert_ne!(created.api_key, created.key.id);
+
+        let reloaded =
+            ApiKeys::load(MISSING, store.clone(), 0)
+                .unwrap();
+        assert_eq!(reloaded.keys()[0].key, created.key);
+
+        assert!(reloaded
+            .revoke(&created.key.id, 1)
+            .unwrap());
+        assert!(!reloaded
+            .revoke(&created.key.id, 1)
+            .unwrap());
+        assert!(reloaded.is_empty());
+        assert!(ApiKeys::load(MISSING, store, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn rate_limited_response_has_retry_after() {
+        let response =
+            AuthError::RateLimited(1500).into_response();
+        assert_eq!(
+            response.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            response.headers()[header::RETRY_AFTER],
+            "2"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use super::models::{
+    ApiKeyWithUsage, CreateApiKeyRequest, CreatedApiKey,
+};
+use crate::{
+    alerts::alert_engine::now_millis, state::AppState,
+};
+
+/// Keys with their usage since startup.
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<ApiKeyWithUsage>>) {
+    (StatusCode::OK, Json(state.api_keys.keys()))
+}
+
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Json(body): Json<CreateApiKeyRequest>,
+) -> Result<
+    (StatusCode, Json<CreatedApiKey>),
+    (StatusCode, Json<String>),
+> {
+    if body.name.trim().is_empty() || body.scopes.is_empty()
+    {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(
+                "A key needs a name and at least one scope"
+                    .into(),
+            ),
+        ));
+    }
+
+    state
+        .api_keys
+        .create(body, now_millis())
+        .map(|created| (StatusCode::CREATED, Json(created)))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(e.to_string()),
+            )
+        })
+}
+
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<String>)> {
+    match state.api_keys.revoke(&id, now_millis()) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(format!(
+                "No revocable key with id '{id}'"
+            )),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(e.to_string()),
+        )),
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::models::Scope;
+use crate::{
+    alerts::alert_engine::now_millis, state::AppState,
+};
+
+/// Key sent with `request`, from the `x-api-key` header, a bearer
+/// token, or the `api_key` query parameter for clients that can't
+/// set headers such as browser WebSockets and `EventSource`.
+fn presented_key(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    if let Some(key) = headers
+        .get("x-api-key")
+        .and_then(|key| key.to_str().ok())
+    {
+        return Some(key.to_string());
+    }
+    if let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(token.to_string());
+    }
+    Query::<HashMap<String, String>>::try_from_uri(
+        request.uri(),
+    )
+    .ok()
+    .and_then(|Query(mut query)| query.remove("api_key"))
+}
+
+/// Lets requests through to versioned routes only with a key that
+/// has the route's [`Scope`] and tokens left in its bucket.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(scope) = Scope::for_path(request.uri().path())
+    else {
+        return next.run(request).await;
+    };
+
+    match state.api_keys.authorize(
+        presented_key(&request).as_deref(),
+        scope,
+        now_millis(),
+    ) {
+        Ok(_) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{middleware, routing::get, Router};
+    use crypto_service::{
+        alphavantage_service::alpha_vantage_client::AlphaVantageClient,
+        binance_service::binance_client::BinanceClient,
+        coin_watch_service::coin_watch_client::CoinWatchClient,
+        coinapi_service::coinapi_client::CoinApiClient,
+        coingecko_service::coingecko_client::{
+            CoinGeckoClient, CoinGeckoPlan,
+        },
+    };
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::{
+        alerts::{
+            alert_engine::AlertEngine,
+            notifier::LogNotifier,
+        },
+        alphavantage_api::trades_feed::TradesFeed,
+        api_client::api_client::ApiClient,
+        auth::{
+            api_keys::ApiKeys,
+            models::{CreateApiKeyRequest, RateLimit},
+        },
+        icons::icon_cache::{
+            IconCache, DEFAULT_ICON_BASE_URL,
+        },
+        providers::market_data::FailoverProvider,
+        store::quote_store::QuoteStore,
+        stream::price_hub::PriceHub,
+        test_support,
+        webhooks::webhook_dispatcher::WebhookDispatcher,
+    };
+
+    async fn serve() -> (String, String) {
+        let store = QuoteStore::open_in_memory().unwrap();
+        let api_keys = ApiKeys::load(
+            "/nonexistent/api_keys.json",
+            store.clone(),
+            now_millis(),
+        )
+        .unwrap();
+        let api_key = api_keys
+            .create(
+                CreateApiKeyRequest {
+                    name: "app".into(),
+                    scopes: vec![Scope::Coins],
+                    rate_limit: Some(RateLimit {
+                        requests_per_minute: 1,
+                        burst: 2,
+                    }),
+                },
+                now_millis(),
+            )
+            .unwrap()
+            .api_key;
+        let state = AppState::new(
+            AlphaVantageClient::new_with_key(String::new()),
+            CoinWatchClient::new_with_key("key".into()),
+            CoinGeckoClient::new_with_key(
+                String::new(),
+                CoinGeckoPlan::Public,
+            ),
+            BinanceClient::new_with_key(String::new()),
+            CoinApiClient::new_with_key(String::new()),
+            ApiClient::new(),
+            AlertEngine::new(Arc::new(LogNotifier)),
+            WebhookDispatcher::default(),
+            PriceHub::default(),
+            TradesFeed::default(),
+            store,
+            api_keys,
+            IconCache::new(
+                std::env::temp_dir(),
+                DEFAULT_ICON_BASE_URL,
+            ),
+            FailoverProvider::new(vec![], vec![], 0, 0.0),
+        );
+
+        let app = Router::new()
+            .route("/readyz", get(|| async { "ready" }))
+            .route(
+                "/v1/coins/list",
+                get(|| async { "coins" }),
+            )
+            .route(
+                "/v1/admin/keys",
+                get(|| async { "keys" }),
+            )
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                authenticate,
+            ))
+            .with_state(state);
+        let base_url = test_support::serve(app).await;
+        (base_url, api_key)
+    }
+
+    #[tokio::test]
+    async fn routes_need_a_scoped_key() {
+        let (base_url, api_key) = serve().await;
+        let client = reqwest::Client::new();
+        let status = |request: reqwest::RequestBuilder| async {
+            request.send().await.unwrap().status()
+        };
+
+        assert_eq!(
+            status(
+                client.get(format!("{base_url}/readyz"))
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                client.get(format!(
+                    "{base_url}/v1/coins/list"
+                ))
+            )
+            .await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                client
+                    .get(format!(
+                        "{base_url}/v1/coins/list"
+                    ))
+                    .header("x-api-key", &api_key)
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                client
+                    .get(format!(
+                        "{base_url}/v1/admin/keys"
+                    ))
+                    .bearer_auth(&api_key)
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(client.get(format!(
+                "{base_url}/v1/coins/list?api_key={api_key}"
+            )))
+            .await,
+            StatusCode::OK
+        );
+
+        let limited = client
+            .get(format!("{base_url}/v1/coins/list"))
+            .header("x-api-key", &api_key)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            limited.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert!(limited
+            .headers()
+            .contains_key("retry-after"));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod api_keys;
+pub mod auth_handlers;
+pub mod auth_middleware;
+pub mod models;
+pub mod rate_limit;
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+/// Area of the API a key may call.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// `/v1/stocks` and `/v1/alphavantage`, which share the Alpha
+    /// Vantage budget.
+    Stocks,
+    Coins,
+    /// Everything under `/v1/admin`, and every other scope too.
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Stocks => "stocks",
+            Scope::Coins => "coins",
+            Scope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "stocks" => Some(Scope::Stocks),
+            "coins" => Some(Scope::Coins),
+            "admin" => Some(Scope::Admin),
+            _ => None,
+        }
+    }
+
+    /// Scope needed for `path`, or `None` for routes open to
+    /// everyone: those outside a versioned API such as `/readyz`,
+    /// and icons, which `<img>` tags fetch without a key.
+    pub fn for_path(path: &str) -> Option<Self> {
+        let mut segments =
+            path.trim_start_matches('/').split('/');
+        let version = segments.next()?;
+        let is_versioned = version.len() > 1
+            && version.starts_with('v')
+            && version[1..]
+                .chars()
+                .all(|c| c.is_ascii_digit());
+        if !is_versioned {
+            return None;
+        }
+        match segments.next() {
+            Some("icons") => None,
+            Some("stocks" | "alphavantage") => {
+                Some(Scope::Stocks)
+            }
+            Some("admin") => Some(Scope::Admin),
+            _ => Some(Scope::Coins),
+        }
+    }
+}
+
+/// Token bucket settings for a key.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests_per_minute: u32,
+    /// Requests that may be made at once after a quiet spell.
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 60,
+            burst: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Read from the `API_KEYS_PATH` file, can't be revoked through
+    /// the API.
+    Config,
+    /// Created through `/v1/admin/keys` and kept in the local store.
+    Store,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub rate_limit: RateLimit,
+    pub source: KeySource,
+    pub created_at: Option<i64>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+            || self.scopes.contains(&Scope::Admin)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeyUsage {
+    /// Requests let through since startup.
+    pub requests: u64,
+    pub rate_limited: u64,
+    /// Requests outside the key's scopes.
+    pub forbidden: u64,
+    pub last_used_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyWithUsage {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub usage: KeyUsage,
+}
+
+/// An entry of the `API_KEYS_PATH` file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfiguredKey {
+    pub name: String,
+    /// Hex encoded SHA-256 of the key.
+    pub key_sha256: String,
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Response to creating a key, the only time the key itself is
+/// shown.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub api_key: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_for_path() {
+        assert_eq!(
+            Scope::for_path("/v1/stocks/stream"),
+            Some(Scope::Stocks)
+        );
+        assert_eq!(
+            Scope::for_path("/v1/alphavantage/fx/daily"),
+            Some(Scope::Stocks)
+        );
+        assert_eq!(
+            Scope::for_path("/v1/coins/list"),
+            Some(Scope::Coins)
+        );
+        assert_eq!(
+            Scope::for_path("/v2/local/coins/BTC/history"),
+            Some(Scope::Coins)
+        );
+        assert_eq!(
+            Scope::for_path("/v1/admin/keys"),
+            Some(Scope::Admin)
+        );
+        assert_eq!(Scope::for_path("/readyz"), None);
+        assert_eq!(Scope::for_path("/v1/icons/btc.png"), None);
+        assert_eq!(Scope::for_path("/validate"), None);
+    }
+
+    #[test]
+    fn admin_allows_every_scope() {
+        let key = ApiKey {
+            id: "1".into(),
+            name: "ops".into(),
+            scopes: vec![Scope::Admin],
+            rate_limit: RateLimit::default(),
+            source: KeySource::Store,
+            created_at: None,
+        };
+        assert!(key.allows(Scope::Stocks));
+        assert!(key.allows(Scope::Coins));
+    }
+
+    #[test]
+    fn configured_key_defaults_rate_limit() {
+        let key: ConfiguredKey = serde_json::from_str(
+            r#"{"name": "app", "key_sha256": "ab", "scopes": ["coins"]}"#,
+        )
+        .unwrap();
+        assert_eq!(key.scopes, vec![Scope::Coins]);
+        assert_eq!(key.rate_limit, RateLimit::default());
+    }
+}
@@ -0,0 +1,90 @@
+use super::models::RateLimit;
+
+/// Classic token bucket: holds up to `burst` requests and refills at
+/// `requests_per_minute`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBucket {
+    capacity: f64,
+    /// Tokens added per millisecond.
+    refill_rate: f64,
+    tokens: f64,
+    updated_at: i64,
+}
+
+impl TokenBucket {
+    /// A full bucket.
+    pub fn new(rate_limit: RateLimit, now: i64) -> Self {
+        let capacity = rate_limit.burst.max(1) as f64;
+        Self {
+            capacity,
+            refill_rate: rate_limit.requests_per_minute
+                as f64
+                / 60_000.0,
+            tokens: capacity,
+            updated_at: now,
+        }
+    }
+
+    /// Takes a token, or returns the milliseconds until one is
+    /// available.
+    pub fn try_take(
+        &mut self,
+        now: i64,
+    ) -> Result<(), u64> {
+        let elapsed = (now - self.updated_at).max(0) as f64;
+        self.tokens = (self.tokens
+            + elapsed * self.refill_rate)
+            .min(self.capacity);
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        if self.refill_rate == 0.0 {
+            return Err(u64::MAX);
+        }
+        Err(((1.0 - self.tokens) / self.refill_rate).ceil()
+            as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket() -> TokenBucket {
+        TokenBucket::new(
+            RateLimit {
+                requests_per_minute: 60,
+                burst: 3,
+            },
+            0,
+        )
+    }
+
+    #[test]
+    fn allows_burst_then_refuses() {
+        let mut bucket = bucket();
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(0), Ok(()));
+        }
+        assert_eq!(bucket.try_take(0), Err(1000));
+        assert_eq!(bucket.try_take(400), Err(600));
+    }
+
+    #[test]
+    fn refills_over_time_up_to_burst() {
+        let mut bucket = bucket();
+        for _ in 0..3 {
+            bucket.try_take(0).unwrap();
+        }
+        assert_eq!(bucket.try_take(1000), Ok(()));
+        assert!(bucket.try_take(1000).is_err());
+
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(60_000), Ok(()));
+        }
+        assert!(bucket.try_take(60_000).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Runs a history backfill against the local quote store without
+//! starting the server.
+//!
+//! ```text
+//! backfill --codes BTC,ETH --start <ms> --end <ms> --budget <credits>
+//!          [--chunk-secs <secs>] [--max-gap-secs <secs>]
+//! backfill --resume <job id> [--budget <additional credits>]
+//! ```
+
+use std::{collections::HashMap, env};
+
+use anyhow::{anyhow, Context, Result};
+use crypto_service::coin_watch_service::coin_watch_client::CoinWatchClient;
+use crypto_service_server::{
+    api_client::api_client::ApiClient,
+    store::{
+        backfill::Backfill, collector::parse_watchlist,
+        models::CreateBackfillRequest,
+        quote_store::QuoteStore,
+    },
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let args = parse_args(env::args().skip(1))?;
+    let backfill = Backfill {
+        store: QuoteStore::open(
+            env::var("QUOTES_DB_PATH")
+                .unwrap_or("quotes.db".into()),
+        )?,
+        api_client: ApiClient::new(),
+        coin_watch_client: CoinWatchClient::new(),
+    };
+
+    let id = match args.get("resume") {
+        Some(id) => {
+            let id =
+                id.parse().context("Invalid job id")?;
+            backfill.add_credits(
+                id,
+                number(&args, "budget")?.unwrap_or(0),
+            )?;
+            id
+        }
+        None => {
+            let job = backfill.create_job(
+                CreateBackfillRequest {
+                    codes: parse_watchlist(
+                        args.get("codes").ok_or(
+                            anyhow!("Missing --codes"),
+                        )?,
+                    ),
+                    start: number(&args, "start")?.ok_or(
+                        anyhow!("Missing --start"),
+                    )?,
+                    end: number(&args, "end")?
+                        .ok_or(anyhow!("Missing --end"))?,
+                    credit_budget: number(&args, "budget")?
+                        .ok_or(anyhow!(
+                            "Missing --budget"
+                        ))?,
+                    chunk_secs: number(
+                        &args,
+                        "chunk-secs",
+                    )?,
+                    max_gap_secs: number(
+                        &args,
+                        "max-gap-secs",
+                    )?,
+                },
+            )?;
+            println!(
+                "Created backfill {} with {} chunks",
+                job.id, job.chunks_total
+            );
+            job.id
+        }
+    };
+
+    let job = backfill
+        .run(id)
+        .await?
+        .ok_or(anyhow!("No backfill with id '{id}'"))?;
+    println!("{}", serde_json::to_string_pretty(&job)?);
+    Ok(())
+}
+
+/// `--name value` pairs.
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<HashMap<String, String>> {
+    let mut parsed = HashMap::new();
+    while let Some(arg) = args.next() {
+        let name = arg.strip_prefix("--").ok_or(
+            anyhow!("Unexpected argument '{arg}'"),
+        )?;
+        let value = args
+            .next()
+            .ok_or(anyhow!("Missing value for --{name}"))?;
+        parsed.insert(name.to_string(), value);
+    }
+    Ok(parsed)
+}
+
+fn number<T: std::str::FromStr>(
+    args: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>> {
+    args.get(name)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                anyhow!("Invalid number for --{name}")
+            })
+        })
+        .transpose()
+}
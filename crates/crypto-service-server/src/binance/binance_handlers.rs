@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use crypto_service::binance_service::{
+    binance_client::BinanceClient,
+    models::{
+        Candle, DepthRequest, ExchangeInfo, ExchangeInfoRequest,
+        Kline, KlinesRequest, OrderBook, Ticker, TickerRequest,
+    },
+};
+
+use crate::{export::export_format::ExportFormat, state::AppState};
+
+/// Rolling 24 hour statistics of a Binance pair such as `BTCUSDT`.
+#[utoipa::path(
+    get,
+    path = "/v1/binance/ticker/24hr",
+    tag = "binance",
+    params(TickerRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Ticker),
+        (status = 304, description = "Matches `If-None-Match`"),
+    )
+)]
+pub async fn get_ticker(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<TickerRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .api_client
+        .get::<TickerRequest, Ticker, BinanceClient>(
+            state.binance_client.clone(),
+            "/ticker/24hr",
+            request,
+        )
+        .await
+        .map(|(_, Json(ticker))| format.respond(ticker))
+}
+
+/// Candles of a pair, oldest first.
+#[utoipa::path(
+    get,
+    path = "/v1/binance/klines",
+    tag = "binance",
+    params(KlinesRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<Candle>),
+        (status = 304, description = "Matches `If-None-Match`"),
+    )
+)]
+pub async fn get_candles(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<KlinesRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .api_client
+        .get::<KlinesRequest, Vec<Kline>, BinanceClient>(
+            state.binance_client.clone(),
+            "/klines",
+            request,
+        )
+        .await
+        .map(|(_, Json(klines))| {
+            format.respond(
+                klines
+                    .into_iter()
+                    .map(Candle::from)
+                    .collect::<Vec<_>>(),
+            )
+        })
+}
+
+/// Order book of a pair, `limit` levels per side.
+#[utoipa::path(
+    get,
+    path = "/v1/binance/depth",
+    tag = "binance",
+    params(DepthRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = OrderBook),
+    )
+)]
+pub async fn get_order_book(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<DepthRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .api_client
+        .get::<DepthRequest, OrderBook, BinanceClient>(
+            state.binance_client.clone(),
+            "/depth",
+            request,
+        )
+        .await
+        .map(|(_, Json(order_book))| format.respond(order_book))
+}
+
+/// Trading rules of one pair, or of every pair when `symbol` is
+/// left out.
+#[utoipa::path(
+    get,
+    path = "/v1/binance/exchange-info",
+    tag = "binance",
+    params(ExchangeInfoRequest),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = ExchangeInfo),
+        (status = 304, description = "Matches `If-None-Match`"),
+    )
+)]
+pub async fn get_exchange_info(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(request): Query<ExchangeInfoRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .api_client
+        .get::<ExchangeInfoRequest, ExchangeInfo, BinanceClient>(
+            state.binance_client.clone(),
+            "/exchangeInfo",
+            request,
+        )
+        .await
+        .map(|(_, Json(info))| format.respond(info))
+}
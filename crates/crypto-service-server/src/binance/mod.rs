@@ -0,0 +1 @@
+pub mod binance_handlers;
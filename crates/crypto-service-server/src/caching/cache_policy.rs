@@ -0,0 +1,302 @@
+use axum::http::Method;
+
+/// How long clients may reuse a response, picked per route from how
+/// often its upstream data changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Reusable for this many seconds, then revalidated with the
+    /// `ETag`.
+    MaxAge(u64),
+    /// Like `MaxAge`, but shared caches may keep it too.
+    Public(u64),
+    /// Always revalidated, e.g. alert rules that change on writes.
+    NoCache,
+    /// Never stored, for admin and health routes.
+    NoStore,
+}
+
+/// Live Coin Watch updates quotes about every half a minute.
+pub const QUOTE_MAX_AGE_SECS: u64 = 30;
+/// Aggregated lists carry icons and metadata that barely change.
+pub const AGGREGATED_MAX_AGE_SECS: u64 = 60;
+pub const HISTORY_MAX_AGE_SECS: u64 = 5 * 60;
+/// Snapshots are written by the collector, every five minutes by
+/// default.
+pub const LOCAL_MAX_AGE_SECS: u64 = 60;
+/// Alpha Vantage refreshes top gainers and losers a few times a
+/// day at most.
+pub const STOCKS_MAX_AGE_SECS: u64 = 15 * 60;
+/// Stock quotes, intraday bars and exchange rates move all the
+/// time, but every Alpha Vantage call counts against a small daily
+/// budget.
+pub const STOCK_QUOTE_MAX_AGE_SECS: u64 = 60;
+/// Binance market data is live, so it's only reused briefly.
+pub const MARKET_MAX_AGE_SECS: u64 = 5;
+/// Trading rules, assets and symbols change when something is
+/// listed or delisted.
+pub const LISTING_MAX_AGE_SECS: u64 = 60 * 60;
+pub const DOCS_MAX_AGE_SECS: u64 = 60 * 60;
+/// Coin icons practically never change.
+pub const ICON_MAX_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+impl CachePolicy {
+    /// Policy for a request, `None` for requests that change state
+    /// or stream, which are left alone.
+    ///
+    /// The `POST /v1/coins` routes only read, so they get `ETag`s
+    /// like their `/v2` counterparts.
+    pub fn for_request(
+        method: &Method,
+        path: &str,
+    ) -> Option<Self> {
+        if method == Method::POST {
+            return match path {
+                "/v1/coins/list/aggregated" => {
+                    Some(CachePolicy::MaxAge(
+                        AGGREGATED_MAX_AGE_SECS,
+                    ))
+                }
+                "/v1/coins/single/history" => {
+                    Some(CachePolicy::MaxAge(
+                        HISTORY_MAX_AGE_SECS,
+                    ))
+                }
+                "/v1/coins/list" | "/v1/coins/single" => {
+                    Some(CachePolicy::MaxAge(
+                        QUOTE_MAX_AGE_SECS,
+                    ))
+                }
+                _ => None,
+            };
+        }
+        if method != Method::GET && method != Method::HEAD {
+            return None;
+        }
+
+        let policy = match path {
+            "/v1/stream" | "/v1/stocks/stream" => {
+                return None
+            }
+            "/readyz" | "/metrics" => CachePolicy::NoStore,
+            "/openapi.json" | "/docs" => {
+                CachePolicy::MaxAge(DOCS_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/docs/") => {
+                CachePolicy::MaxAge(DOCS_MAX_AGE_SECS)
+            }
+            "/v1/stocks" | "/v1/stocks/daily" => {
+                CachePolicy::MaxAge(STOCKS_MAX_AGE_SECS)
+            }
+            "/v1/stocks/quote"
+            | "/v1/stocks/intraday"
+            | "/v1/alphavantage/exchange-rate" => {
+                CachePolicy::MaxAge(STOCK_QUOTE_MAX_AGE_SECS)
+            }
+            "/v1/stocks/search" => {
+                CachePolicy::MaxAge(LISTING_MAX_AGE_SECS)
+            }
+            "/v1/binance/depth" => CachePolicy::NoCache,
+            "/v1/binance/exchange-info"
+            | "/v1/coinapi/assets"
+            | "/v1/coinapi/symbols" => {
+                CachePolicy::MaxAge(LISTING_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/coinapi/icons/") => {
+                CachePolicy::MaxAge(LISTING_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/coinapi/exchangerate/") => {
+                CachePolicy::MaxAge(QUOTE_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/alphavantage/") => {
+                CachePolicy::MaxAge(STOCKS_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/binance/") => {
+                CachePolicy::MaxAge(MARKET_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/icons/") => {
+                CachePolicy::Public(ICON_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/admin") => {
+                CachePolicy::NoStore
+            }
+            _ if path.starts_with("/v1/local/") => {
+                CachePolicy::MaxAge(LOCAL_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v1/coins/")
+                && path.ends_with("/consensus") =>
+            {
+                CachePolicy::MaxAge(QUOTE_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v2/coins")
+                && path.ends_with("/history") =>
+            {
+                CachePolicy::MaxAge(HISTORY_MAX_AGE_SECS)
+            }
+            _ if path.starts_with("/v2/coins") => {
+                CachePolicy::MaxAge(QUOTE_MAX_AGE_SECS)
+            }
+            _ => CachePolicy::NoCache,
+        };
+        Some(policy)
+    }
+
+    /// `Cache-Control` value. Private unless public, since most
+    /// responses depend on the API key.
+    pub fn header_value(&self) -> String {
+        match self {
+            CachePolicy::MaxAge(secs) => {
+                format!("private, max-age={secs}")
+            }
+            CachePolicy::Public(secs) => {
+                format!("public, max-age={secs}")
+            }
+            CachePolicy::NoCache => {
+                "private, no-cache".into()
+            }
+            CachePolicy::NoStore => "no-store".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_are_short_lived() {
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v2/coins/BTC"
+            ),
+            Some(CachePolicy::MaxAge(QUOTE_MAX_AGE_SECS))
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v2/coins/BTC/history"
+            ),
+            Some(CachePolicy::MaxAge(HISTORY_MAX_AGE_SECS))
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/coins/BTC/consensus"
+            ),
+            Some(CachePolicy::MaxAge(QUOTE_MAX_AGE_SECS))
+        );
+    }
+
+    #[test]
+    fn read_only_posts_are_cached() {
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::POST,
+                "/v1/coins/list/aggregated"
+            ),
+            Some(CachePolicy::MaxAge(
+                AGGREGATED_MAX_AGE_SECS
+            ))
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::POST,
+                "/v1/alerts"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn streams_and_writes_are_left_alone() {
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/stream"
+            ),
+            None
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::DELETE,
+                "/v1/alerts/1"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn order_books_are_always_revalidated() {
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/binance/depth"
+            ),
+            Some(CachePolicy::NoCache)
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/binance/klines"
+            ),
+            Some(CachePolicy::MaxAge(MARKET_MAX_AGE_SECS))
+        );
+    }
+
+    #[test]
+    fn stock_quotes_are_reused_briefly() {
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/stocks/quote"
+            ),
+            Some(CachePolicy::MaxAge(
+                STOCK_QUOTE_MAX_AGE_SECS
+            ))
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/stocks/daily"
+            ),
+            Some(CachePolicy::MaxAge(STOCKS_MAX_AGE_SECS))
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/stocks/search"
+            ),
+            Some(CachePolicy::MaxAge(LISTING_MAX_AGE_SECS))
+        );
+        assert_eq!(
+            CachePolicy::for_request(
+                &Method::GET,
+                "/v1/alphavantage/fx/daily"
+            ),
+            Some(CachePolicy::MaxAge(STOCKS_MAX_AGE_SECS))
+        );
+    }
+
+    #[test]
+    fn icons_are_public() {
+        let policy = CachePolicy::for_request(
+            &Method::GET,
+            "/v1/icons/btc.png",
+        )
+        .unwrap();
+        assert_eq!(
+            policy.header_value(),
+            "public, max-age=604800"
+        );
+    }
+
+    #[test]
+    fn admin_is_never_stored() {
+        let policy = CachePolicy::for_request(
+            &Method::GET,
+            "/v1/admin/keys",
+        )
+        .unwrap();
+        assert_eq!(policy.header_value(), "no-store");
+    }
+}
@@ -0,0 +1,296 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::Request,
+    http::{
+        header, HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sha2::{Digest, Sha256};
+
+use super::cache_policy::CachePolicy;
+
+/// Largest body buffered to hash it. Bodies of unknown length,
+/// such as the streamed CSV and JSON Lines exports, are never
+/// buffered.
+pub const MAX_ETAG_BODY_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Strong ETag of a response body.
+pub fn etag(body: &[u8]) -> String {
+    format!(
+        "\"{}\"",
+        hex::encode(&Sha256::digest(body)[..16])
+    )
+}
+
+/// Whether `If-None-Match` in `headers` matches `etag`, in which
+/// case the client's copy is still good.
+pub fn is_fresh(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// Adds `Cache-Control` by [`CachePolicy`] and an `ETag` to
+/// successful responses, answering a `GET` or `HEAD` with
+/// `304 Not Modified` when its `If-None-Match` already has the
+/// body. Cached `POST`s only get the `ETag`.
+///
+/// The body is buffered to hash it, so streaming routes have no
+/// policy and are passed through, as are bodies of unknown or
+/// more than [`MAX_ETAG_BODY_BYTES`] length.
+pub async fn conditional_requests(
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(policy) = CachePolicy::for_request(
+        request.method(),
+        request.uri().path(),
+    ) else {
+        return next.run(request).await;
+    };
+    let is_read = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD
+    );
+    let request_headers = request.headers().clone();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    if let Ok(cache_control) =
+        HeaderValue::from_str(&policy.header_value())
+    {
+        parts
+            .headers
+            .insert(header::CACHE_CONTROL, cache_control);
+    }
+    parts.headers.insert(
+        header::VARY,
+        HeaderValue::from_static("accept"),
+    );
+    if policy == CachePolicy::NoStore
+        || body.size_hint().exact().map_or(true, |length| {
+            length > MAX_ETAG_BODY_BYTES
+        })
+    {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes =
+        match to_bytes(body, MAX_ETAG_BODY_BYTES as usize)
+            .await
+        {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(e.to_string()),
+                )
+                    .into_response()
+            }
+        };
+    let etag = etag(&bytes);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(header::ETAG, value);
+    }
+
+    if is_read && is_fresh(&request_headers, &etag) {
+        let mut not_modified =
+            StatusCode::NOT_MODIFIED.into_response();
+        for name in [
+            header::ETAG,
+            header::CACHE_CONTROL,
+            header::VARY,
+        ] {
+            if let Some(value) = parts.headers.remove(&name)
+            {
+                not_modified
+                    .headers_mut()
+                    .insert(name, value);
+            }
+        }
+        return not_modified;
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+
+    use super::*;
+    use crate::test_support;
+
+    fn if_none_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    async fn serve() -> String {
+        let app = Router::new()
+            .route(
+                "/v2/coins",
+                get(|| async { "[1, 2, 3]" }),
+            )
+            .route(
+                "/v1/coins/list/aggregated",
+                post(|| async { "[4, 5, 6]" }),
+            )
+            .route("/v1/admin/keys", get(|| async { "[]" }))
+            .route(
+                "/v2/coins/NOPE",
+                get(|| async { StatusCode::NOT_FOUND }),
+            )
+            .route(
+                "/v2/coins/BTC/history",
+                get(|| async {
+                    Body::from_stream(
+                        futures::stream::iter([Ok::<
+                            _,
+                            std::io::Error,
+                        >(
+                            "date,rate\n",
+                        )]),
+                    )
+                }),
+            )
+            .layer(middleware::from_fn(
+                conditional_requests,
+            ));
+        test_support::serve(app).await
+    }
+
+    #[test]
+    fn etag_is_stable_per_body() {
+        assert_eq!(etag(b"a"), etag(b"a"));
+        assert_ne!(etag(b"a"), etag(b"b"));
+    }
+
+    #[test]
+    fn is_fresh_matches_any_listed_tag() {
+        let headers = if_none_match("\"a\", W/\"b\"");
+        assert!(is_fresh(&headers, "\"b\""));
+        assert!(!is_fresh(&headers, "\"c\""));
+        assert!(is_fresh(&if_none_match("*"), "\"c\""));
+        assert!(!is_fresh(&HeaderMap::new(), "\"c\""));
+    }
+
+    #[tokio::test]
+    async fn revalidation_returns_not_modified() {
+        let base_url = serve().await;
+        let client = reqwest::Client::new();
+
+        let first = client
+            .get(format!("{base_url}/v2/coins"))
+            .send()
+            .await
+            .unwrap();
+        let tag = first.headers()["etag"].clone();
+        assert_eq!(tag, etag(b"[1, 2, 3]").as_str());
+        assert_eq!(
+            first.headers()["cache-control"],
+            "private, max-age=30"
+        );
+        assert_eq!(
+            first.text().await.unwrap(),
+            "[1, 2, 3]"
+        );
+
+        let second = client
+            .get(format!("{base_url}/v2/coins"))
+            .header("if-none-match", tag.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            second.status(),
+            reqwest::StatusCode::NOT_MODIFIED
+        );
+        assert_eq!(second.headers()["etag"], tag);
+        assert_eq!(second.text().await.unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn posts_get_an_etag_but_never_not_modified() {
+        let base_url = serve().await;
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{base_url}/v1/coins/list/aggregated"
+            ))
+            .header("if-none-match", etag(b"[4, 5, 6]"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::OK
+        );
+        assert_eq!(
+            response.headers()["etag"],
+            etag(b"[4, 5, 6]").as_str()
+        );
+        assert_eq!(
+            response.text().await.unwrap(),
+            "[4, 5, 6]"
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_bodies_are_not_buffered() {
+        let base_url = serve().await;
+        let response = reqwest::get(format!(
+            "{base_url}/v2/coins/BTC/history"
+        ))
+        .await
+        .unwrap();
+        assert!(!response.headers().contains_key("etag"));
+        assert!(response
+            .headers()
+            .contains_key("cache-control"));
+        assert_eq!(
+            response.text().await.unwrap(),
+            "date,rate\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_and_admin_get_no_etag() {
+        let base_url = serve().await;
+        let client = reqwest::Client::new();
+
+        let missing = client
+            .get(format!("{base_url}/v2/coins/NOPE"))
+            .send()
+            .await
+            .unwrap();
+        assert!(!missing.headers().contains_key("etag"));
+
+        let admin = client
+            .get(format!("{base_url}/v1/admin/keys"))
+            .send()
+            .await
+            .unwrap();
+        assert!(!admin.headers().contains_key("etag"));
+        assert_eq!(
+            admin.headers()["cache-control"],
+            "no-store"
+        );
+    }
+}
@@ -0,0 +1,2 @@
+pub mod cache_policy;
+pub mod etag_middleware;
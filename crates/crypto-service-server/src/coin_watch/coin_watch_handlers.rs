@@ -1,114 +1,366 @@
-use crate::state::AppState;
-use axum::{extract::State, http::StatusCode, Json};
-use crypto_service::coin_watch_service::{coin_watch_client::CoinWatchClient, models::{
-    AggregatedCoinInformation, Coin, CoinHistoryRequest,
+use crate::{
+    api_client::quota::Priority,
+    export::export_format::ExportFormat,
+    icons::models::{icon_url, IconFormat, IconMode, IconsParams},
+    providers::{
+        market_data::Sourced,
+        models::{ConsensusParams, SourceParams},
+    },
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use crypto_service::{coin_watch_service::models::{
+    AggregatedCoinInformation, Coin, CoinHistory, CoinHistoryRequest,
     CoinMeta, CoinMetaRequest, ListOfCoinsRequest,
-}};
+}, consensus::ConsensusQuote, data_source::DataSource};
 
+/// Coins from the first source in `COIN_DATA_SOURCES` that has them,
+/// named in `x-data-source`.
+#[utoipa::path(
+    post,
+    path = "/v1/coins/list",
+    tag = "coins",
+    request_body = ListOfCoinsRequest,
+    params(SourceParams),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<Coin>,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
 pub async fn get_list_of_coins(
     State(state): State<AppState>,
+    format: ExportFormat,
+    Query(source): Query<SourceParams>,
     Json(body): Json<ListOfCoinsRequest>,
-) -> Result<
-    (StatusCode, Json<Vec<Coin>>),
-    (StatusCode, Json<String>),
-> {
-    state
-        .api_client
-        .post::<Vec<Coin>, CoinWatchClient, ListOfCoinsRequest>(
-            state.coin_watch_client,
-            "/coins/list",
-            body,
-        )
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch_list_of_coins(&state, source.source, body)
         .await
+        .map(|coins| coins.respond(format))
 }
 
+/// A single coin with its metadata.
+#[utoipa::path(
+    post,
+    path = "/v1/coins/single",
+    tag = "coins",
+    request_body = CoinMetaRequest,
+    params(IconsParams, SourceParams),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = CoinMeta,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
 pub async fn get_coin_meta_info(
     State(state): State<AppState>,
+    format: ExportFormat,
+    Query(params): Query<IconsParams>,
+    Query(source): Query<SourceParams>,
     Json(body): Json<CoinMetaRequest>,
-) -> Result<
-    (StatusCode, Json<CoinMeta>),
-    (StatusCode, Json<String>),
-> {
-    state
-        .api_client
-        .post::<CoinMeta, CoinWatchClient, CoinMetaRequest>(
-            state.coin_watch_client,
-            "/coins/single",
-            body,
-        )
-        .await
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let mut coin_meta =
+        fetch_coin_meta(&state, source.source, &body.code).await?;
+    params
+        .icons
+        .unwrap_or_default()
+        .apply(&mut coin_meta.value, &body.code);
+    Ok(coin_meta.respond(format))
 }
 
+/// History of a coin, also recorded in the local store.
+#[utoipa::path(
+    post,
+    path = "/v1/coins/single/history",
+    tag = "coins",
+    request_body = CoinHistoryRequest,
+    params(SourceParams),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = CoinHistory,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
 pub async fn get_coin_history_info(
     State(state): State<AppState>,
+    format: ExportFormat,
+    Query(source): Query<SourceParams>,
     Json(body): Json<CoinHistoryRequest>,
-) -> Result<
-    (StatusCode, Json<CoinMeta>),
-    (StatusCode, Json<String>),
-> {
-    state
-        .api_client
-        .post::<CoinMeta, CoinWatchClient, CoinHistoryRequest>(
-            state.coin_watch_client,
-            "/coins/single/history",
-            body,
-        )
+) -> Result<Response, (StatusCode, Json<String>)> {
+    fetch_coin_history(&state, source.source, body)
         .await
+        .map(|history| history.respond(format))
 }
 
+/// Median of the rates every configured source quotes for a coin,
+/// leaving out sources that stray too far from the rest.
+#[utoipa::path(
+    get,
+    path = "/v1/coins/{code}/consensus",
+    tag = "coins",
+    params(
+        ("code" = String, Path, description = "Coin code such as `BTC`"),
+        ConsensusParams,
+    ),
+    responses(
+        (status = 200, body = ConsensusQuote),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 429, description = "Every source's credits are used up", body = String),
+        (status = 502, description = "No source had a fresh price", body = String),
+    )
+)]
+pub async fn get_consensus_quote(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(params): Query<ConsensusParams>,
+) -> Result<(StatusCode, Json<ConsensusQuote>), (StatusCode, Json<String>)> {
+    let consensus = state
+        .providers
+        .consensus(&code, params.max_deviation)
+        .await?;
+    Ok((StatusCode::OK, Json(consensus)))
+}
+
+/// Coins from the first source in `COIN_DATA_SOURCES` that lists
+/// them, joined with their metadata, one upstream call per coin.
+/// Listed coins without a code or rate are left out.
+#[utoipa::path(
+    post,
+    path = "/v1/coins/list/aggregated",
+    tag = "coins",
+    request_body = ListOfCoinsRequest,
+    params(IconsParams),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<AggregatedCoinInformation>),
+        (status = 429, description = "The upstream's credits are running low", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
 pub async fn get_aggregated_coin_list(
     State(state): State<AppState>,
+    format: ExportFormat,
+    Query(params): Query<IconsParams>,
     Json(body): Json<ListOfCoinsRequest>,
-) -> Result<Json<Vec<AggregatedCoinInformation>>, StatusCode>
-{
-    // let body = ListOfCoinsRequest::new(body);
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let icons = params.icons.unwrap_or_default();
 
+    // One call per coin, so it's the first thing to go when credits
+    // run low.
     let list_of_coins = state
-        .api_client
-        .post::<Vec<Coin>, CoinWatchClient, ListOfCoinsRequest>(
-            state.clone().coin_watch_client,
-            "/coins/list",
-            body,
-        )
-        .await
-        .expect("Failed to receive result");
+        .providers
+        .list_coins_with_priority(None, &body, Priority::Background)
+        .await?;
 
-    let mut coin_meta: Vec<CoinMeta> = vec![];
-    for coin in &list_of_coins.1 .0 {
-        let coin_body = CoinMetaRequest::new(
-            coin.code.clone().unwrap(),
-        );
-        coin_meta.push(
-            state
-                .api_client
-                .post::<CoinMeta, CoinWatchClient, CoinMetaRequest>(
-                    state.clone().coin_watch_client,
-                    "/coins/single",
-                    coin_body,
-                )
-                .await
-                .map(|x| x.1 .0)
-                .unwrap(),
-        );
-    }
     let mut list_of_aggregated_coins: Vec<
         AggregatedCoinInformation,
     > = vec![];
-    for (idx, coin) in list_of_coins.1 .0.iter().enumerate()
-    {
+    for coin in list_of_coins.value {
+        let (Some(code), Some(rate)) = (coin.code, coin.rate)
+        else {
+            continue;
+        };
+        let coin_meta = state
+            .providers
+            .coin_meta_with_priority(None, &code, Priority::Background)
+            .await?
+            .value;
         list_of_aggregated_coins.push(
             AggregatedCoinInformation {
-                name: coin_meta[idx].name.clone().unwrap_or("".into()),
-                symbol: coin_meta[idx]
+                name: coin_meta.name.unwrap_or("".into()),
+                symbol: coin_meta
                     .symbol
-                    .clone()
                     .unwrap_or("0".to_string()),
-                rank: coin_meta[idx].rank.unwrap_or(0.into()),
-                rate: coin.rate.unwrap(),
-                color: coin_meta[idx].color.clone().unwrap_or("".into()),
-                png64: coin_meta[idx].png64.clone().unwrap_or("".into()),
+                rank: coin_meta.rank.unwrap_or(0.into()),
+                rate,
+                color: coin_meta.color.unwrap_or("".into()),
+                png64: match icons {
+                    IconMode::Url => {
+                        icon_url(&code, IconFormat::Png)
+                    }
+                    _ => coin_meta.png64.unwrap_or("".into()),
+                },
             },
         )
     }
-    Ok(axum::Json(list_of_aggregated_coins))
+    Ok(format.respond(list_of_aggregated_coins))
+}
+
+// Shared by the v1 and v2 routes, which only differ in how the
+// request is put together. Without a `source` the providers are
+// tried in the configured order.
+
+pub async fn fetch_list_of_coins(
+    state: &AppState,
+    source: Option<DataSource>,
+    body: ListOfCoinsRequest,
+) -> Result<Sourced<Vec<Coin>>, (StatusCode, Json<String>)> {
+    Ok(state.providers.list_coins(source, &body).await?)
+}
+
+pub async fn fetch_coin_meta(
+    state: &AppState,
+    source: Option<DataSource>,
+    code: &str,
+) -> Result<Sourced<CoinMeta>, (StatusCode, Json<String>)> {
+    Ok(state.providers.coin_meta(source, code).await?)
+}
+
+/// Fetches history and records it in the local store.
+pub async fn fetch_coin_history(
+    state: &AppState,
+    source: Option<DataSource>,
+    body: CoinHistoryRequest,
+) -> Result<Sourced<CoinHistory>, (StatusCode, Json<String>)> {
+    let code = body.code().to_string();
+    let history =
+        state.providers.coin_history(source, &body).await?;
+
+    // Keep what we paid credits for, the response is served even
+    // if storing it fails.
+    if let Some(points) = &history.value.history {
+        if let Err(e) = state.quote_store.record_history(&code, points) {
+            println!("Failed to store history for {code}: {e}");
+        }
+    }
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{routing::post, Router};
+    use crypto_service::{
+        alphavantage_service::alpha_vantage_client::AlphaVantageClient,
+        binance_service::binance_client::BinanceClient,
+        coin_watch_service::coin_watch_client::CoinWatchClient,
+        coinapi_service::coinapi_client::CoinApiClient,
+        coingecko_service::coingecko_client::{
+            CoinGeckoClient, CoinGeckoPlan,
+        },
+    };
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        alerts::{alert_engine::AlertEngine, notifier::LogNotifier},
+        alphavantage_api::trades_feed::TradesFeed,
+        api_client::api_client::ApiClient,
+        auth::api_keys::ApiKeys,
+        icons::icon_cache::{
+            IconCache, DEFAULT_ICON_BASE_URL,
+        },
+        providers::{
+            live_coin_watch::LiveCoinWatchProvider,
+            market_data::FailoverProvider,
+        },
+        store::quote_store::QuoteStore,
+        stream::price_hub::PriceHub,
+        test_support,
+        webhooks::webhook_dispatcher::WebhookDispatcher,
+    };
+
+    /// The aggregated route in front of a local stand-in for Live
+    /// Coin Watch, listing a coin without a code and one without a
+    /// rate.
+    async fn serve() -> String {
+        let upstream = Router::new()
+            .route(
+                "/coins/list",
+                post(|| async {
+                    Json(json!([
+                        {"code": "BTC", "rate": 64000.0, "volume": null, "cap": null, "delta": {}},
+                        {"code": null, "rate": 1.0, "volume": null, "cap": null, "delta": {}},
+                        {"code": "ETH", "rate": null, "volume": null, "cap": null, "delta": {}},
+                    ]))
+                }),
+            )
+            .route(
+                "/coins/single",
+                post(|Json(body): Json<Value>| async move {
+                    Json(json!({
+                        "name": body["code"],
+                        "rank": 1,
+                        "rate": 64000.0,
+                    }))
+                }),
+            );
+        let mut coin_watch_client =
+            CoinWatchClient::new_with_key("key".into());
+        coin_watch_client.base_url =
+            test_support::serve(upstream).await;
+
+        let api_client = ApiClient::new();
+        let store = QuoteStore::open_in_memory().unwrap();
+        let state = AppState::new(
+            AlphaVantageClient::new_with_key(String::new()),
+            coin_watch_client.clone(),
+            CoinGeckoClient::new_with_key(
+                String::new(),
+                CoinGeckoPlan::Public,
+            ),
+            BinanceClient::new_with_key(String::new()),
+            CoinApiClient::new_with_key(String::new()),
+            api_client.clone(),
+            AlertEngine::new(Arc::new(LogNotifier)),
+            WebhookDispatcher::default(),
+            PriceHub::default(),
+            TradesFeed::default(),
+            store.clone(),
+            ApiKeys::load(
+                "/nonexistent/api_keys.json",
+                store,
+                0,
+            )
+            .unwrap(),
+            IconCache::new(
+                std::env::temp_dir(),
+                DEFAULT_ICON_BASE_URL,
+            ),
+            FailoverProvider::new(
+                vec![Arc::new(LiveCoinWatchProvider::new(
+                    api_client,
+                    coin_watch_client,
+                ))],
+                vec![DataSource::LiveCoinWatch],
+                60_000,
+                0.02,
+            ),
+        );
+
+        let app = Router::new()
+            .route(
+                "/v1/coins/list/aggregated",
+                post(get_aggregated_coin_list),
+            )
+            .with_state(state);
+        test_support::serve(app).await
+    }
+
+    #[tokio::test]
+    async fn aggregated_list_skips_incomplete_coins() {
+        let base_url = serve().await;
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{base_url}/v1/coins/list/aggregated"
+            ))
+            .json(&ListOfCoinsRequest::new(3))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let coins: Vec<AggregatedCoinInformation> =
+            response.json().await.unwrap();
+        assert_eq!(coins.len(), 1);
+        assert_eq!(coins[0].name, "BTC");
+        assert_eq!(coins[0].rate, 64000.0);
+    }
 }
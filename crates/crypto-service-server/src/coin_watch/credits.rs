@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use axum::{http::StatusCode, Json};
+use crypto_service::coin_watch_service::{
+    coin_watch_client::CoinWatchClient, models::Credits,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    alerts::alert_engine::now_millis,
+    api_client::api_client::ApiClient, state::AppState,
+};
+
+/// Fetches `/credits` and replaces the locally counted Live Coin
+/// Watch budget with it. `/credits` is free, so it goes out even
+/// when the local count has run out, which is when a wrong count
+/// needs correcting most.
+pub async fn sync_credits(
+    api_client: &ApiClient,
+    coin_watch_client: CoinWatchClient,
+) -> Result<Credits, (StatusCode, Json<String>)> {
+    let base_url = coin_watch_client.base_url.clone();
+    let (_, Json(credits)) = api_client
+        .post_unmetered::<Credits, CoinWatchClient, Value>(
+            coin_watch_client,
+            "/credits",
+            json!({}),
+        )
+        .await?;
+    api_client.quota.sync(
+        &base_url,
+        credits.daily_credits_remaining,
+        credits.daily_credits_limit,
+        now_millis(),
+    );
+    Ok(credits)
+}
+
+/// Keeps the Live Coin Watch budget in line with `/credits`, which
+/// also counts calls made with the same key elsewhere.
+pub async fn run_credits_sync(
+    state: AppState,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err((status, Json(message))) = sync_credits(
+            &state.api_client,
+            state.coin_watch_client.clone(),
+        )
+        .await
+        {
+            println!("Failed to sync credits: {status} {message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Router};
+
+    use super::*;
+    use crate::{
+        api_client::quota::BudgetSource, test_support,
+    };
+
+    #[tokio::test]
+    async fn sync_replaces_exhausted_local_budget() {
+        let app = Router::new().route(
+            "/credits",
+            post(|| async {
+                Json(json!({
+                    "dailyCreditsRemaining": 9000,
+                    "dailyCreditsLimit": 10000
+                }))
+            }),
+        );
+        let base_url = test_support::serve(app).await;
+
+        let mut coin_watch_client =
+            CoinWatchClient::new_with_key("key".into());
+        coin_watch_client.base_url = base_url;
+        let api_client = ApiClient::new();
+        api_client.quota.track(
+            "live_coin_watch",
+            &coin_watch_client.base_url,
+            0,
+            BudgetSource::Upstream,
+            now_millis(),
+        );
+
+        let credits =
+            sync_credits(&api_client, coin_watch_client)
+                .await
+                .unwrap();
+        assert_eq!(credits.daily_credits_remaining, 9000);
+        let budget = &api_client.quota.budgets()[0];
+        assert_eq!(
+            (budget.remaining, budget.limit),
+            (9000, 10000)
+        );
+    }
+}
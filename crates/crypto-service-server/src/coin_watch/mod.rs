@@ -1 +1,2 @@
-pub mod coin_watch_handlers;
\ No newline at end of file
+pub mod coin_watch_handlers;
+pub mod credits;
@@ -0,0 +1,340 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use crypto_service::{
+    client_trait::QueryItems,
+    coinapi_service::{
+        coinapi_client::CoinApiClient,
+        models::{
+            filter_icons, AssetIcon, AssetsRequest, CoinApiAsset,
+            CoinApiSymbol, ExchangeRate, ExchangeRateRequest,
+            SymbolsRequest,
+        },
+    },
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    api_client::{api_client::ApiClient, quota::Priority},
+    state::AppState,
+};
+
+/// Assets CoinAPI knows of, crypto and fiat.
+#[utoipa::path(
+    get,
+    path = "/v1/coinapi/assets",
+    tag = "coinapi",
+    params(AssetsRequest),
+    responses(
+        (status = 200, body = Vec<CoinApiAsset>),
+        (status = 429, description = "CoinAPI calls are used up", body = String),
+        (status = 503, description = "No CoinAPI key is configured", body = String),
+    )
+)]
+pub async fn get_assets(
+    State(state): State<AppState>,
+    Query(request): Query<AssetsRequest>,
+) -> Result<(StatusCode, Json<Vec<CoinApiAsset>>), (StatusCode, Json<String>)> {
+    fetch(&state.api_client, &state.coinapi_client, "/assets", request)
+        .await
+        .map(|assets| (StatusCode::OK, Json(assets)))
+}
+
+/// Markets CoinAPI tracks, such as `BINANCE_SPOT_BTC_USDT`.
+#[utoipa::path(
+    get,
+    path = "/v1/coinapi/symbols",
+    tag = "coinapi",
+    params(SymbolsRequest),
+    responses(
+        (status = 200, body = Vec<CoinApiSymbol>),
+        (status = 429, description = "CoinAPI calls are used up", body = String),
+        (status = 503, description = "No CoinAPI key is configured", body = String),
+    )
+)]
+pub async fn get_symbols(
+    State(state): State<AppState>,
+    Query(request): Query<SymbolsRequest>,
+) -> Result<(StatusCode, Json<Vec<CoinApiSymbol>>), (StatusCode, Json<String>)> {
+    fetch(&state.api_client, &state.coinapi_client, "/symbols", request)
+        .await
+        .map(|symbols| (StatusCode::OK, Json(symbols)))
+}
+
+/// Rate of `base` in `quote`, e.g. `BTC` in `USD`.
+#[utoipa::path(
+    get,
+    path = "/v1/coinapi/exchangerate/{base}/{quote}",
+    tag = "coinapi",
+    params(
+        ("base" = String, Path, description = "Asset id such as `BTC`"),
+        ("quote" = String, Path, description = "Asset id such as `USD`"),
+        ExchangeRateRequest,
+    ),
+    responses(
+        (status = 200, body = ExchangeRate),
+        (status = 429, description = "CoinAPI calls are used up", body = String),
+        (status = 503, description = "No CoinAPI key is configured", body = String),
+    )
+)]
+pub async fn get_exchange_rate(
+    State(state): State<AppState>,
+    Path((base, quote)): Path<(String, String)>,
+    Query(request): Query<ExchangeRateRequest>,
+) -> Result<(StatusCode, Json<ExchangeRate>), (StatusCode, Json<String>)> {
+    fetch(
+        &state.api_client,
+        &state.coinapi_client,
+        &format!(
+            "/exchangerate/{}/{}",
+            base.to_uppercase(),
+            quote.to_uppercase()
+        ),
+        request,
+    )
+    .await
+    .map(|rate| (StatusCode::OK, Json(rate)))
+}
+
+/// Icon URLs of assets at `size` pixels, filtered here since
+/// CoinAPI always returns all of them.
+#[utoipa::path(
+    get,
+    path = "/v1/coinapi/icons/{size}",
+    tag = "coinapi",
+    params(
+        ("size" = u32, Path, description = "Width in pixels, e.g. `32`"),
+        AssetsRequest,
+    ),
+    responses(
+        (status = 200, body = Vec<AssetIcon>),
+        (status = 429, description = "CoinAPI calls are used up", body = String),
+        (status = 503, description = "No CoinAPI key is configured", body = String),
+    )
+)]
+pub async fn get_asset_icons(
+    State(state): State<AppState>,
+    Path(size): Path<u32>,
+    Query(request): Query<AssetsRequest>,
+) -> Result<(StatusCode, Json<Vec<AssetIcon>>), (StatusCode, Json<String>)> {
+    fetch_asset_icons(&state.api_client, &state.coinapi_client, size, request)
+        .await
+        .map(|icons| (StatusCode::OK, Json(icons)))
+}
+
+pub async fn fetch_asset_icons(
+    api_client: &ApiClient,
+    client: &CoinApiClient,
+    size: u32,
+    request: AssetsRequest,
+) -> Result<Vec<AssetIcon>, (StatusCode, Json<String>)> {
+    let icons = fetch(
+        api_client,
+        client,
+        &format!("/assets/icons/{size}"),
+        AssetsRequest::default(),
+    )
+    .await?;
+    Ok(filter_icons(icons, request.filter_asset_id.as_deref()))
+}
+
+/// Every CoinAPI call needs a key, so calls without one are
+/// refused here instead of spending a request on a `401`.
+pub async fn fetch<T, U>(
+    api_client: &ApiClient,
+    client: &CoinApiClient,
+    path: &str,
+    query: T,
+) -> Result<U, (StatusCode, Json<String>)>
+where
+    T: QueryItems<Query = String> + std::fmt::Debug + Serialize,
+    U: DeserializeOwned,
+{
+    fetch_with_priority(api_client, client, path, query, Priority::Normal)
+        .await
+}
+
+/// Like [`fetch`], made at `priority`.
+pub async fn fetch_with_priority<T, U>(
+    api_client: &ApiClient,
+    client: &CoinApiClient,
+    path: &str,
+    query: T,
+    priority: Priority,
+) -> Result<U, (StatusCode, Json<String>)>
+where
+    T: QueryItems<Query = String> + std::fmt::Debug + Serialize,
+    U: DeserializeOwned,
+{
+    if !client.has_key() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json("No CoinAPI key configured, set COINAPI_API_KEY".into()),
+        ));
+    }
+    api_client
+        .get_with_priority::<T, U, CoinApiClient>(
+            client.clone(),
+            path,
+            query,
+            priority,
+        )
+        .await
+        .map(|(_, Json(body))| body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{
+        http::HeaderMap, routing::get, Router,
+    };
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support;
+
+    /// Local stand-in for CoinAPI that only answers with the right
+    /// key.
+    async fn client(key: &str) -> CoinApiClient {
+        let app = Router::new()
+            .route(
+                "/assets",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    Json(json!([{
+                        "asset_id": query["filter_asset_id"],
+                        "name": "Bitcoin",
+                        "type_is_crypto": 1,
+                        "price_usd": 69512.3
+                    }]))
+                }),
+            )
+            .route(
+                "/symbols",
+                get(|| async {
+                    Json(json!([{
+                        "symbol_id": "BINANCE_SPOT_BTC_USDT",
+                        "exchange_id": "BINANCE",
+                        "symbol_type": "SPOT",
+                        "asset_id_base": "BTC",
+                        "asset_id_quote": "USDT"
+                    }]))
+                }),
+            )
+            .route(
+                "/exchangerate/:base/:quote",
+                get(|Path((base, quote)): Path<(String, String)>, Query(query): Query<HashMap<String, String>>| async move {
+                    Json(json!({
+                        "time": query.get("time").cloned().unwrap_or("2024-03-28T00:00:00.0000000Z".into()),
+                        "asset_id_base": base,
+                        "asset_id_quote": quote,
+                        "rate": 69500.5
+                    }))
+                }),
+            )
+            .route(
+                "/assets/icons/:size",
+                get(|Path(size): Path<u32>, headers: HeaderMap| async move {
+                    if headers.get("x-coinapi-key").unwrap() != "TEST" {
+                        return Json(Value::Null);
+                    }
+                    Json(json!(["BTC", "ETH", "DOGE"].map(|asset_id| json!({
+                        "asset_id": asset_id,
+                        "url": format!("https://example.com/{size}/{asset_id}.png")
+                    }))))
+                }),
+            );
+        let base_url = test_support::serve(app).await;
+
+        let mut client = CoinApiClient::new_with_key(key.into());
+        client.base_url = base_url;
+        client
+    }
+
+    #[tokio::test]
+    async fn assets_are_filtered_upstream() {
+        let assets: Vec<CoinApiAsset> = fetch(
+            &ApiClient::new(),
+            &client("TEST").await,
+            "/assets",
+            AssetsRequest {
+                filter_asset_id: Some("btc".into()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(assets[0].asset_id, "BTC");
+        assert!(assets[0].type_is_crypto);
+    }
+
+    #[tokio::test]
+    async fn symbols() {
+        let symbols: Vec<CoinApiSymbol> = fetch(
+            &ApiClient::new(),
+            &client("TEST").await,
+            "/symbols",
+            SymbolsRequest::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(symbols[0].asset_id_quote, Some("USDT".into()));
+    }
+
+    #[tokio::test]
+    async fn exchange_rate_at_time() {
+        let rate: ExchangeRate = fetch(
+            &ApiClient::new(),
+            &client("TEST").await,
+            "/exchangerate/BTC/USD",
+            ExchangeRateRequest {
+                time: Some("2024-01-01T00:00:00Z".into()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(rate.time, "2024-01-01T00:00:00Z");
+        assert_eq!(
+            (rate.asset_id_base.as_str(), rate.asset_id_quote.as_str()),
+            ("BTC", "USD")
+        );
+    }
+
+    #[tokio::test]
+    async fn asset_icons_are_filtered_here() {
+        let icons = fetch_asset_icons(
+            &ApiClient::new(),
+            &client("TEST").await,
+            32,
+            AssetsRequest {
+                filter_asset_id: Some("eth".into()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            icons,
+            vec![AssetIcon {
+                asset_id: "ETH".into(),
+                url: "https://example.com/32/ETH.png".into(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_without_key() {
+        let result: Result<Vec<CoinApiAsset>, _> = fetch(
+            &ApiClient::new(),
+            &client("").await,
+            "/assets",
+            AssetsRequest::default(),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
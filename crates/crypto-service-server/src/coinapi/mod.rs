@@ -0,0 +1 @@
+pub mod coinapi_handlers;
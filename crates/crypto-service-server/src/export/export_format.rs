@@ -0,0 +1,239 @@
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use crypto_service::tabular::{
+    csv_header, csv_line, json_line, Tabular,
+};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+
+/// Lines are sent in chunks of this many rows, so long histories
+/// are streamed to the client as they are encoded.
+const ROWS_PER_CHUNK: usize = 512;
+
+/// Response format picked from the request's `Accept` header.
+/// Anything not asking for CSV or JSON Lines gets JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    JsonLines,
+}
+
+impl ExportFormat {
+    /// Picks the supported media range with the highest `q`
+    /// value, the first one listed wins on equal quality.
+    pub fn from_accept(accept: &str) -> Self {
+        let mut ranges: Vec<(f32, ExportFormat)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut params =
+                    range.split(';').map(str::trim);
+                let format = match params.next()? {
+                    "text/csv" => ExportFormat::Csv,
+                    "application/x-ndjson"
+                    | "application/jsonl" => {
+                        ExportFormat::JsonLines
+                    }
+                    "application/json"
+                    | "application/*" | "*/*" => {
+                        ExportFormat::Json
+                    }
+                    _ => return None,
+                };
+                let quality = params
+                    .find_map(|param| {
+                        param.strip_prefix("q=")
+                    })
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((quality, format))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranges
+            .first()
+            .map(|(_, format)| *format)
+            .unwrap_or(ExportFormat::Json)
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonLines => {
+                "application/x-ndjson"
+            }
+        }
+    }
+
+    pub fn respond<T>(self, value: T) -> Response
+    where
+        T: Tabular + Serialize + Send + 'static,
+    {
+        match self {
+            ExportFormat::Json => {
+                (StatusCode::OK, Json(value))
+                    .into_response()
+            }
+            ExportFormat::Csv => self.stream_lines(
+                Some(csv_header::<T>()),
+                value.into_rows().map(csv_line),
+            ),
+            ExportFormat::JsonLines => self.stream_lines(
+                None,
+                value.into_rows().map(json_line::<T>),
+            ),
+        }
+    }
+
+    fn stream_lines(
+        self,
+        header: Option<String>,
+        lines: impl Iterator<Item = String> + Send + 'static,
+    ) -> Response {
+        let chunks =
+            stream::iter(header.into_iter().chain(lines))
+                .chunks(ROWS_PER_CHUNK)
+                .map(|lines| {
+                    Ok::<_, Infallible>(lines.concat())
+                });
+
+        (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, self.content_type())],
+            Body::from_stream(chunks),
+        )
+            .into_response()
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ExportFormat {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(ExportFormat::from_accept)
+            .unwrap_or(ExportFormat::Json))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use crypto_service::coin_watch_service::models::{
+        Coin, Delta,
+    };
+
+    fn coins() -> Vec<Coin> {
+        vec![Coin::new(
+            Some("BTC".into()),
+            Some(64000.0),
+            None,
+            None,
+            Delta::new(None, None, None, None, None, None),
+        )]
+    }
+
+    async fn body(response: Response) -> String {
+        let bytes =
+            to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn accept_csv() {
+        assert_eq!(
+            ExportFormat::from_accept("text/csv"),
+            ExportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn accept_ndjson() {
+        assert_eq!(
+            ExportFormat::from_accept(
+                "application/x-ndjson"
+            ),
+            ExportFormat::JsonLines
+        );
+    }
+
+    #[test]
+    fn accept_anything_is_json() {
+        assert_eq!(
+            ExportFormat::from_accept("*/*"),
+            ExportFormat::Json
+        );
+    }
+
+    #[test]
+    fn accept_unsupported_is_json() {
+        assert_eq!(
+            ExportFormat::from_accept("text/html"),
+            ExportFormat::Json
+        );
+    }
+
+    #[test]
+    fn accept_highest_quality_wins() {
+        assert_eq!(
+            ExportFormat::from_accept(
+                "application/json;q=0.5, text/csv;q=0.9"
+            ),
+            ExportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn accept_first_listed_wins_on_equal_quality() {
+        assert_eq!(
+            ExportFormat::from_accept(
+                "application/x-ndjson, text/csv"
+            ),
+            ExportFormat::JsonLines
+        );
+    }
+
+    #[tokio::test]
+    async fn respond_csv() {
+        let response = ExportFormat::Csv.respond(coins());
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        assert_eq!(
+            body(response).await,
+            "code,rate,volume,cap,delta_hour,delta_day,delta_week,delta_month,delta_quarter,delta_year\n\
+            BTC,64000.0,,,,,,,,\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn respond_json_lines() {
+        let response =
+            ExportFormat::JsonLines.respond(coins());
+        assert!(body(response).await.starts_with(
+            "{\"code\":\"BTC\",\"rate\":64000.0,"
+        ));
+    }
+}
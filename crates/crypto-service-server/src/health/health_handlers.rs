@@ -0,0 +1,170 @@
+use std::fmt::Write;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{api_client::quota::Budget, state::AppState};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct Readiness {
+    /// `"degraded"` once any upstream budget is used up for the day,
+    /// `"ready"` otherwise.
+    pub status: String,
+    pub budgets: Vec<Budget>,
+}
+
+impl Readiness {
+    pub fn from_budgets(budgets: Vec<Budget>) -> Self {
+        let status = if budgets
+            .iter()
+            .any(|budget| budget.remaining == 0)
+        {
+            "degraded"
+        } else {
+            "ready"
+        };
+        Self {
+            status: status.into(),
+            budgets,
+        }
+    }
+}
+
+/// Stays `200 OK` when degraded: cached and local routes keep
+/// working without upstream credits.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Readiness and upstream budgets", body = Readiness),
+    )
+)]
+pub async fn readyz(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Readiness>) {
+    (
+        StatusCode::OK,
+        Json(Readiness::from_budgets(
+            state.api_client.quota.budgets(),
+        )),
+    )
+}
+
+pub async fn metrics(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4",
+        )],
+        render_metrics(&state.api_client.quota.budgets()),
+    )
+}
+
+/// Name, type, help text and value of a metric family.
+type Family = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Budget) -> u64,
+);
+
+const FAMILIES: [Family; 3] = [
+    (
+        "upstream_credits_remaining",
+        "gauge",
+        "Calls left in today's upstream budget.",
+        |budget| budget.remaining,
+    ),
+    (
+        "upstream_credits_limit",
+        "gauge",
+        "Daily upstream call budget.",
+        |budget| budget.limit,
+    ),
+    (
+        "upstream_calls_refused_total",
+        "counter",
+        "Upstream calls refused to protect the budget.",
+        |budget| budget.refused,
+    ),
+];
+
+/// Budgets in the Prometheus text format, labelled by upstream.
+pub fn render_metrics(budgets: &[Budget]) -> String {
+    let mut out = String::new();
+    for (name, kind, help, value) in FAMILIES {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for budget in budgets {
+            let _ = writeln!(
+                out,
+                "{name}{{upstream=\"{}\"}} {}",
+                budget.name,
+                value(budget)
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::quota::BudgetSource;
+
+    fn budget(name: &str, remaining: u64) -> Budget {
+        Budget {
+            name: name.into(),
+            base_url: format!("https://{name}"),
+            source: BudgetSource::Local,
+            limit: 25,
+            remaining,
+            refused: 3,
+            resets_at: 0,
+        }
+    }
+
+    #[test]
+    fn ready_with_credits_left() {
+        let readiness =
+            Readiness::from_budgets(vec![budget("a", 1)]);
+        assert_eq!(readiness.status, "ready");
+    }
+
+    #[test]
+    fn degraded_when_a_budget_is_used_up() {
+        let readiness = Readiness::from_budgets(vec![
+            budget("a", 1),
+            budget("b", 0),
+        ]);
+        assert_eq!(readiness.status, "degraded");
+    }
+
+    #[test]
+    fn metrics_are_labelled_by_upstream() {
+        let metrics =
+            render_metrics(&[budget("alpha_vantage", 20)]);
+        assert!(metrics.contains(
+            "# TYPE upstream_credits_remaining gauge\n"
+        ));
+        assert!(metrics.contains(
+            "upstream_credits_remaining{upstream=\"alpha_vantage\"} 20\n"
+        ));
+        assert!(metrics.contains(
+            "upstream_credits_limit{upstream=\"alpha_vantage\"} 25\n"
+        ));
+        assert!(metrics.contains(
+            "upstream_calls_refused_total{upstream=\"alpha_vantage\"} 3\n"
+        ));
+    }
+}
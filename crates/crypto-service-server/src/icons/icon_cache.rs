@@ -0,0 +1,298 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::http::StatusCode;
+
+use super::models::IconFormat;
+
+/// Where Live Coin Watch keeps its 64px icons, named
+/// `{code}.{png|webp}` in lowercase.
+pub const DEFAULT_ICON_BASE_URL: &str =
+    "https://lcw.nyc3.cdn.digitaloceanspaces.com/production/currencies/64";
+
+/// Largest icon fetched, far above what a 64px icon weighs.
+pub const MAX_ICON_BYTES: usize = 512 * 1024;
+
+#[derive(Debug)]
+pub enum IconError {
+    NotFound,
+    Upstream(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for IconError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            IconError::NotFound => {
+                write!(f, "No such icon")
+            }
+            IconError::Upstream(e) => {
+                write!(f, "Fetching icon failed: {e}")
+            }
+            IconError::Io(e) => {
+                write!(f, "Icon cache failed: {e}")
+            }
+        }
+    }
+}
+
+impl IconError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            IconError::NotFound => StatusCode::NOT_FOUND,
+            IconError::Upstream(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            IconError::Io(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// Icons fetched from the Live Coin Watch CDN once and served from
+/// `dir` afterwards. Icons don't cost credits, only bandwidth.
+#[derive(Debug, Clone)]
+pub struct IconCache {
+    dir: PathBuf,
+    base_url: String,
+    http_client: reqwest::Client,
+}
+
+impl IconCache {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            base_url: base_url.into(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    fn path(
+        &self,
+        code: &str,
+        format: IconFormat,
+    ) -> PathBuf {
+        self.dir
+            .join(format!("{code}.{}", format.extension()))
+    }
+
+    /// The icon for a code already checked by
+    /// [`parse_icon_file`](super::models::parse_icon_file).
+    pub async fn get(
+        &self,
+        code: &str,
+        format: IconFormat,
+    ) -> Result<Vec<u8>, IconError> {
+        let path = self.path(code, format);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => return Ok(bytes),
+            Err(e)
+                if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(IconError::Io(e)),
+        }
+
+        let response = self
+            .http_client
+            .get(format!(
+                "{}/{code}.{}",
+                self.base_url,
+                format.extension()
+            ))
+            .send()
+            .await
+            .map_err(|e| {
+                IconError::Upstream(e.to_string())
+            })?;
+        match response.status() {
+            status if status.is_success() => {}
+            // The CDN answers 403 for keys it doesn't have.
+            reqwest::StatusCode::NOT_FOUND
+            | reqwest::StatusCode::FORBIDDEN => {
+                return Err(IconError::NotFound)
+            }
+            status => {
+                return Err(IconError::Upstream(
+                    status.to_string(),
+                ))
+            }
+        }
+        let bytes = read_capped(response).await?;
+
+        write_atomically(&path, &bytes)
+            .await
+            .map_err(IconError::Io)?;
+        Ok(bytes)
+    }
+}
+
+/// The body of `response`, refused once it grows past
+/// [`MAX_ICON_BYTES`].
+async fn read_capped(
+    mut response: reqwest::Response,
+) -> Result<Vec<u8>, IconError> {
+    let too_large = || {
+        IconError::Upstream(format!(
+            "Icon is larger than {MAX_ICON_BYTES} bytes"
+        ))
+    };
+    if response.content_length().is_some_and(|length| {
+        length > MAX_ICON_BYTES as u64
+    }) {
+        return Err(too_large());
+    }
+    let mut bytes = vec![];
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| IconError::Upstream(e.to_string()))?
+    {
+        if bytes.len() + chunk.len() > MAX_ICON_BYTES {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Writes through a temporary file of its own, so concurrent
+/// readers never see half an icon and concurrent writers never
+/// share one.
+async fn write_atomically(
+    path: &Path,
+    bytes: &[u8],
+) -> io::Result<()> {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut name =
+        path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temporary = path.with_file_name(name);
+    tokio::fs::write(&temporary, bytes).await?;
+    tokio::fs::rename(&temporary, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicU32, Arc};
+
+    use axum::{
+        extract::{Path, State},
+        routing::get,
+        Router,
+    };
+
+    use super::*;
+    use crate::test_support;
+
+    async fn icon(
+        State(requests): State<Arc<AtomicU32>>,
+        Path(file): Path<String>,
+    ) -> Result<&'static [u8], StatusCode> {
+        requests.fetch_add(1, Ordering::SeqCst);
+        match file.as_str() {
+            "btc.png" => Ok(b"png bytes"),
+            "big.png" => Ok(&[0; MAX_ICON_BYTES + 1]),
+            _ => Err(StatusCode::FORBIDDEN),
+        }
+    }
+
+    async fn cache(
+        name: &str,
+    ) -> (IconCache, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let app = Router::new()
+            .route("/icons/:file", get(icon))
+            .with_state(requests.clone());
+        let base_url = format!(
+            "{}/icons",
+            test_support::serve(app).await
+        );
+
+        let dir = std::env::temp_dir().join(format!(
+            "crypto-service-icons-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        (IconCache::new(dir, base_url), requests)
+    }
+
+    #[tokio::test]
+    async fn icons_are_fetched_once() {
+        let (cache, requests) = cache("once").await;
+        for _ in 0..2 {
+            assert_eq!(
+                cache
+                    .get("btc", IconFormat::Png)
+                    .await
+                    .unwrap(),
+                b"png bytes"
+            );
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(cache
+            .path("btc", IconFormat::Png)
+            .exists());
+        let _ = std::fs::remove_dir_all(&cache.dir);
+    }
+
+    #[tokio::test]
+    async fn missing_icons_are_not_cached() {
+        let (cache, requests) = cache("missing").await;
+        for _ in 0..2 {
+            assert!(matches!(
+                cache.get("nope", IconFormat::Webp).await,
+                Err(IconError::NotFound)
+            ));
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn oversized_icons_are_refused() {
+        let (cache, _) = cache("oversized").await;
+        assert!(matches!(
+            cache.get("big", IconFormat::Png).await,
+            Err(IconError::Upstream(_))
+        ));
+        assert!(!cache
+            .path("big", IconFormat::Png)
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes_do_not_collide() {
+        let (cache, _) = cache("concurrent").await;
+        let path = cache.path("eth", IconFormat::Png);
+        let writes = (0..8).map(|_| {
+            let path = path.clone();
+            tokio::spawn(async move {
+                write_atomically(&path, b"png bytes").await
+            })
+        });
+        for write in writes.collect::<Vec<_>>() {
+            write.await.unwrap().unwrap();
+        }
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            b"png bytes"
+        );
+        let _ = std::fs::remove_dir_all(&cache.dir);
+    }
+}
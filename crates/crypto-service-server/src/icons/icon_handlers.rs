@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use super::models::parse_icon_file;
+use crate::state::AppState;
+
+/// A coin icon as an image, cached on disk. Open to everyone, since
+/// `<img>` tags can't send API keys and icons cost no credits.
+#[utoipa::path(
+    get,
+    path = "/v1/icons/{file}",
+    tag = "coins",
+    security(()),
+    params(("file" = String, Path, description = "Lowercase code and format, such as `btc.png` or `btc.webp`")),
+    responses(
+        (status = 200, description = "The icon", content_type = "image/png"),
+        (status = 404, description = "No icon for that code or format", body = String),
+    )
+)]
+pub async fn get_icon(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let Some((code, format)) = parse_icon_file(&file)
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(format!("No icon '{file}'")),
+        ));
+    };
+    state
+        .icons
+        .get(&code, format)
+        .await
+        .map(|bytes| {
+            (
+                [(
+                    header::CONTENT_TYPE,
+                    format.content_type(),
+                )],
+                bytes,
+            )
+                .into_response()
+        })
+        .map_err(|e| (e.status(), Json(e.to_string())))
+}
@@ -0,0 +1,3 @@
+pub mod icon_cache;
+pub mod icon_handlers;
+pub mod models;
@@ -0,0 +1,145 @@
+use crypto_service::coin_watch_service::models::CoinMeta;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IconFormat {
+    Png,
+    Webp,
+}
+
+impl IconFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            IconFormat::Png => "png",
+            IconFormat::Webp => "webp",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            IconFormat::Png => "image/png",
+            IconFormat::Webp => "image/webp",
+        }
+    }
+}
+
+/// How icons are returned by the coin routes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IconMode {
+    /// As Live Coin Watch sends them.
+    #[default]
+    Inline,
+    /// As `/v1/icons/{code}.{png|webp}` URLs served by us.
+    Url,
+}
+
+impl IconMode {
+    /// Points the icons of `coin_meta` at our icon route when URLs
+    /// were asked for.
+    pub fn apply(
+        &self,
+        coin_meta: &mut CoinMeta,
+        code: &str,
+    ) {
+        if *self == IconMode::Url {
+            coin_meta.png64 =
+                Some(icon_url(code, IconFormat::Png));
+            coin_meta.webp64 =
+                Some(icon_url(code, IconFormat::Webp));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct IconsParams {
+    pub icons: Option<IconMode>,
+}
+
+/// Splits `btc.png` into a code and format. Codes are kept to
+/// ASCII letters, digits, `_` and `-` since they name files.
+pub fn parse_icon_file(
+    file: &str,
+) -> Option<(String, IconFormat)> {
+    let (code, extension) = file.rsplit_once('.')?;
+    let format = match extension {
+        "png" => IconFormat::Png,
+        "webp" => IconFormat::Webp,
+        _ => return None,
+    };
+    let is_valid = !code.is_empty()
+        && code.len() <= 32
+        && code.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || c == '_'
+                || c == '-'
+        });
+    is_valid.then(|| (code.to_lowercase(), format))
+}
+
+/// Path of our icon route for `code`.
+pub fn icon_url(code: &str, format: IconFormat) -> String {
+    format!(
+        "/v1/icons/{}.{}",
+        code.to_lowercase(),
+        format.extension()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid_icon_file() {
+        assert_eq!(
+            parse_icon_file("BTC.webp"),
+            Some(("btc".into(), IconFormat::Webp))
+        );
+        assert_eq!(
+            parse_icon_file("_bnb-2.png"),
+            Some(("_bnb-2".into(), IconFormat::Png))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_paths_and_formats() {
+        assert_eq!(parse_icon_file("btc.gif"), None);
+        assert_eq!(parse_icon_file("../btc.png"), None);
+        assert_eq!(parse_icon_file(".png"), None);
+        assert_eq!(parse_icon_file("btc"), None);
+    }
+
+    #[test]
+    fn url_mode_replaces_inline_icons() {
+        let mut coin_meta: CoinMeta = serde_json::from_str(
+            r#"{"png64": "data", "webp64": "data"}"#,
+        )
+        .unwrap();
+        IconMode::Inline.apply(&mut coin_meta, "BTC");
+        assert_eq!(
+            coin_meta.png64.as_deref(),
+            Some("data")
+        );
+
+        IconMode::Url.apply(&mut coin_meta, "BTC");
+        assert_eq!(
+            coin_meta.png64.as_deref(),
+            Some("/v1/icons/btc.png")
+        );
+        assert_eq!(
+            coin_meta.webp64.as_deref(),
+            Some("/v1/icons/btc.webp")
+        );
+    }
+
+    #[test]
+    fn icon_url_is_lowercase() {
+        assert_eq!(
+            icon_url("BTC", IconFormat::Png),
+            "/v1/icons/btc.png"
+        );
+    }
+}
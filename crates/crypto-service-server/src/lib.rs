@@ -1,4 +1,23 @@
 pub mod state;
+pub mod alerts;
 pub mod api_client;
+pub mod assets;
+pub mod auth;
+pub mod caching;
 pub mod alphavantage_api;
-pub mod coin_watch;
\ No newline at end of file
+pub mod binance;
+pub mod coin_watch;
+pub mod coinapi;
+pub mod export;
+pub mod health;
+pub mod icons;
+pub mod openapi;
+pub mod portfolio;
+pub mod providers;
+pub mod store;
+pub mod stream;
+pub mod v2;
+pub mod watchlist;
+pub mod webhooks;
+#[cfg(test)]
+mod test_support;
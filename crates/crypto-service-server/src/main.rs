@@ -1,39 +1,342 @@
+use std::{env, sync::Arc, time::Duration};
+
 use anyhow::Result;
 use axum::{
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
-use crypto_service::coin_watch_service::coin_watch_client::CoinWatchClient;
+use crypto_service::{
+    alphavantage_service::alpha_vantage_client::AlphaVantageClient,
+    binance_service::binance_client::BinanceClient,
+    coin_watch_service::coin_watch_client::CoinWatchClient,
+    coinapi_service::coinapi_client::CoinApiClient,
+    coingecko_service::coingecko_client::CoinGeckoClient,
+    consensus::DEFAULT_MAX_DEVIATION,
+    data_source::DataSource,
+};
 use crypto_service_server::{
+    alerts::{
+        alert_engine::{now_millis, run_alert_poller, AlertEngine},
+        alert_handlers,
+        notifier::{FanOutNotifier, LogNotifier},
+    },
     alphavantage_api::{
-        alpha_client::AlphaAdvantageClient, alpha_handler,
+        alpha_handler,
+        trades_feed::{self, run_trades_feed, TradesFeed},
+    },
+    api_client::{api_client::ApiClient, quota::BudgetSource},
+    assets::{asset_handlers, refresh::run_asset_refresh},
+    auth::{api_keys::ApiKeys, auth_handlers, auth_middleware},
+    binance::binance_handlers,
+    caching::etag_middleware,
+    coin_watch::{coin_watch_handlers, credits::run_credits_sync},
+    coinapi::coinapi_handlers,
+    health::health_handlers,
+    icons::{
+        icon_cache::{IconCache, DEFAULT_ICON_BASE_URL},
+        icon_handlers,
+    },
+    openapi::api_doc,
+    portfolio::portfolio_handlers,
+    providers::{
+        binance::BinanceProvider, coinapi::CoinApiProvider,
+        coingecko::CoinGeckoProvider,
+        live_coin_watch::LiveCoinWatchProvider,
+        market_data::FailoverProvider,
     },
-    api_client::api_client::ApiClient,
-    coin_watch::coin_watch_handlers,
     state::AppState,
+    store::{
+        backfill::Backfill,
+        collector::{parse_watchlist, run_collector},
+        quote_store::QuoteStore,
+        store_handlers,
+    },
+    stream::{
+        price_hub::{run_price_stream, PriceHub},
+        stream_handler,
+    },
+    v2::v2_handlers,
+    watchlist::watchlist_handlers,
+    webhooks::{
+        webhook_dispatcher::WebhookDispatcher, webhook_handlers,
+    },
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
 
-    let alpha_client: AlphaAdvantageClient =
-        AlphaAdvantageClient::new();
+    let alpha_client = AlphaVantageClient::new();
+    if !alpha_client.has_key() {
+        println!("No ALPHA_VANTAGE_KEY set, /v1/stocks and /v1/alphavantage routes will answer 503");
+    }
     let coin_watch_client = CoinWatchClient::new();
+    let coingecko_client = CoinGeckoClient::new();
+    let binance_client = BinanceClient::new();
+    let coinapi_client = CoinApiClient::new();
+    if !coinapi_client.has_key() {
+        println!("No COINAPI_API_KEY set, /v1/coinapi routes will answer 503");
+    }
     let api_client = ApiClient::new();
+    api_client.quota.track(
+        "live_coin_watch",
+        &coin_watch_client.base_url,
+        env::var("LIVE_COIN_WATCH_DAILY_LIMIT")
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(10_000),
+        BudgetSource::Upstream,
+        now_millis(),
+    );
+    api_client.quota.track(
+        "alpha_vantage",
+        &alpha_client.base_url,
+        env::var("ALPHA_VANTAGE_DAILY_LIMIT")
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(25),
+        BudgetSource::Local,
+        now_millis(),
+    );
+    // The demo plan allows 10,000 calls a month.
+    api_client.quota.track(
+        "coingecko",
+        &coingecko_client.base_url,
+        env::var("COINGECKO_DAILY_LIMIT")
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(300),
+        BudgetSource::Local,
+        now_millis(),
+    );
+    // CoinAPI's free tier allows 100 requests a day.
+    api_client.quota.track(
+        "coinapi",
+        &coinapi_client.base_url,
+        env::var("COINAPI_DAILY_LIMIT")
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(100),
+        BudgetSource::Local,
+        now_millis(),
+    );
+    // Where coin routes fail over through, e.g.
+    // `livecoinwatch,coingecko,binance`. Unknown names are skipped.
+    let data_sources: Vec<DataSource> = env::var("COIN_DATA_SOURCES")
+        .map(|sources| {
+            sources
+                .split(',')
+                .filter_map(|source| DataSource::parse(source.trim()))
+                .collect()
+        })
+        .unwrap_or(vec![DataSource::LiveCoinWatch, DataSource::CoinGecko]);
+    let quote_max_age = env::var("QUOTE_STALE_AFTER_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<i64>().ok())
+        .unwrap_or(5 * 60);
+    let max_deviation = env::var("CONSENSUS_MAX_DEVIATION")
+        .ok()
+        .and_then(|deviation| deviation.parse().ok())
+        .unwrap_or(DEFAULT_MAX_DEVIATION);
+    let providers = FailoverProvider::new(
+        vec![
+            Arc::new(LiveCoinWatchProvider::new(
+                api_client.clone(),
+                coin_watch_client.clone(),
+            )),
+            Arc::new(CoinGeckoProvider::new(
+                api_client.clone(),
+                coingecko_client.clone(),
+            )),
+            Arc::new(BinanceProvider::new(
+                api_client.clone(),
+                binance_client.clone(),
+            )),
+            Arc::new(CoinApiProvider::new(
+                api_client.clone(),
+                coinapi_client.clone(),
+            )),
+        ],
+        data_sources,
+        quote_max_age * 1000,
+        max_deviation,
+    );
+    let webhooks = WebhookDispatcher::default();
+    let quote_store = QuoteStore::open(
+        env::var("QUOTES_DB_PATH").unwrap_or("quotes.db".into()),
+    )?;
+    let api_keys = ApiKeys::load(
+        env::var("API_KEYS_PATH").unwrap_or("api_keys.json".into()),
+        quote_store.clone(),
+        now_millis(),
+    )?;
+    if api_keys.is_empty() {
+        println!("No API keys configured, only /readyz and /metrics will answer");
+    }
+    let alert_engine = AlertEngine::load(
+        env::var("ALERTS_PATH")
+            .unwrap_or("alerts.json".into())
+            .into(),
+        Arc::new(FanOutNotifier(vec![
+            Arc::new(LogNotifier),
+            Arc::new(webhooks.clone()),
+        ])),
+    )?;
 
     let state = AppState::new(
         alpha_client,
         coin_watch_client,
+        coingecko_client,
+        binance_client,
+        coinapi_client,
         api_client,
+        alert_engine,
+        webhooks,
+        PriceHub::default(),
+        TradesFeed::default(),
+        quote_store,
+        api_keys,
+        IconCache::new(
+            env::var("ICON_CACHE_DIR").unwrap_or("icon_cache".into()),
+            env::var("ICON_BASE_URL")
+                .unwrap_or(DEFAULT_ICON_BASE_URL.into()),
+        ),
+        providers,
+    );
+
+    let credits_sync_interval = env::var("CREDITS_SYNC_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(5 * 60);
+    tokio::spawn(run_credits_sync(
+        state.clone(),
+        Duration::from_secs(credits_sync_interval),
+    ));
+
+    let asset_refresh_interval = env::var("ASSET_REFRESH_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(24 * 60 * 60);
+    tokio::spawn(run_asset_refresh(
+        state.clone(),
+        Duration::from_secs(asset_refresh_interval),
+    ));
+
+    let alerts_poll_interval = env::var("ALERTS_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(60);
+    tokio::spawn(run_alert_poller(
+        state.clone(),
+        Duration::from_secs(alerts_poll_interval),
+    ));
+
+    let stream_poll_interval = env::var("STREAM_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(5);
+    tokio::spawn(run_price_stream(
+        state.clone(),
+        Duration::from_secs(stream_poll_interval),
+    ));
+
+    // Alpha Vantage's free tier allows 25 calls a day, most of
+    // which are left to the quote and series routes.
+    let stocks_refresh_interval = env::var("STOCKS_REFRESH_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(4 * 60 * 60);
+    tokio::spawn(run_trades_feed(
+        state.clone(),
+        Duration::from_secs(stocks_refresh_interval),
+    ));
+
+    let backfill = Backfill::from_state(&state);
+    for id in backfill.interrupted_jobs()? {
+        store_handlers::spawn_backfill(backfill.clone(), id);
+    }
+
+    let watchlist = parse_watchlist(
+        &env::var("WATCHLIST").unwrap_or_default(),
     );
+    if !watchlist.is_empty() {
+        let collect_interval = env::var("COLLECT_INTERVAL_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(5 * 60);
+        tokio::spawn(run_collector(
+            state.clone(),
+            watchlist,
+            Duration::from_secs(collect_interval),
+        ));
+    }
 
     let app = Router::new()
+        .route("/readyz", get(health_handlers::readyz))
+        .route("/metrics", get(health_handlers::metrics))
+        .merge(api_doc::docs())
         .route("/v1/stocks", get(alpha_handler::get_top_gainers_and_losers))
+        .route("/v1/stocks/stream", get(trades_feed::stream_top_gainers_and_losers))
+        .route("/v1/stocks/quote", get(alpha_handler::get_stock_quote))
+        .route("/v1/stocks/daily", get(alpha_handler::get_stock_daily))
+        .route("/v1/stocks/intraday", get(alpha_handler::get_stock_intraday))
+        .route("/v1/stocks/search", get(alpha_handler::search_stock_symbols))
         .route("/v1/coins/list", post(coin_watch_handlers::get_list_of_coins))
         .route("/v1/coins/single", post(coin_watch_handlers::get_coin_meta_info))
         .route("/v1/coins/single/history", post(coin_watch_handlers::get_coin_history_info))
         .route("/v1/coins/list/aggregated", post(coin_watch_handlers::get_aggregated_coin_list))
+        .route("/v1/coins/:code/consensus", get(coin_watch_handlers::get_consensus_quote))
+        .route("/v2/coins", get(v2_handlers::list_coins))
+        .route("/v2/coins/:code", get(v2_handlers::get_coin))
+        .route("/v2/coins/:code/history", get(v2_handlers::get_coin_history))
+        .route("/v2/coins/:code/quote", get(v2_handlers::get_coin_quote))
+        .route("/v1/binance/ticker/24hr", get(binance_handlers::get_ticker))
+        .route("/v1/binance/klines", get(binance_handlers::get_candles))
+        .route("/v1/binance/depth", get(binance_handlers::get_order_book))
+        .route("/v1/binance/exchange-info", get(binance_handlers::get_exchange_info))
+        .route("/v1/alphavantage/exchange-rate", get(alpha_handler::get_currency_exchange_rate))
+        .route("/v1/alphavantage/fx/daily", get(alpha_handler::get_fx_daily))
+        .route("/v1/alphavantage/digital-currency/daily", get(alpha_handler::get_digital_currency_daily))
+        .route("/v1/coinapi/assets", get(coinapi_handlers::get_assets))
+        .route("/v1/coinapi/symbols", get(coinapi_handlers::get_symbols))
+        .route("/v1/coinapi/exchangerate/:base/:quote", get(coinapi_handlers::get_exchange_rate))
+        .route("/v1/coinapi/icons/:size", get(coinapi_handlers::get_asset_icons))
+        .route("/v1/icons/:file", get(icon_handlers::get_icon))
+        .route("/v1/assets", get(asset_handlers::list_assets))
+        .route("/v1/assets/:code", get(asset_handlers::get_asset))
+        .route("/v1/watchlist", get(watchlist_handlers::get_watchlist))
+        .route("/v1/local/coins/:code/snapshots", get(store_handlers::get_local_snapshots))
+        .route("/v1/local/coins/:code/history", get(store_handlers::get_local_history))
+        .route("/v1/portfolio/gains", post(portfolio_handlers::get_realized_gains))
+        .route(
+            "/v1/portfolio/import",
+            post(portfolio_handlers::import_transactions)
+                .layer(DefaultBodyLimit::max(portfolio_handlers::IMPORT_BODY_LIMIT)),
+        )
+        .route("/v1/alerts", get(alert_handlers::list_alerts).post(alert_handlers::create_alert))
+        .route("/v1/alerts/:id", delete(alert_handlers::delete_alert))
+        .route("/v1/stream", get(stream_handler::stream))
+        .route("/v1/admin/backfill", get(store_handlers::list_backfills).post(store_handlers::create_backfill))
+        .route("/v1/admin/backfill/:id", get(store_handlers::get_backfill))
+        .route("/v1/admin/backfill/:id/resume", post(store_handlers::resume_backfill))
+        .route("/v1/admin/keys", get(auth_handlers::list_api_keys).post(auth_handlers::create_api_key))
+        .route("/v1/admin/assets/refresh", post(asset_handlers::refresh_assets))
+        .route("/v1/admin/keys/:id", delete(auth_handlers::revoke_api_key))
+        .route("/v1/admin/webhooks", get(webhook_handlers::list_webhooks).post(webhook_handlers::register_webhook))
+        .route("/v1/admin/webhooks/:id", delete(webhook_handlers::delete_webhook))
+        .route("/v1/admin/webhooks/:id/test", post(webhook_handlers::test_webhook))
+        .route("/v1/admin/webhooks/deliveries", get(webhook_handlers::list_deliveries))
+        .route("/v1/admin/webhooks/dead-letters", get(webhook_handlers::list_dead_letters))
+        .route("/v1/admin/webhooks/dead-letters/:id/replay", post(webhook_handlers::replay_dead_letter))
+        .layer(middleware::from_fn(
+            etag_middleware::conditional_requests,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware::authenticate,
+        ))
         .with_state(state);
 
     let listener =
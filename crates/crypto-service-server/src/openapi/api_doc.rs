@@ -0,0 +1,304 @@
+use crypto_service::{
+    alphavantage_service::models::{
+        CurrencyExchangeRate, DigitalCurrencyBar,
+        DigitalCurrencySeries, FxBar, FxSeries,
+        IntradayInterval, MostActivelyTraded, OutputSize,
+        RawTrade, StockBar, StockQuote, StockSeries,
+        SymbolMatch, TopAndBottomTrades,
+    },
+    asset::{AssetClass, AssetSummary, ChangePercentages},
+    asset_registry::AssetIdentity,
+    binance_service::models::{
+        Candle, ExchangeInfo, KlineInterval, OrderBook,
+        OrderBookLevel, SymbolInfo, Ticker,
+    },
+    coin_watch_service::models::{
+        AggregatedCoinInformation, Coin, CoinHistory,
+        CoinHistoryRequest, CoinMeta, CoinMetaRequest,
+        Delta, History, Links, ListOfCoinsRequest, Order,
+        Sort,
+    },
+    coinapi_service::models::{
+        AssetIcon, CoinApiAsset, CoinApiSymbol,
+        ExchangeRate,
+    },
+    consensus::ConsensusQuote,
+    data_source::{DataSource, Quote},
+    portfolio::models::{
+        ExchangeFormat, HoldingPeriod, ImportReport,
+        ImportRowError, LotMethod, RealizedGain,
+        Transaction, TransactionKind, YearlyGainsReport,
+    },
+};
+use utoipa::{
+    openapi::{
+        self,
+        security::{ApiKey, ApiKeyValue, SecurityScheme},
+    },
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    alerts::{
+        alert_handlers,
+        models::{
+            AlertCondition, AlertRule, AlertState,
+            AlertWithState, CreateAlertRequest,
+            DeltaWindow,
+        },
+    },
+    alphavantage_api::alpha_handler,
+    api_client::quota::{Budget, BudgetSource},
+    assets::asset_handlers,
+    binance::binance_handlers,
+    coin_watch::coin_watch_handlers,
+    coinapi::coinapi_handlers,
+    health::health_handlers::{self, Readiness},
+    icons::{icon_handlers, models::IconMode},
+    portfolio::portfolio_handlers::{
+        self, ImportForm, RealizedGainsRequest,
+        RealizedGainsResponse,
+    },
+    store::{models::CoinSnapshot, store_handlers},
+    v2::{models::HistoryRange, v2_handlers},
+    watchlist::watchlist_handlers,
+};
+
+/// The public API, generated from the handlers and their models.
+/// `openapi.json` next to `Cargo.toml` is a snapshot of it, see the
+/// test below.
+///
+/// Left out are `/v1/stream` and `/v1/stocks/stream`, WebSocket
+/// and server-sent event upgrades OpenAPI can't describe, and the
+/// operator routes under `/v1/admin`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "crypto-service",
+        description = "Stock and crypto quotes from Alpha Vantage, Live Coin Watch, CoinGecko, Binance and CoinAPI. Versioned routes need an API key with the matching scope."
+    ),
+    paths(
+        alpha_handler::get_top_gainers_and_losers,
+        alpha_handler::get_stock_quote,
+        alpha_handler::get_stock_daily,
+        alpha_handler::get_stock_intraday,
+        alpha_handler::search_stock_symbols,
+        alpha_handler::get_currency_exchange_rate,
+        alpha_handler::get_fx_daily,
+        alpha_handler::get_digital_currency_daily,
+        coin_watch_handlers::get_list_of_coins,
+        coin_watch_handlers::get_coin_meta_info,
+        coin_watch_handlers::get_coin_history_info,
+        coin_watch_handlers::get_aggregated_coin_list,
+        coin_watch_handlers::get_consensus_quote,
+        v2_handlers::list_coins,
+        v2_handlers::get_coin,
+        v2_handlers::get_coin_history,
+        v2_handlers::get_coin_quote,
+        binance_handlers::get_ticker,
+        binance_handlers::get_candles,
+        binance_handlers::get_order_book,
+        binance_handlers::get_exchange_info,
+        coinapi_handlers::get_assets,
+        coinapi_handlers::get_symbols,
+        coinapi_handlers::get_exchange_rate,
+        coinapi_handlers::get_asset_icons,
+        icon_handlers::get_icon,
+        asset_handlers::list_assets,
+        asset_handlers::get_asset,
+        watchlist_handlers::get_watchlist,
+        store_handlers::get_local_snapshots,
+        store_handlers::get_local_history,
+        alert_handlers::list_alerts,
+        alert_handlers::create_alert,
+        alert_handlers::delete_alert,
+        portfolio_handlers::get_realized_gains,
+        portfolio_handlers::import_transactions,
+        health_handlers::readyz,
+    ),
+    components(schemas(
+        TopAndBottomTrades,
+        MostActivelyTraded,
+        RawTrade,
+        StockQuote,
+        StockSeries,
+        StockBar,
+        OutputSize,
+        IntradayInterval,
+        SymbolMatch,
+        CurrencyExchangeRate,
+        FxSeries,
+        FxBar,
+        DigitalCurrencySeries,
+        DigitalCurrencyBar,
+        ListOfCoinsRequest,
+        Coin,
+        Delta,
+        CoinMetaRequest,
+        CoinMeta,
+        CoinHistoryRequest,
+        CoinHistory,
+        History,
+        Links,
+        AggregatedCoinInformation,
+        Sort,
+        Order,
+        HistoryRange,
+        IconMode,
+        DataSource,
+        Quote,
+        ConsensusQuote,
+        Ticker,
+        KlineInterval,
+        Candle,
+        OrderBook,
+        OrderBookLevel,
+        ExchangeInfo,
+        SymbolInfo,
+        CoinApiAsset,
+        CoinApiSymbol,
+        ExchangeRate,
+        AssetIcon,
+        AssetIdentity,
+        AssetClass,
+        AssetSummary,
+        ChangePercentages,
+        CoinSnapshot,
+        DeltaWindow,
+        AlertCondition,
+        AlertRule,
+        AlertState,
+        AlertWithState,
+        CreateAlertRequest,
+        Readiness,
+        Budget,
+        BudgetSource,
+        Transaction,
+        TransactionKind,
+        LotMethod,
+        HoldingPeriod,
+        RealizedGain,
+        YearlyGainsReport,
+        RealizedGainsRequest,
+        RealizedGainsResponse,
+        ExchangeFormat,
+        ImportRowError,
+        ImportReport,
+        ImportForm,
+    )),
+    modifiers(&ApiKeyHeader),
+    security(("api_key" = [])),
+    tags(
+        (name = "stocks", description = "Needs the `stocks` scope"),
+        (name = "coins", description = "Needs the `coins` scope"),
+        (name = "binance", description = "Needs the `coins` scope"),
+        (name = "coinapi", description = "Needs the `coins` scope"),
+        (name = "alphavantage", description = "Needs the `coins` scope"),
+        (name = "local", description = "Needs the `coins` scope"),
+        (name = "alerts", description = "Needs the `coins` scope"),
+        (name = "portfolio", description = "Needs the `coins` scope"),
+        (name = "health", description = "Open to everyone"),
+    )
+)]
+pub struct ApiDoc;
+
+struct ApiKeyHeader;
+
+impl Modify for ApiKeyHeader {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(
+                    ApiKeyValue::new("x-api-key"),
+                )),
+            );
+    }
+}
+
+/// Swagger UI at `/docs` for the spec at `/openapi.json`. Its
+/// assets are bundled into the binary rather than loaded from a
+/// CDN.
+pub fn docs() -> SwaggerUi {
+    SwaggerUi::new("/docs")
+        .url("/openapi.json", ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+    use crate::test_support;
+
+    const SNAPSHOT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/openapi.json"
+    );
+
+    /// Fails when a model or handler changes without the committed
+    /// spec changing with it. Run with `UPDATE_OPENAPI=1` to
+    /// rewrite the snapshot, then review the diff.
+    #[test]
+    fn spec_matches_snapshot() {
+        let spec =
+            ApiDoc::openapi().to_pretty_json().unwrap();
+        if env::var("UPDATE_OPENAPI").is_ok() {
+            fs::write(SNAPSHOT, format!("{spec}\n"))
+                .unwrap();
+        }
+        let snapshot = fs::read_to_string(SNAPSHOT)
+            .unwrap_or_default();
+        assert_eq!(
+            snapshot.trim_end(),
+            spec,
+            "openapi.json is out of date, rerun with UPDATE_OPENAPI=1"
+        );
+    }
+
+    #[test]
+    fn documents_frontend_models() {
+        let spec = ApiDoc::openapi();
+        let schemas = spec.components.unwrap().schemas;
+        for model in [
+            "Coin",
+            "CoinMeta",
+            "AggregatedCoinInformation",
+            "TopAndBottomTrades",
+        ] {
+            assert!(schemas.contains_key(model), "{model}");
+        }
+    }
+
+    #[tokio::test]
+    async fn docs_are_served_without_a_cdn() {
+        let app: axum::Router = docs().into();
+        let base_url = test_support::serve(app).await;
+
+        let page =
+            reqwest::get(format!("{base_url}/docs/"))
+                .await
+                .unwrap();
+        assert!(page.status().is_success());
+        assert!(!page
+            .text()
+            .await
+            .unwrap()
+            .contains("cdn"));
+        let script = reqwest::get(format!(
+            "{base_url}/docs/swagger-ui-bundle.js"
+        ))
+        .await
+        .unwrap();
+        assert!(script.status().is_success());
+        let spec = reqwest::get(format!(
+            "{base_url}/openapi.json"
+        ))
+        .await
+        .unwrap();
+        assert!(spec.status().is_success());
+    }
+}
@@ -0,0 +1,159 @@
+use axum::{
+    extract::Multipart, http::StatusCode, response::Response,
+    Json,
+};
+use crypto_service::portfolio::{
+    import::import_transactions_csv,
+    models::{
+        ImportReport, LotMethod, RealizedGain, Transaction,
+        YearlyGainsReport,
+    },
+    tax_lots::{
+        compute_realized_gains, yearly_gains_report,
+    },
+};
+use crypto_service::tabular::{Rows, Tabular};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::export::export_format::ExportFormat;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct RealizedGainsRequest {
+    pub method: LotMethod,
+    pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct RealizedGainsResponse {
+    pub gains: Vec<RealizedGain>,
+    pub yearly_reports: Vec<YearlyGainsReport>,
+}
+
+/// Exported as CSV or JSON Lines, only the individual gains are
+/// included, one per row.
+impl Tabular for RealizedGainsResponse {
+    fn columns() -> &'static [&'static str] {
+        RealizedGain::columns()
+    }
+
+    fn into_rows(self) -> Rows {
+        self.gains.into_rows()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/portfolio/gains",
+    tag = "portfolio",
+    request_body = RealizedGainsRequest,
+    responses(
+        (status = 200, description = "JSON, or the gains alone as CSV or JSON Lines depending on `Accept`", body = RealizedGainsResponse),
+        (status = 422, description = "An invalid transaction or a disposal of more than is held", body = String),
+    )
+)]
+pub async fn get_realized_gains(
+    format: ExportFormat,
+    Json(body): Json<RealizedGainsRequest>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    compute_realized_gains(body.transactions, body.method)
+        .and_then(|gains| {
+            Ok(RealizedGainsResponse {
+                yearly_reports: yearly_gains_report(
+                    &gains,
+                )?,
+                gains,
+            })
+        })
+        .map(|response| format.respond(response))
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(e.to_string()),
+            )
+        })
+}
+
+/// Exchange exports easily exceed axum's default 2 MB body limit.
+pub const IMPORT_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Multipart form of [`import_transactions`], only described for
+/// the spec.
+#[derive(ToSchema)]
+pub struct ImportForm {
+    /// Binance, Coinbase or Kraken CSV export.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    /// Ids of already imported transactions, comma or newline
+    /// separated.
+    pub existing_ids: Option<String>,
+}
+
+/// Imports an exchange CSV export sent as the multipart field
+/// `file`. Ids of already imported transactions can be passed,
+/// comma or newline separated, in the field `existing_ids`.
+#[utoipa::path(
+    post,
+    path = "/v1/portfolio/import",
+    tag = "portfolio",
+    request_body(content = ImportForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Transactions read from the file and rows that failed", body = ImportReport),
+        (status = 400, description = "Malformed multipart body or no `file` field", body = String),
+        (status = 422, description = "Malformed CSV or not an export of a known exchange", body = String),
+    )
+)]
+pub async fn import_transactions(
+    mut multipart: Multipart,
+) -> Result<
+    (StatusCode, Json<ImportReport>),
+    (StatusCode, Json<String>),
+> {
+    let bad_request =
+        |e: axum::extract::multipart::MultipartError| {
+            (StatusCode::BAD_REQUEST, Json(e.to_string()))
+        };
+
+    let mut file = None;
+    let mut existing_ids = vec![];
+    while let Some(field) =
+        multipart.next_field().await.map_err(bad_request)?
+    {
+        match field.name() {
+            Some("file") => {
+                file = Some(
+                    field
+                        .bytes()
+                        .await
+                        .map_err(bad_request)?,
+                )
+            }
+            Some("existing_ids") => {
+                existing_ids = field
+                    .text()
+                    .await
+                    .map_err(bad_request)?
+                    .split([',', '\n'])
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(String::from)
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+
+    let file = file.ok_or((
+        StatusCode::BAD_REQUEST,
+        Json("Missing multipart field 'file'".to_string()),
+    ))?;
+
+    import_transactions_csv(file.to_vec(), existing_ids)
+        .map(|report| (StatusCode::OK, Json(report)))
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(e.to_string()),
+            )
+        })
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use axum::Json;
+use crypto_service::{
+    binance_service::{
+        binance_client::BinanceClient,
+        models::{
+            candles_into_history, usdt_pair, Candle, Kline,
+            KlinesRequest, Ticker, TickerRequest,
+        },
+    },
+    coin_watch_service::models::{
+        Coin, CoinHistory, CoinHistoryRequest, CoinMeta,
+        ListOfCoinsRequest,
+    },
+    data_source::{DataSource, Quote},
+};
+
+use super::market_data::{
+    MarketDataProvider, ProviderError,
+};
+use crate::api_client::{
+    api_client::ApiClient, quota::Priority,
+};
+
+/// Prices from the coin's [`usdt_pair`], Tether standing in for USD.
+#[derive(Debug, Clone)]
+pub struct BinanceProvider {
+    api_client: ApiClient,
+    client: BinanceClient,
+}
+
+impl BinanceProvider {
+    pub fn new(
+        api_client: ApiClient,
+        client: BinanceClient,
+    ) -> Self {
+        Self { api_client, client }
+    }
+
+    async fn ticker(
+        &self,
+        code: &str,
+        priority: Priority,
+    ) -> Result<Ticker, ProviderError> {
+        let pair = usdt_pair(code)
+            .ok_or(ProviderError::Unsupported)?;
+        self.api_client
+            .get_with_priority::<TickerRequest, Ticker, BinanceClient>(
+                self.client.clone(),
+                "/ticker/24hr",
+                TickerRequest::new(pair),
+                priority,
+            )
+            .await
+            .map(|(_, Json(ticker))| ticker)
+            .map_err(ProviderError::from)
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for BinanceProvider {
+    fn source(&self) -> DataSource {
+        DataSource::Binance
+    }
+
+    /// Binance lists pairs, not coins ranked by market cap.
+    async fn list_coins(
+        &self,
+        _request: &ListOfCoinsRequest,
+        _priority: Priority,
+    ) -> Result<Vec<Coin>, ProviderError> {
+        Err(ProviderError::Unsupported)
+    }
+
+    async fn coin_meta(
+        &self,
+        code: &str,
+        priority: Priority,
+    ) -> Result<CoinMeta, ProviderError> {
+        self.ticker(code, priority)
+            .await
+            .map(|ticker| ticker.coin_meta(code))
+    }
+
+    async fn coin_history(
+        &self,
+        request: &CoinHistoryRequest,
+    ) -> Result<CoinHistory, ProviderError> {
+        let klines = KlinesRequest::for_history(request)
+            .ok_or(ProviderError::Unsupported)?;
+        self.api_client
+            .get::<KlinesRequest, Vec<Kline>, BinanceClient>(
+                self.client.clone(),
+                "/klines",
+                klines,
+            )
+            .await
+            .map(|(_, Json(klines))| {
+                candles_into_history(
+                    request.code(),
+                    klines.into_iter().map(Candle::from).collect(),
+                )
+            })
+            .map_err(ProviderError::from)
+    }
+
+    async fn quote(
+        &self,
+        code: &str,
+    ) -> Result<Quote, ProviderError> {
+        self.ticker(code, Priority::Normal)
+            .await
+            .map(|ticker| ticker.quote(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{extract::Query, routing::get, Router};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support;
+
+    /// Local stand-in for Binance, `/klines` echoes the interval as
+    /// the trade count.
+    async fn provider() -> BinanceProvider {
+        let app = Router::new()
+            .route(
+                "/ticker/24hr",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    Json(json!({
+                        "symbol": query["symbol"],
+                        "priceChange": "100", "priceChangePercent": "2.5",
+                        "weightedAvgPrice": "64000", "prevClosePrice": "63900",
+                        "lastPrice": "64000.5", "bidPrice": "64000",
+                        "askPrice": "64001", "openPrice": "63900",
+                        "highPrice": "64500", "lowPrice": "63500",
+                        "volume": "10", "quoteVolume": "640000",
+                        "openTime": 1, "closeTime": 2, "count": 3
+                    }))
+                }),
+            )
+            .route(
+                "/klines",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    Json(json!([[
+                        query["startTime"].parse::<i64>().unwrap(),
+                        "1", "2", "0.5", "1.5", "10",
+                        2, "15", 3, "5", "7.5", "0"
+                    ]]))
+                }),
+            );
+        let base_url = test_support::serve(app).await;
+
+        let mut client =
+            BinanceClient::new_with_key(String::new());
+        client.base_url = base_url;
+        BinanceProvider::new(ApiClient::new(), client)
+    }
+
+    #[tokio::test]
+    async fn quote_from_usdt_ticker() {
+        let quote =
+            provider().await.quote("btc").await.unwrap();
+        assert_eq!(quote.code, "BTC");
+        assert_eq!(quote.rate, 64000.5);
+        assert_eq!(quote.source, DataSource::Binance);
+    }
+
+    #[tokio::test]
+    async fn history_from_klines() {
+        let history = provider()
+            .await
+            .coin_history(&CoinHistoryRequest::new(
+                "btc".into(),
+                5_000,
+                9_000,
+                false,
+            ))
+            .await
+            .unwrap();
+        let points = history.history.unwrap();
+        assert_eq!(points[0].date, Some(5_000));
+        assert_eq!(points[0].rate, Some(1.5));
+    }
+
+    #[tokio::test]
+    async fn no_listing() {
+        assert!(matches!(
+            provider()
+                .await
+                .list_coins(
+                    &ListOfCoinsRequest::new(10),
+                    Priority::Normal,
+                )
+                .await,
+            Err(ProviderError::Unsupported)
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_tether_pair_for_tether() {
+        assert_eq!(
+            provider().await.quote("usdt").await,
+            Err(ProviderError::Unsupported)
+        );
+    }
+}
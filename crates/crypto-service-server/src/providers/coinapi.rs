@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use crypto_service::{
+    asset_registry::{self, AssetProvider},
+    coin_watch_service::models::{
+        Coin, CoinHistory, CoinHistoryRequest, CoinMeta,
+        ListOfCoinsRequest,
+    },
+    coinapi_service::{
+        coinapi_client::CoinApiClient,
+        models::{
+            AssetsRequest, CoinApiAsset, ExchangeRate,
+            ExchangeRateRequest,
+        },
+    },
+    data_source::{DataSource, Quote},
+};
+
+use super::market_data::{
+    MarketDataProvider, ProviderError,
+};
+use crate::{
+    api_client::{api_client::ApiClient, quota::Priority},
+    coinapi::coinapi_handlers::{fetch, fetch_with_priority},
+};
+
+/// CoinAPI id of `code`, unsupported for an asset it doesn't list.
+fn asset_id(code: &str) -> Result<String, ProviderError> {
+    asset_registry::identifier(code, AssetProvider::CoinApi)
+        .ok_or(ProviderError::Unsupported)
+}
+
+/// Metadata from `/assets` and quotes from `/exchangerate`. Its
+/// daily budget is small, so it's best kept last in the chain.
+#[derive(Debug, Clone)]
+pub struct CoinApiProvider {
+    api_client: ApiClient,
+    client: CoinApiClient,
+}
+
+impl CoinApiProvider {
+    pub fn new(
+        api_client: ApiClient,
+        client: CoinApiClient,
+    ) -> Self {
+        Self { api_client, client }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for CoinApiProvider {
+    fn source(&self) -> DataSource {
+        DataSource::CoinApi
+    }
+
+    async fn list_coins(
+        &self,
+        _request: &ListOfCoinsRequest,
+        _priority: Priority,
+    ) -> Result<Vec<Coin>, ProviderError> {
+        Err(ProviderError::Unsupported)
+    }
+
+    async fn coin_meta(
+        &self,
+        code: &str,
+        priority: Priority,
+    ) -> Result<CoinMeta, ProviderError> {
+        let assets: Vec<CoinApiAsset> = fetch_with_priority(
+            &self.api_client,
+            &self.client,
+            "/assets",
+            AssetsRequest {
+                filter_asset_id: Some(asset_id(code)?),
+            },
+            priority,
+        )
+        .await?;
+        assets
+            .into_iter()
+            .next()
+            .map(CoinMeta::from)
+            .ok_or(ProviderError::Empty)
+    }
+
+    async fn coin_history(
+        &self,
+        _request: &CoinHistoryRequest,
+    ) -> Result<CoinHistory, ProviderError> {
+        Err(ProviderError::Unsupported)
+    }
+
+    async fn quote(
+        &self,
+        code: &str,
+    ) -> Result<Quote, ProviderError> {
+        let rate: ExchangeRate = fetch(
+            &self.api_client,
+            &self.client,
+            &format!(
+                "/exchangerate/{}/USD",
+                asset_id(code)?
+            ),
+            ExchangeRateRequest::default(),
+        )
+        .await?;
+        rate.quote().ok_or(ProviderError::Empty)
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use axum::{http::StatusCode, Json};
+use crypto_service::{
+    coin_watch_service::models::{
+        Coin, CoinHistory, CoinHistoryRequest, CoinMeta,
+        ListOfCoinsRequest,
+    },
+    coingecko_service::{
+        coingecko_client::CoinGeckoClient,
+        models::{
+            coin_id, simple_price_quote, CoinDetail,
+            CoinDetailQuery, Market, MarketChart,
+            MarketChartQuery, MarketsQuery, SimplePrice,
+            SimplePriceQuery,
+        },
+    },
+    data_source::{DataSource, Quote},
+};
+
+use super::market_data::{MarketDataProvider, ProviderError};
+use crate::api_client::{
+    api_client::ApiClient, quota::Priority,
+};
+
+// CoinGecko counterparts of the Live Coin Watch calls in
+// `coin_watch_handlers`, mapped into the same shapes.
+
+pub async fn fetch_list_of_coins(
+    api_client: &ApiClient,
+    client: &CoinGeckoClient,
+    body: &ListOfCoinsRequest,
+    priority: Priority,
+) -> Result<Vec<Coin>, (StatusCode, Json<String>)> {
+    api_client
+        .get_with_priority::<MarketsQuery, Vec<Market>, CoinGeckoClient>(
+            client.clone(),
+            "/coins/markets",
+            MarketsQuery::from(body),
+            priority,
+        )
+        .await
+        .map(|(_, Json(markets))| {
+            markets.into_iter().map(Coin::from).collect()
+        })
+}
+
+/// Detail of the coin with the CoinGecko id `id`, see [`coin_id`].
+pub async fn fetch_coin_detail(
+    api_client: &ApiClient,
+    client: &CoinGeckoClient,
+    id: &str,
+    priority: Priority,
+) -> Result<CoinDetail, (StatusCode, Json<String>)> {
+    api_client
+        .get_with_priority::<CoinDetailQuery, CoinDetail, CoinGeckoClient>(
+            client.clone(),
+            &format!("/coins/{id}"),
+            CoinDetailQuery::default(),
+            priority,
+        )
+        .await
+        .map(|(_, Json(detail))| detail)
+}
+
+pub async fn fetch_coin_meta(
+    api_client: &ApiClient,
+    client: &CoinGeckoClient,
+    id: &str,
+    priority: Priority,
+) -> Result<CoinMeta, (StatusCode, Json<String>)> {
+    fetch_coin_detail(api_client, client, id, priority)
+        .await
+        .map(CoinMeta::from)
+}
+
+/// `/market_chart/range` has no metadata, so asking for it costs a
+/// second call to `/coins/{id}`.
+pub async fn fetch_coin_history(
+    api_client: &ApiClient,
+    client: &CoinGeckoClient,
+    id: &str,
+    body: &CoinHistoryRequest,
+) -> Result<CoinHistory, (StatusCode, Json<String>)> {
+    let detail = match body.meta() {
+        true => Some(
+            fetch_coin_detail(
+                api_client,
+                client,
+                id,
+                Priority::Normal,
+            )
+            .await?,
+        ),
+        false => None,
+    };
+    let (_, Json(chart)) = api_client
+        .get::<MarketChartQuery, MarketChart, CoinGeckoClient>(
+            client.clone(),
+            &format!("/coins/{id}/market_chart/range"),
+            MarketChartQuery::from(body),
+        )
+        .await?;
+    Ok(chart.into_coin_history(body.code(), detail))
+}
+
+/// `None` when CoinGecko doesn't know the coin.
+pub async fn fetch_quote(
+    api_client: &ApiClient,
+    client: &CoinGeckoClient,
+    id: &str,
+    code: &str,
+) -> Result<Option<Quote>, (StatusCode, Json<String>)> {
+    api_client
+        .get::<SimplePriceQuery, HashMap<String, SimplePrice>, CoinGeckoClient>(
+            client.clone(),
+            "/simple/price",
+            SimplePriceQuery::new(id.to_string()),
+        )
+        .await
+        .map(|(_, Json(prices))| simple_price_quote(code, &prices))
+}
+
+/// CoinGecko id of `code`, unsupported for a coin it doesn't list.
+fn listed(code: &str) -> Result<String, ProviderError> {
+    coin_id(code).ok_or(ProviderError::Unsupported)
+}
+
+#[derive(Debug, Clone)]
+pub struct CoinGeckoProvider {
+    api_client: ApiClient,
+    client: CoinGeckoClient,
+}
+
+impl CoinGeckoProvider {
+    pub fn new(
+        api_client: ApiClient,
+        client: CoinGeckoClient,
+    ) -> Self {
+        Self { api_client, client }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for CoinGeckoProvider {
+    fn source(&self) -> DataSource {
+        DataSource::CoinGecko
+    }
+
+    async fn list_coins(
+        &self,
+        request: &ListOfCoinsRequest,
+        priority: Priority,
+    ) -> Result<Vec<Coin>, ProviderError> {
+        Ok(fetch_list_of_coins(
+            &self.api_client,
+            &self.client,
+            request,
+            priority,
+        )
+        .await?)
+    }
+
+    async fn coin_meta(
+        &self,
+        code: &str,
+        priority: Priority,
+    ) -> Result<CoinMeta, ProviderError> {
+        let id = listed(code)?;
+        Ok(fetch_coin_meta(
+            &self.api_client,
+            &self.client,
+            &id,
+            priority,
+        )
+        .await?)
+    }
+
+    async fn coin_history(
+        &self,
+        request: &CoinHistoryRequest,
+    ) -> Result<CoinHistory, ProviderError> {
+        let id = listed(request.code())?;
+        Ok(fetch_coin_history(&self.api_client, &self.client, &id, request)
+            .await?)
+    }
+
+    async fn quote(
+        &self,
+        code: &str,
+    ) -> Result<Quote, ProviderError> {
+        let id = listed(code)?;
+        fetch_quote(&self.api_client, &self.client, &id, code)
+            .await?
+            .ok_or(ProviderError::Empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::Query, routing::get, Router};
+    use crypto_service::coingecko_service::coingecko_client::CoinGeckoPlan;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support;
+
+    /// Local stand-in for CoinGecko. `/coins/markets` echoes the
+    /// page and page size back as cap and volume.
+    async fn client() -> CoinGeckoClient {
+        let app = Router::new()
+            .route(
+                "/coins/markets",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    Json(json!([{
+                        "id": "bitcoin",
+                        "symbol": "btc",
+                        "name": "Bitcoin",
+                        "current_price": 64000.0,
+                        "market_cap": query["page"].parse::<f64>().unwrap(),
+                        "total_volume": query["per_page"].parse::<f64>().unwrap()
+                    }]))
+                }),
+            )
+            .route(
+                "/coins/bitcoin",
+                get(|| async {
+                    Json(json!({
+                        "id": "bitcoin",
+                        "symbol": "btc",
+                        "name": "Bitcoin",
+                        "market_data": { "current_price": { "usd": 64000.0 } }
+                    }))
+                }),
+            )
+            .route(
+                "/simple/price",
+                get(|| async {
+                    Json(json!({
+                        "bitcoin": { "usd": 64000.0, "last_updated_at": 1711929600 }
+                    }))
+                }),
+            )
+            .route(
+                "/coins/bitcoin/market_chart/range",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    let from: f64 = query["from"].parse().unwrap();
+                    Json(json!({ "prices": [[from * 1000.0, 1.0]] }))
+                }),
+            );
+        let base_url = test_support::serve(app).await;
+
+        let mut client = CoinGeckoClient::new_with_key(
+            String::new(),
+            CoinGeckoPlan::Public,
+        );
+        client.base_url = base_url;
+        client
+    }
+
+    #[tokio::test]
+    async fn list_of_coins_pages_through_markets() {
+        let coins = fetch_list_of_coins(
+            &ApiClient::new(),
+            &client().await,
+            &ListOfCoinsRequest::new(10).with_offset(20),
+            Priority::Normal,
+        )
+        .await
+        .unwrap();
+        assert_eq!(coins[0].code, Some("BTC".into()));
+        assert_eq!(coins[0].rate, Some(64000.0));
+        assert_eq!(
+            (coins[0].cap, coins[0].volume),
+            (Some(3), Some(10))
+        );
+    }
+
+    #[tokio::test]
+    async fn coin_meta_by_code() {
+        let meta =
+            CoinGeckoProvider::new(ApiClient::new(), client().await)
+                .coin_meta("btc", Priority::Normal)
+                .await
+                .unwrap();
+        assert_eq!(meta.name, Some("Bitcoin".into()));
+        assert_eq!(meta.rate, Some(64000.0));
+    }
+
+    #[tokio::test]
+    async fn history_with_meta() {
+        let history = fetch_coin_history(
+            &ApiClient::new(),
+            &client().await,
+            "bitcoin",
+            &CoinHistoryRequest::new(
+                "BTC".into(),
+                5_000,
+                9_000,
+                true,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(history.name, Some("Bitcoin".into()));
+        assert_eq!(
+            history.history.unwrap()[0].date,
+            Some(5_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn quote_from_simple_price() {
+        let client = client().await;
+        let quote =
+            fetch_quote(&ApiClient::new(), &client, "bitcoin", "btc")
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(quote.rate, 64000.0);
+        assert_eq!(quote.time, 1_711_929_600_000);
+
+        let unknown =
+            CoinGeckoProvider::new(ApiClient::new(), client)
+                .quote("eth")
+                .await;
+        assert_eq!(unknown, Err(ProviderError::Empty));
+    }
+}
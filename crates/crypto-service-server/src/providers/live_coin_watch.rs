@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use axum::Json;
+use crypto_service::{
+    coin_watch_service::{
+        coin_watch_client::CoinWatchClient,
+        models::{
+            Coin, CoinHistory, CoinHistoryRequest,
+            CoinMeta, CoinMetaRequest, ListOfCoinsRequest,
+        },
+    },
+    data_source::{DataSource, Quote},
+};
+
+use super::market_data::{
+    MarketDataProvider, ProviderError,
+};
+use crate::{
+    alerts::alert_engine::now_millis,
+    api_client::{api_client::ApiClient, quota::Priority},
+};
+
+#[derive(Debug, Clone)]
+pub struct LiveCoinWatchProvider {
+    api_client: ApiClient,
+    client: CoinWatchClient,
+}
+
+impl LiveCoinWatchProvider {
+    pub fn new(
+        api_client: ApiClient,
+        client: CoinWatchClient,
+    ) -> Self {
+        Self { api_client, client }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for LiveCoinWatchProvider {
+    fn source(&self) -> DataSource {
+        DataSource::LiveCoinWatch
+    }
+
+    async fn list_coins(
+        &self,
+        request: &ListOfCoinsRequest,
+        priority: Priority,
+    ) -> Result<Vec<Coin>, ProviderError> {
+        self.api_client
+            .post_with_priority::<Vec<Coin>, CoinWatchClient, ListOfCoinsRequest>(
+                self.client.clone(),
+                "/coins/list",
+                request.clone(),
+                priority,
+            )
+            .await
+            .map(|(_, Json(coins))| coins)
+            .map_err(ProviderError::from)
+    }
+
+    async fn coin_meta(
+        &self,
+        code: &str,
+        priority: Priority,
+    ) -> Result<CoinMeta, ProviderError> {
+        self.api_client
+            .post_with_priority::<CoinMeta, CoinWatchClient, CoinMetaRequest>(
+                self.client.clone(),
+                "/coins/single",
+                CoinMetaRequest::new(code.to_uppercase()),
+                priority,
+            )
+            .await
+            .map(|(_, Json(coin_meta))| coin_meta)
+            .map_err(ProviderError::from)
+    }
+
+    async fn coin_history(
+        &self,
+        request: &CoinHistoryRequest,
+    ) -> Result<CoinHistory, ProviderError> {
+        self.api_client
+            .post::<CoinHistory, CoinWatchClient, CoinHistoryRequest>(
+                self.client.clone(),
+                "/coins/single/history",
+                request.clone(),
+            )
+            .await
+            .map(|(_, Json(history))| history)
+            .map_err(ProviderError::from)
+    }
+
+    /// Live Coin Watch doesn't timestamp its rates, they're taken
+    /// as current.
+    async fn quote(
+        &self,
+        code: &str,
+    ) -> Result<Quote, ProviderError> {
+        let coin_meta =
+            self.coin_meta(code, Priority::Normal).await?;
+        Ok(Quote {
+            code: code.to_uppercase(),
+            rate: coin_meta
+                .rate
+                .ok_or(ProviderError::Empty)?,
+            time: now_millis(),
+            source: DataSource::LiveCoinWatch,
+        })
+    }
+}
@@ -0,0 +1,738 @@
+use core::fmt::{self, Debug};
+use std::{future::Future, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{
+    http::{HeaderValue, StatusCode},
+    response::Response,
+    Json,
+};
+use crypto_service::{
+    coin_watch_service::models::{
+        Coin, CoinHistory, CoinHistoryRequest, CoinMeta,
+        ListOfCoinsRequest,
+    },
+    consensus::ConsensusQuote,
+    data_source::{DataSource, Quote},
+    tabular::Tabular,
+};
+use futures::future::join_all;
+use serde::Serialize;
+
+use crate::{
+    alerts::alert_engine::now_millis,
+    api_client::quota::Priority,
+    export::export_format::ExportFormat,
+};
+
+/// Response header naming the source that answered.
+pub const DATA_SOURCE_HEADER: &str = "x-data-source";
+
+/// Why a provider didn't answer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// The upstream call failed or was refused by the quota.
+    Upstream { status: StatusCode, message: String },
+    /// The provider has no way of answering this.
+    Unsupported,
+    /// It answered, but with nothing to use.
+    Empty,
+    /// Its price is older than the chain accepts.
+    Stale { age_secs: i64 },
+}
+
+impl ProviderError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ProviderError::Upstream { status, .. } => {
+                *status
+            }
+            ProviderError::Unsupported => {
+                StatusCode::NOT_IMPLEMENTED
+            }
+            ProviderError::Empty
+            | ProviderError::Stale { .. } => {
+                StatusCode::BAD_GATEWAY
+            }
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            ProviderError::Upstream { status, message } => {
+                write!(f, "{status}: {message}")
+            }
+            ProviderError::Unsupported => {
+                write!(f, "not offered")
+            }
+            ProviderError::Empty => write!(f, "no data"),
+            ProviderError::Stale { age_secs } => {
+                write!(f, "price is {age_secs}s old")
+            }
+        }
+    }
+}
+
+impl From<(StatusCode, Json<String>)> for ProviderError {
+    fn from(
+        (status, Json(message)): (StatusCode, Json<String>),
+    ) -> Self {
+        ProviderError::Upstream { status, message }
+    }
+}
+
+/// Every source [`FailoverProvider`] tried, and why each of them
+/// didn't answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailoverError(
+    pub Vec<(DataSource, ProviderError)>,
+);
+
+/// The shared status when all sources failed alike, so a spent
+/// quota is still a `429`, otherwise `502`.
+impl From<FailoverError> for (StatusCode, Json<String>) {
+    fn from(
+        FailoverError(failures): FailoverError,
+    ) -> Self {
+        let Some((_, first)) = failures.first() else {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json("No data sources configured".into()),
+            );
+        };
+        let status =
+            match failures.iter().all(|(_, error)| {
+                error.status() == first.status()
+            }) {
+                true => first.status(),
+                false => StatusCode::BAD_GATEWAY,
+            };
+        let message = failures
+            .iter()
+            .map(|(source, error)| {
+                format!("{}: {error}", source.as_str())
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        (status, Json(message))
+    }
+}
+
+/// A value and the source it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub source: DataSource,
+}
+
+impl<T> Sourced<T>
+where
+    T: Tabular + Serialize + Send + 'static,
+{
+    /// Like [`ExportFormat::respond`], naming the source in
+    /// [`DATA_SOURCE_HEADER`].
+    pub fn respond(self, format: ExportFormat) -> Response {
+        let mut response = format.respond(self.value);
+        response.headers_mut().insert(
+            DATA_SOURCE_HEADER,
+            HeaderValue::from_static(self.source.as_str()),
+        );
+        response
+    }
+}
+
+/// Coin market data from one upstream, in Live Coin Watch's shapes.
+///
+/// `priority` decides how far into the upstream's daily budget a
+/// call may dig, see [`Priority`].
+#[async_trait]
+pub trait MarketDataProvider: Debug + Send + Sync {
+    fn source(&self) -> DataSource;
+
+    async fn list_coins(
+        &self,
+        request: &ListOfCoinsRequest,
+        priority: Priority,
+    ) -> Result<Vec<Coin>, ProviderError>;
+
+    async fn coin_meta(
+        &self,
+        code: &str,
+        priority: Priority,
+    ) -> Result<CoinMeta, ProviderError>;
+
+    async fn coin_history(
+        &self,
+        request: &CoinHistoryRequest,
+    ) -> Result<CoinHistory, ProviderError>;
+
+    async fn quote(
+        &self,
+        code: &str,
+    ) -> Result<Quote, ProviderError>;
+}
+
+/// Asks providers in priority order until one answers with usable
+/// data, so an upstream that's down, out of credits or lagging is
+/// skipped.
+#[derive(Debug, Clone)]
+pub struct FailoverProvider {
+    providers: Vec<Arc<dyn MarketDataProvider>>,
+    order: Vec<DataSource>,
+    max_quote_age_ms: i64,
+    max_deviation: f64,
+}
+
+impl FailoverProvider {
+    /// Fails over through `order`, while any of `providers` can be
+    /// asked for by name. Quotes older than `max_quote_age_ms`
+    /// count as failures, and consensus leaves out quotes further
+    /// than `max_deviation` from the median.
+    pub fn new(
+        providers: Vec<Arc<dyn MarketDataProvider>>,
+        order: Vec<DataSource>,
+        max_quote_age_ms: i64,
+        max_deviation: f64,
+    ) -> Self {
+        Self {
+            providers,
+            order,
+            max_quote_age_ms,
+            max_deviation,
+        }
+    }
+
+    pub fn provider(
+        &self,
+        source: DataSource,
+    ) -> Option<Arc<dyn MarketDataProvider>> {
+        self.providers
+            .iter()
+            .find(|provider| provider.source() == source)
+            .cloned()
+    }
+
+    /// Just the `pinned` source when a request names one,
+    /// otherwise the configured order.
+    pub fn chain(
+        &self,
+        pinned: Option<DataSource>,
+    ) -> Vec<Arc<dyn MarketDataProvider>> {
+        match pinned {
+            Some(source) => {
+                self.provider(source).into_iter().collect()
+            }
+            None => self
+                .order
+                .iter()
+                .filter_map(|source| self.provider(*source))
+                .collect(),
+        }
+    }
+
+    pub async fn list_coins(
+        &self,
+        pinned: Option<DataSource>,
+        request: &ListOfCoinsRequest,
+    ) -> Result<Sourced<Vec<Coin>>, FailoverError> {
+        self.list_coins_with_priority(
+            pinned,
+            request,
+            Priority::Normal,
+        )
+        .await
+    }
+
+    /// Like [`FailoverProvider::list_coins`], with every upstream
+    /// call made at `priority`.
+    pub async fn list_coins_with_priority(
+        &self,
+        pinned: Option<DataSource>,
+        request: &ListOfCoinsRequest,
+        priority: Priority,
+    ) -> Result<Sourced<Vec<Coin>>, FailoverError> {
+        self.first_usable(
+            pinned,
+            |provider| {
+                let request = request.clone();
+                async move {
+                    provider
+                        .list_coins(&request, priority)
+                        .await
+                }
+            },
+            |coins| match coins.is_empty() {
+                true => Err(ProviderError::Empty),
+                false => Ok(()),
+            },
+        )
+        .await
+    }
+
+    pub async fn coin_meta(
+        &self,
+        pinned: Option<DataSource>,
+        code: &str,
+    ) -> Result<Sourced<CoinMeta>, FailoverError> {
+        self.coin_meta_with_priority(
+            pinned,
+            code,
+            Priority::Normal,
+        )
+        .await
+    }
+
+    /// Like [`FailoverProvider::coin_meta`], with every upstream
+    /// call made at `priority`.
+    pub async fn coin_meta_with_priority(
+        &self,
+        pinned: Option<DataSource>,
+        code: &str,
+        priority: Priority,
+    ) -> Result<Sourced<CoinMeta>, FailoverError> {
+        self.first_usable(
+            pinned,
+            |provider| {
+                let code = code.to_string();
+                async move {
+                    provider.coin_meta(&code, priority).await
+                }
+            },
+            |meta| match meta.rate {
+                Some(_) => Ok(()),
+                None => Err(ProviderError::Empty),
+            },
+        )
+        .await
+    }
+
+    pub async fn coin_history(
+        &self,
+        pinned: Option<DataSource>,
+        request: &CoinHistoryRequest,
+    ) -> Result<Sourced<CoinHistory>, FailoverError> {
+        self.first_usable(
+            pinned,
+            |provider| {
+                let request = request.clone();
+                async move {
+                    provider.coin_history(&request).await
+                }
+            },
+            |history| match &history.history {
+                Some(points) if !points.is_empty() => {
+                    Ok(())
+                }
+                _ => Err(ProviderError::Empty),
+            },
+        )
+        .await
+    }
+
+    pub async fn quote(
+        &self,
+        pinned: Option<DataSource>,
+        code: &str,
+    ) -> Result<Sourced<Quote>, FailoverError> {
+        let now = now_millis();
+        self.first_usable(
+            pinned,
+            |provider| {
+                let code = code.to_string();
+                async move { provider.quote(&code).await }
+            },
+            |quote| self.fresh(quote, now),
+        )
+        .await
+    }
+
+    /// Median of a fresh quote from every source in the configured
+    /// order, all asked at once. `max_deviation` overrides the
+    /// configured one.
+    pub async fn consensus(
+        &self,
+        code: &str,
+        max_deviation: Option<f64>,
+    ) -> Result<ConsensusQuote, FailoverError> {
+        let now = now_millis();
+        let chain = self.chain(None);
+        let results = join_all(
+            chain
+                .iter()
+                .map(|provider| provider.quote(code)),
+        )
+        .await;
+
+        let mut quotes = vec![];
+        let mut failures = vec![];
+        for (provider, result) in chain.iter().zip(results)
+        {
+            match result.and_then(|quote| {
+                self.fresh(&quote, now).map(|_| quote)
+            }) {
+                Ok(quote) => quotes.push(quote),
+                Err(error) => failures
+                    .push((provider.source(), error)),
+            }
+        }
+        ConsensusQuote::from_quotes(
+            code,
+            quotes,
+            max_deviation.unwrap_or(self.max_deviation),
+        )
+        .ok_or(FailoverError(failures))
+    }
+
+    fn fresh(
+        &self,
+        quote: &Quote,
+        now: i64,
+    ) -> Result<(), ProviderError> {
+        let age = now - quote.time;
+        match age > self.max_quote_age_ms {
+            true => Err(ProviderError::Stale {
+                age_secs: age / 1000,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    async fn first_usable<T, F, Fut>(
+        &self,
+        pinned: Option<DataSource>,
+        call: F,
+        usable: impl Fn(&T) -> Result<(), ProviderError>,
+    ) -> Result<Sourced<T>, FailoverError>
+    where
+        F: Fn(Arc<dyn MarketDataProvider>) -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        let mut failures = vec![];
+        for provider in self.chain(pinned) {
+            let source = provider.source();
+            match call(provider).await.and_then(|value| {
+                usable(&value).map(|_| value)
+            }) {
+                Ok(value) => {
+                    return Ok(Sourced { value, source })
+                }
+                Err(error) => {
+                    println!(
+                        "{} didn't answer: {error}",
+                        source.as_str()
+                    );
+                    failures.push((source, error));
+                }
+            }
+        }
+        Err(FailoverError(failures))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Answers every call with `result` and counts the calls.
+    #[derive(Debug)]
+    struct FakeProvider {
+        source: DataSource,
+        result: Result<f64, ProviderError>,
+        time: i64,
+        calls: Mutex<u32>,
+    }
+
+    impl FakeProvider {
+        fn new(
+            source: DataSource,
+            result: Result<f64, ProviderError>,
+        ) -> Arc<Self> {
+            Self::at(source, result, now_millis())
+        }
+
+        /// Quoting prices last updated at `time`.
+        fn at(
+            source: DataSource,
+            result: Result<f64, ProviderError>,
+            time: i64,
+        ) -> Arc<Self> {
+            Arc::new(Self {
+                source,
+                result,
+                time,
+                calls: Mutex::new(0),
+            })
+        }
+
+        fn calls(&self) -> u32 {
+            *self.calls.lock().unwrap()
+        }
+
+        fn answer(&self) -> Result<f64, ProviderError> {
+            *self.calls.lock().unwrap() += 1;
+            self.result.clone()
+        }
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for FakeProvider {
+        fn source(&self) -> DataSource {
+            self.source
+        }
+
+        async fn list_coins(
+            &self,
+            _request: &ListOfCoinsRequest,
+            _priority: Priority,
+        ) -> Result<Vec<Coin>, ProviderError> {
+            Err(ProviderError::Unsupported)
+        }
+
+        async fn coin_meta(
+            &self,
+            code: &str,
+            _priority: Priority,
+        ) -> Result<CoinMeta, ProviderError> {
+            let rate = self.answer()?;
+            Ok(CoinMeta {
+                name: None,
+                symbol: None,
+                rank: None,
+                color: None,
+                png64: None,
+                webp64: None,
+                all_time_high_usd: None,
+                code: Some(code.into()),
+                rate: (rate > 0.0).then_some(rate),
+                delta: None,
+            })
+        }
+
+        async fn coin_history(
+            &self,
+            _request: &CoinHistoryRequest,
+        ) -> Result<CoinHistory, ProviderError> {
+            Err(ProviderError::Unsupported)
+        }
+
+        async fn quote(
+            &self,
+            code: &str,
+        ) -> Result<Quote, ProviderError> {
+            Ok(Quote {
+                code: code.into(),
+                rate: self.answer()?,
+                time: self.time,
+                source: self.source,
+            })
+        }
+    }
+
+    fn upstream(status: StatusCode) -> ProviderError {
+        ProviderError::Upstream {
+            status,
+            message: "nope".into(),
+        }
+    }
+
+    fn chain(
+        providers: Vec<Arc<FakeProvider>>,
+    ) -> FailoverProvider {
+        let order = providers
+            .iter()
+            .map(|provider| provider.source)
+            .collect();
+        FailoverProvider::new(
+            providers
+                .into_iter()
+                .map(|provider| {
+                    provider as Arc<dyn MarketDataProvider>
+                })
+                .collect(),
+            order,
+            60_000,
+            0.02,
+        )
+    }
+
+    #[tokio::test]
+    async fn first_answer_wins() {
+        let first = FakeProvider::new(
+            DataSource::LiveCoinWatch,
+            Ok(1.0),
+        );
+        let second = FakeProvider::new(
+            DataSource::CoinGecko,
+            Ok(2.0),
+        );
+        let providers =
+            chain(vec![first.clone(), second.clone()]);
+
+        let quote =
+            providers.quote(None, "BTC").await.unwrap();
+
+        assert_eq!(quote.source, DataSource::LiveCoinWatch);
+        assert_eq!(quote.value.rate, 1.0);
+        assert_eq!(second.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_errors_and_empty_data() {
+        let providers = chain(vec![
+            FakeProvider::new(
+                DataSource::LiveCoinWatch,
+                Err(upstream(
+                    StatusCode::TOO_MANY_REQUESTS,
+                )),
+            ),
+            FakeProvider::new(
+                DataSource::CoinGecko,
+                Ok(0.0),
+            ),
+            FakeProvider::new(DataSource::Binance, Ok(3.0)),
+        ]);
+
+        let meta =
+            providers.coin_meta(None, "BTC").await.unwrap();
+
+        assert_eq!(meta.source, DataSource::Binance);
+        assert_eq!(meta.value.rate, Some(3.0));
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_stale_quotes() {
+        let providers = chain(vec![
+            FakeProvider::at(
+                DataSource::CoinApi,
+                Ok(1.0),
+                now_millis() - 120_000,
+            ),
+            FakeProvider::new(DataSource::Binance, Ok(2.0)),
+        ]);
+
+        let quote =
+            providers.quote(None, "BTC").await.unwrap();
+
+        assert_eq!(quote.source, DataSource::Binance);
+    }
+
+    #[tokio::test]
+    async fn pinned_source_has_no_failover() {
+        let first = FakeProvider::new(
+            DataSource::LiveCoinWatch,
+            Ok(1.0),
+        );
+        let pinned = FakeProvider::new(
+            DataSource::CoinGecko,
+            Err(upstream(
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        );
+        let providers =
+            chain(vec![first.clone(), pinned.clone()]);
+
+        let error = providers
+            .quote(Some(DataSource::CoinGecko), "BTC")
+            .await
+            .unwrap_err();
+
+        assert_eq!(first.calls(), 0);
+        assert_eq!(pinned.calls(), 1);
+        let (status, _) =
+            <(StatusCode, Json<String>)>::from(error);
+        assert_eq!(
+            status,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn failures_share_a_status_or_become_bad_gateway() {
+        let (status, Json(message)) = FailoverError(vec![
+            (
+                DataSource::LiveCoinWatch,
+                upstream(StatusCode::TOO_MANY_REQUESTS),
+            ),
+            (
+                DataSource::CoinGecko,
+                upstream(StatusCode::TOO_MANY_REQUESTS),
+            ),
+        ])
+        .into();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            message,
+            "livecoinwatch: 429 Too Many Requests: nope; coingecko: 429 Too Many Requests: nope"
+        );
+
+        let (status, _) = FailoverError(vec![
+            (
+                DataSource::LiveCoinWatch,
+                upstream(StatusCode::TOO_MANY_REQUESTS),
+            ),
+            (
+                DataSource::Binance,
+                ProviderError::Unsupported,
+            ),
+        ])
+        .into();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn consensus_of_every_source() {
+        let providers = chain(vec![
+            FakeProvider::new(
+                DataSource::LiveCoinWatch,
+                Ok(100.0),
+            ),
+            FakeProvider::new(
+                DataSource::CoinGecko,
+                Ok(101.0),
+            ),
+            FakeProvider::new(
+                DataSource::Binance,
+                Err(upstream(StatusCode::BAD_REQUEST)),
+            ),
+            FakeProvider::new(
+                DataSource::CoinApi,
+                Ok(150.0),
+            ),
+        ]);
+
+        let consensus =
+            providers.consensus("BTC", None).await.unwrap();
+
+        assert_eq!(consensus.rate, 100.5);
+        assert_eq!(consensus.outliers.len(), 1);
+        assert!(consensus.disagreement);
+
+        let lenient = providers
+            .consensus("BTC", Some(1.0))
+            .await
+            .unwrap();
+        assert_eq!(lenient.sources.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn no_consensus_without_quotes() {
+        let providers = chain(vec![FakeProvider::new(
+            DataSource::LiveCoinWatch,
+            Err(upstream(StatusCode::TOO_MANY_REQUESTS)),
+        )]);
+
+        let error = providers
+            .consensus("BTC", None)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.0.len(), 1);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod binance;
+pub mod coinapi;
+pub mod coingecko;
+pub mod live_coin_watch;
+pub mod market_data;
+pub mod models;
@@ -0,0 +1,40 @@
+use crypto_service::data_source::DataSource;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct SourceParams {
+    /// Only ask this upstream, without failing over. The
+    /// `COIN_DATA_SOURCES` chain when left out.
+    pub source: Option<DataSource>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct ConsensusParams {
+    /// Fraction of the median a quote may stray from before it's
+    /// left out, `CONSENSUS_MAX_DEVIATION` when left out.
+    pub max_deviation: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn source_from_query() {
+        let params: SourceParams = serde_json::from_value(
+            json!({ "source": "coingecko" }),
+        )
+        .unwrap();
+        assert_eq!(params.source, Some(DataSource::CoinGecko));
+    }
+
+    #[test]
+    fn no_source_means_failover() {
+        let params: SourceParams =
+            serde_json::from_value(json!({})).unwrap();
+        assert_eq!(params.source, None);
+    }
+}
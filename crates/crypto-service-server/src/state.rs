@@ -1,27 +1,74 @@
-use crypto_service::coin_watch_service::coin_watch_client::CoinWatchClient;
+use crypto_service::{
+    alphavantage_service::alpha_vantage_client::AlphaVantageClient,
+    binance_service::binance_client::BinanceClient,
+    coin_watch_service::coin_watch_client::CoinWatchClient,
+    coinapi_service::coinapi_client::CoinApiClient,
+    coingecko_service::coingecko_client::CoinGeckoClient,
+};
 
 use crate::{
-    alphavantage_api::alpha_client::AlphaAdvantageClient,
+    alerts::alert_engine::AlertEngine,
+    alphavantage_api::trades_feed::TradesFeed,
     api_client::api_client::ApiClient,
+    auth::api_keys::ApiKeys, icons::icon_cache::IconCache,
+    providers::market_data::FailoverProvider,
+    store::quote_store::QuoteStore,
+    stream::price_hub::PriceHub,
+    webhooks::webhook_dispatcher::WebhookDispatcher,
 };
 
 #[derive(Debug, Clone)]
 pub struct AppState {
-    pub alpha_client: AlphaAdvantageClient,
+    pub alpha_client: AlphaVantageClient,
     pub coin_watch_client: CoinWatchClient,
+    pub coingecko_client: CoinGeckoClient,
+    pub binance_client: BinanceClient,
+    pub coinapi_client: CoinApiClient,
     pub api_client: ApiClient,
+    pub alert_engine: AlertEngine,
+    pub webhooks: WebhookDispatcher,
+    pub price_hub: PriceHub,
+    pub trades_feed: TradesFeed,
+    pub quote_store: QuoteStore,
+    pub api_keys: ApiKeys,
+    pub icons: IconCache,
+    /// Where coin routes fetch from unless asked for a source.
+    pub providers: FailoverProvider,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        alpha_client: AlphaAdvantageClient,
+        alpha_client: AlphaVantageClient,
         coin_watch_client: CoinWatchClient,
+        coingecko_client: CoinGeckoClient,
+        binance_client: BinanceClient,
+        coinapi_client: CoinApiClient,
         api_client: ApiClient,
+        alert_engine: AlertEngine,
+        webhooks: WebhookDispatcher,
+        price_hub: PriceHub,
+        trades_feed: TradesFeed,
+        quote_store: QuoteStore,
+        api_keys: ApiKeys,
+        icons: IconCache,
+        providers: FailoverProvider,
     ) -> Self {
         Self {
             alpha_client,
             coin_watch_client,
+            coingecko_client,
+            binance_client,
+            coinapi_client,
             api_client,
+            alert_engine,
+            webhooks,
+            price_hub,
+            trades_feed,
+            quote_store,
+            api_keys,
+            icons,
+            providers,
         }
     }
 }
@@ -0,0 +1,750 @@
+use std::fmt;
+
+use axum::{http::StatusCode, Json};
+use crypto_service::coin_watch_service::{
+    coin_watch_client::CoinWatchClient,
+    models::{CoinHistory, CoinHistoryRequest},
+};
+use rusqlite::{params, OptionalExtension, Row};
+
+use super::{
+    models::{
+        BackfillJob, BackfillStatus, CreateBackfillRequest,
+    },
+    quote_store::QuoteStore,
+};
+use crate::{
+    alerts::alert_engine::now_millis,
+    api_client::{api_client::ApiClient, quota::Priority},
+    state::AppState,
+};
+
+/// Live Coin Watch thins out history points as the requested range
+/// grows, so a day per request keeps them at full resolution.
+pub const DEFAULT_CHUNK_SECS: i64 = 24 * 60 * 60;
+pub const DEFAULT_MAX_GAP_SECS: i64 = 60 * 60;
+/// Most chunks a single job may plan, about ten years of days for
+/// one coin, since they're all inserted in one transaction.
+pub const MAX_CHUNKS: i64 = 4_000;
+
+/// Ranges within `start..=end` where consecutive `dates` (sorted)
+/// are more than `max_gap` apart, including the stretches before
+/// the first and after the last date.
+pub fn find_gaps(
+    dates: &[i64],
+    start: i64,
+    end: i64,
+    max_gap: i64,
+) -> Vec<(i64, i64)> {
+    let mut gaps = vec![];
+    let mut previous = start;
+    for &date in dates
+        .iter()
+        .filter(|date| (start..=end).contains(*date))
+    {
+        if date - previous > max_gap {
+            gaps.push((previous, date));
+        }
+        previous = date;
+    }
+    if end - previous > max_gap {
+        gaps.push((previous, end));
+    }
+    gaps
+}
+
+/// Why a backfill couldn't be planned.
+#[derive(Debug)]
+pub enum BackfillError {
+    /// The request itself can't be planned, such as a start after
+    /// its end.
+    Invalid(String),
+    Store(rusqlite::Error),
+}
+
+impl fmt::Display for BackfillError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            BackfillError::Invalid(message) => {
+                write!(f, "{message}")
+            }
+            BackfillError::Store(e) => {
+                write!(f, "Backfill store failed: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BackfillError {}
+
+impl From<rusqlite::Error> for BackfillError {
+    fn from(e: rusqlite::Error) -> Self {
+        BackfillError::Store(e)
+    }
+}
+
+impl BackfillError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BackfillError::Invalid(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            BackfillError::Store(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// Milliseconds in `secs`, `default` when unset. `None` unless
+/// positive and small enough to fit.
+fn positive_millis(
+    secs: Option<i64>,
+    default: i64,
+) -> Option<i64> {
+    Some(secs.unwrap_or(default))
+        .filter(|secs| *secs > 0)
+        .and_then(|secs| secs.checked_mul(1000))
+}
+
+/// Splits each gap into consecutive ranges of at most `chunk`,
+/// which must be positive.
+pub fn split_into_chunks(
+    gaps: &[(i64, i64)],
+    chunk: i64,
+) -> Vec<(i64, i64)> {
+    debug_assert!(chunk > 0, "Chunks must be positive");
+    gaps.iter()
+        .flat_map(|&(start, end)| {
+            (start..end).step_by(chunk as usize).map(
+                move |from| {
+                    (from, from.saturating_add(chunk).min(end))
+                },
+            )
+        })
+        .collect()
+}
+
+/// Fills gaps in the [`QuoteStore`] from `/coins/single/history`.
+///
+/// A job records its chunks up front and marks each as done once
+/// its points are stored, so an interrupted job picks up where it
+/// left off. Every request spends one credit of the job's budget,
+/// failed ones included.
+#[derive(Debug, Clone)]
+pub struct Backfill {
+    pub store: QuoteStore,
+    pub api_client: ApiClient,
+    pub coin_watch_client: CoinWatchClient,
+}
+
+impl Backfill {
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            store: state.quote_store.clone(),
+            api_client: state.api_client.clone(),
+            coin_watch_client: state
+                .coin_watch_client
+                .clone(),
+        }
+    }
+
+    /// Plans a job covering the gaps in what's stored for each
+    /// code. It doesn't fetch anything until [`Backfill::run`].
+    pub fn create_job(
+        &self,
+        request: CreateBackfillRequest,
+    ) -> Result<BackfillJob, BackfillError> {
+        let codes: Vec<String> = request
+            .codes
+            .iter()
+            .map(|code| code.trim().to_uppercase())
+            .filter(|code| !code.is_empty())
+            .collect();
+        let chunk_ms = positive_millis(
+            request.chunk_secs,
+            DEFAULT_CHUNK_SECS,
+        );
+        let max_gap_ms = positive_millis(
+            request.max_gap_secs,
+            DEFAULT_MAX_GAP_SECS,
+        );
+        let (Some(chunk_ms), Some(max_gap_ms)) =
+            (chunk_ms, max_gap_ms)
+        else {
+            return Err(BackfillError::Invalid(
+                "A backfill needs positive durations".into(),
+            ));
+        };
+        if codes.is_empty() || request.start >= request.end {
+            return Err(BackfillError::Invalid(
+                "A backfill needs coin codes and a start before its end".into(),
+            ));
+        }
+        let most_chunks = request
+            .end
+            .checked_sub(request.start)
+            .and_then(|range| range.checked_add(chunk_ms - 1))
+            .map(|range| range / chunk_ms)
+            .and_then(|per_code| {
+                per_code.checked_mul(codes.len() as i64)
+            });
+        if most_chunks.map_or(true, |n| n > MAX_CHUNKS) {
+            return Err(BackfillError::Invalid(format!(
+                "A backfill may plan at most {MAX_CHUNKS} chunks, use fewer codes, a shorter range or longer chunks"
+            )));
+        }
+
+        let mut chunks = vec![];
+        for code in &codes {
+            let dates = self.store.stored_dates(
+                code,
+                request.start,
+                request.end,
+            )?;
+            let gaps = find_gaps(
+                &dates,
+                request.start,
+                request.end,
+                max_gap_ms,
+            );
+            chunks.extend(
+                split_into_chunks(&gaps, chunk_ms)
+                    .into_iter()
+                    .map(|(start, end)| (code, start, end)),
+            );
+        }
+
+        let now = now_millis();
+        let id = {
+            let mut connection = self.store.connection();
+            let transaction = connection.transaction()?;
+            transaction.execute(
+                "INSERT INTO backfill_jobs (codes, start, end,
+                   chunk_ms, credit_budget, status, created_at,
+                   updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                params![
+                    codes.join(","),
+                    request.start,
+                    request.end,
+                    chunk_ms,
+                    request.credit_budget,
+                    BackfillStatus::Pending.as_str(),
+                    now,
+                ],
+            )?;
+            let id = transaction.last_insert_rowid();
+            {
+                let mut statement = transaction.prepare_cached(
+                    "INSERT INTO backfill_chunks
+                       (job_id, code, start, end)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )?;
+                for (code, start, end) in chunks {
+                    statement.execute(params![
+                        id, code, start, end
+                    ])?;
+                }
+            }
+            transaction.commit()?;
+            id
+        };
+
+        Ok(self
+            .job(id)?
+            .expect("Job was just inserted"))
+    }
+
+    pub fn job(
+        &self,
+        id: i64,
+    ) -> rusqlite::Result<Option<BackfillJob>> {
+        self.store
+            .connection()
+            .query_row(
+                &format!("{JOB_QUERY} WHERE id = ?1"),
+                [id],
+                job_from_row,
+            )
+            .optional()
+    }
+
+    /// All jobs, newest first.
+    pub fn jobs(
+        &self,
+    ) -> rusqlite::Result<Vec<BackfillJob>> {
+        let connection = self.store.connection();
+        let mut statement = connection.prepare_cached(
+            &format!("{JOB_QUERY} ORDER BY id DESC"),
+        )?;
+        let jobs = statement
+            .query_map([], job_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(jobs)
+    }
+
+    pub fn add_credits(
+        &self,
+        id: i64,
+        credits: u32,
+    ) -> rusqlite::Result<()> {
+        self.store.connection().execute(
+            "UPDATE backfill_jobs
+             SET credit_budget = credit_budget + ?2, updated_at = ?3
+             WHERE id = ?1",
+            params![id, credits, now_millis()],
+        )?;
+        Ok(())
+    }
+
+    /// Jobs left running by a previous process, which are reset to
+    /// pending so they can be run again.
+    pub fn interrupted_jobs(
+        &self,
+    ) -> rusqlite::Result<Vec<i64>> {
+        let connection = self.store.connection();
+        let mut statement = connection.prepare_cached(
+            "UPDATE backfill_jobs SET status = ?1
+             WHERE status = ?2 RETURNING id",
+        )?;
+        let ids = statement
+            .query_map(
+                params![
+                    BackfillStatus::Pending.as_str(),
+                    BackfillStatus::Running.as_str(),
+                ],
+                |row| row.get(0),
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+
+    /// Fetches the job's remaining chunks until it's done, out of
+    /// credits or an error occurs. Does nothing if the job is
+    /// already running or completed.
+    pub async fn run(
+        &self,
+        id: i64,
+    ) -> rusqlite::Result<Option<BackfillJob>> {
+        if !self.claim(id)? {
+            return self.job(id);
+        }
+        if let Err(e) = self.run_chunks(id).await {
+            self.set_status(
+                id,
+                BackfillStatus::Failed,
+                Some(e.to_string()),
+            )?;
+        }
+        self.job(id)
+    }
+
+    async fn run_chunks(
+        &self,
+        id: i64,
+    ) -> rusqlite::Result<()> {
+        loop {
+            let Some(job) = self.job(id)? else {
+                return Ok(());
+            };
+            let Some((code, start, end)) =
+                self.next_chunk(id)?
+            else {
+                return self.set_status(
+                    id,
+                    BackfillStatus::Completed,
+                    None,
+                );
+            };
+            if job.credits_used >= job.credit_budget {
+                return self.set_status(
+                    id,
+                    BackfillStatus::BudgetExhausted,
+                    None,
+                );
+            }
+
+            let history = self
+                .api_client
+                .post_with_priority::<CoinHistory, CoinWatchClient, CoinHistoryRequest>(
+                    self.coin_watch_client.clone(),
+                    "/coins/single/history",
+                    CoinHistoryRequest::new(
+                        code.clone(),
+                        start as u64,
+                        end as u64,
+                        false,
+                    ),
+                    Priority::Background,
+                )
+                .await;
+            // Refused calls never reach Live Coin Watch, so they
+            // don't cost the job a credit.
+            if !matches!(
+                history,
+                Err((StatusCode::TOO_MANY_REQUESTS, _))
+            ) {
+                self.spend_credit(id)?;
+            }
+
+            match history {
+                Ok((_, Json(history))) => {
+                    let points =
+                        self.store.record_history(
+                            &code,
+                            &history
+                                .history
+                                .unwrap_or_default(),
+                        )?;
+                    self.store.connection().execute(
+                        "UPDATE backfill_chunks SET points = ?4
+                         WHERE job_id = ?1 AND code = ?2
+                           AND start = ?3",
+                        params![id, code, start, points],
+                    )?;
+                }
+                Err((status, Json(message))) => {
+                    return self.set_status(
+                        id,
+                        BackfillStatus::Failed,
+                        Some(format!(
+                            "Fetching {code} from {start} to {end} failed: {status} {message}"
+                        )),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Marks the job as running unless it already is or has
+    /// completed, returning whether it did.
+    fn claim(&self, id: i64) -> rusqlite::Result<bool> {
+        self.store
+            .connection()
+            .execute(
+                "UPDATE backfill_jobs
+                 SET status = ?2, last_error = NULL, updated_at = ?3
+                 WHERE id = ?1 AND status NOT IN (?2, ?4)",
+                params![
+                    id,
+                    BackfillStatus::Running.as_str(),
+                    now_millis(),
+                    BackfillStatus::Completed.as_str(),
+                ],
+            )
+            .map(|updated| updated == 1)
+    }
+
+    fn next_chunk(
+        &self,
+        id: i64,
+    ) -> rusqlite::Result<Option<(String, i64, i64)>> {
+        self.store
+            .connection()
+            .query_row(
+                "SELECT code, start, end FROM backfill_chunks
+                 WHERE job_id = ?1 AND points IS NULL
+                 ORDER BY code, start LIMIT 1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+    }
+
+    fn spend_credit(
+        &self,
+        id: i64,
+    ) -> rusqlite::Result<()> {
+        self.store.connection().execute(
+            "UPDATE backfill_jobs
+             SET credits_used = credits_used + 1, updated_at = ?2
+             WHERE id = ?1",
+            params![id, now_millis()],
+        )?;
+        Ok(())
+    }
+
+    fn set_status(
+        &self,
+        id: i64,
+        status: BackfillStatus,
+        error: Option<String>,
+    ) -> rusqlite::Result<()> {
+        self.store.connection().execute(
+            "UPDATE backfill_jobs
+             SET status = ?2, last_error = ?3, updated_at = ?4
+             WHERE id = ?1",
+            params![id, status.as_str(), error, now_millis()],
+        )?;
+        Ok(())
+    }
+}
+
+const JOB_QUERY: &str = "SELECT *,
+    (SELECT COUNT(*) FROM backfill_chunks
+     WHERE job_id = id) AS chunks_total,
+    (SELECT COUNT(points) FROM backfill_chunks
+     WHERE job_id = id) AS chunks_done,
+    (SELECT COALESCE(SUM(points), 0) FROM backfill_chunks
+     WHERE job_id = id) AS points_stored
+    FROM backfill_jobs";
+
+fn job_from_row(
+    row: &Row,
+) -> rusqlite::Result<BackfillJob> {
+    let codes: String = row.get("codes")?;
+    let status: String = row.get("status")?;
+    Ok(BackfillJob {
+        id: row.get("id")?,
+        codes: codes
+            .split(',')
+            .filter(|code| !code.is_empty())
+            .map(str::to_string)
+            .collect(),
+        start: row.get("start")?,
+        end: row.get("end")?,
+        chunk_ms: row.get("chunk_ms")?,
+        credit_budget: row.get("credit_budget")?,
+        credits_used: row.get("credits_used")?,
+        status: BackfillStatus::parse(&status)
+            .unwrap_or(BackfillStatus::Failed),
+        chunks_total: row.get("chunks_total")?,
+        chunks_done: row.get("chunks_done")?,
+        points_stored: row.get("points_stored")?,
+        last_error: row.get("last_error")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use axum::{extract::State, routing::post, Router};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support;
+
+    const HOUR: i64 = 60 * 60 * 1000;
+    const DAY: i64 = 24 * HOUR;
+
+    /// Local stand-in for Live Coin Watch returning a point every
+    /// half hour of the requested range.
+    async fn history(
+        State(requests): State<Arc<AtomicU32>>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        requests.fetch_add(1, Ordering::SeqCst);
+        let start = body["start"].as_i64().unwrap();
+        let end = body["end"].as_i64().unwrap();
+        let history: Vec<Value> = (start..=end)
+            .step_by((HOUR / 2) as usize)
+            .map(
+                |date| json!({ "date": date, "rate": 1.0 }),
+            )
+            .collect();
+        Json(json!({ "history": history }))
+    }
+
+    async fn backfill() -> (Backfill, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let app = Router::new()
+            .route("/coins/single/history", post(history))
+            .with_state(requests.clone());
+        let base_url = test_support::serve(app).await;
+
+        let mut coin_watch_client =
+            CoinWatchClient::new_with_key("key".into());
+        coin_watch_client.base_url = base_url;
+        let backfill = Backfill {
+            store: QuoteStore::open_in_memory().unwrap(),
+            api_client: ApiClient::new(),
+            coin_watch_client,
+        };
+        (backfill, requests)
+    }
+
+    fn request(
+        credit_budget: u32,
+    ) -> CreateBackfillRequest {
+        CreateBackfillRequest {
+            codes: vec!["btc".into()],
+            start: 0,
+            end: 3 * DAY,
+            credit_budget,
+            chunk_secs: None,
+            max_gap_secs: None,
+        }
+    }
+
+    #[test]
+    fn find_gaps_includes_edges() {
+        assert_eq!(
+            find_gaps(&[3, 4, 10], 0, 14, 2),
+            vec![(0, 3), (4, 10), (10, 14)]
+        );
+    }
+
+    #[test]
+    fn find_gaps_without_dates() {
+        assert_eq!(find_gaps(&[], 0, 10, 2), vec![(0, 10)]);
+    }
+
+    #[test]
+    fn find_gaps_ignores_dates_out_of_range() {
+        assert_eq!(
+            find_gaps(&[-5, 1, 2, 20], 0, 3, 2),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn split_into_chunks_caps_length() {
+        assert_eq!(
+            split_into_chunks(&[(0, 5), (10, 12)], 2),
+            vec![(0, 2), (2, 4), (4, 5), (10, 12)]
+        );
+    }
+
+    #[tokio::test]
+    async fn create_job_plans_only_missing_chunks() {
+        let (backfill, _) = backfill().await;
+        let points: Vec<_> = (0..=DAY)
+            .step_by(HOUR as usize)
+            .map(|date| {
+                crypto_service::coin_watch_service::models::History {
+                    date: Some(date),
+                    rate: Some(1.0),
+                    volume: None,
+                    cap: None,
+                    liquidity: None,
+                }
+            })
+            .collect();
+        backfill
+            .store
+            .record_history("BTC", &points)
+            .unwrap();
+
+        let job = backfill.create_job(request(10)).unwrap();
+        assert_eq!(job.codes, vec!["BTC"]);
+        assert_eq!(job.status, BackfillStatus::Pending);
+        assert_eq!(job.chunks_total, 2);
+    }
+
+    #[tokio::test]
+    async fn create_job_refuses_what_it_cannot_plan() {
+        let (backfill, _) = backfill().await;
+        for (chunk_secs, end) in [
+            (Some(0), 3 * DAY),
+            (Some(-1), 3 * DAY),
+            (Some(i64::MAX), 3 * DAY),
+            (None, 0),
+            (Some(1), 365 * DAY),
+            (None, (MAX_CHUNKS + 1) * DAY),
+        ] {
+            let error = backfill
+                .create_job(CreateBackfillRequest {
+                    chunk_secs,
+                    end,
+                    ..request(10)
+                })
+                .unwrap_err();
+            assert_eq!(
+                error.status(),
+                StatusCode::UNPROCESSABLE_ENTITY
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn run_stops_at_budget_and_resumes() {
+        let (backfill, requests) = backfill().await;
+        let job = backfill.create_job(request(2)).unwrap();
+        assert_eq!(job.chunks_total, 3);
+
+        let job =
+            backfill.run(job.id).await.unwrap().unwrap();
+        assert_eq!(
+            job.status,
+            BackfillStatus::BudgetExhausted
+        );
+        assert_eq!(job.chunks_done, 2);
+        assert_eq!(job.credits_used, 2);
+
+        backfill.add_credits(job.id, 5).unwrap();
+        let job =
+            backfill.run(job.id).await.unwrap().unwrap();
+        assert_eq!(job.status, BackfillStatus::Completed);
+        assert_eq!(job.chunks_done, 3);
+        assert_eq!(job.credits_used, 3);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // Chunk edges are fetched twice but stored once.
+        assert_eq!(job.points_stored, 3 * 49);
+        assert_eq!(
+            backfill
+                .store
+                .stored_dates("BTC", 0, 3 * DAY)
+                .unwrap()
+                .len(),
+            3 * 48 + 1
+        );
+        assert_eq!(
+            backfill
+                .create_job(request(1))
+                .unwrap()
+                .chunks_total,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn run_fails_on_upstream_error() {
+        let (mut backfill, _) = backfill().await;
+        backfill.coin_watch_client.base_url =
+            "http://127.0.0.1:1".into();
+        let job = backfill.create_job(request(5)).unwrap();
+
+        let job =
+            backfill.run(job.id).await.unwrap().unwrap();
+        assert_eq!(job.status, BackfillStatus::Failed);
+        assert_eq!(job.credits_used, 1);
+        assert!(job.last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn completed_job_is_not_run_again() {
+        let (backfill, requests) = backfill().await;
+        let job = backfill.create_job(request(5)).unwrap();
+        backfill.run(job.id).await.unwrap();
+        backfill.run(job.id).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn interrupted_jobs_are_reset() {
+        let (backfill, _) = backfill().await;
+        let job = backfill.create_job(request(5)).unwrap();
+        assert!(backfill.claim(job.id).unwrap());
+        assert!(!backfill.claim(job.id).unwrap());
+
+        assert_eq!(
+            backfill.interrupted_jobs().unwrap(),
+            vec![job.id]
+        );
+        assert_eq!(
+            backfill.job(job.id).unwrap().unwrap().status,
+            BackfillStatus::Pending
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use axum::Json;
+use crypto_service::coin_watch_service::{
+    coin_watch_client::CoinWatchClient,
+    models::{Coin, CoinMapRequest},
+};
+
+use crate::{
+    alerts::alert_engine::now_millis, api_client::quota::Priority,
+    state::AppState,
+};
+
+/// Codes in a comma separated watchlist such as `"btc, eth"`,
+/// uppercased and without blanks.
+pub fn parse_watchlist(watchlist: &str) -> Vec<String> {
+    watchlist
+        .split(',')
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+/// Snapshots the quotes of `watchlist` into the
+/// [`QuoteStore`](super::quote_store::QuoteStore) once per
+/// `interval`.
+pub async fn run_collector(
+    state: AppState,
+    watchlist: Vec<String>,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let coins = state
+            .api_client
+            .post_with_priority::<Vec<Coin>, CoinWatchClient, CoinMapRequest>(
+                state.coin_watch_client.clone(),
+                "/coins/map",
+                CoinMapRequest::new(watchlist.clone()),
+                Priority::Background,
+            )
+            .await;
+
+        match coins {
+            Ok((_, Json(coins))) => {
+                if let Err(e) = state
+                    .quote_store
+                    .record_snapshots(&coins, now_millis())
+                {
+                    println!("Failed to store snapshots: {e}");
+                }
+            }
+            Err((status, Json(message))) => println!(
+                "Failed to collect watchlist quotes: {status} {message}"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_watchlist_normalizes_codes() {
+        assert_eq!(
+            parse_watchlist(" btc,ETH,, "),
+            vec!["BTC", "ETH"]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use rusqlite::Connection;
+
+/// Schema changes in the order they were made. The index of the
+/// last applied one is kept in SQLite's `user_version`, so only
+/// ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: Quotes from `/coins/map` and points from
+    // `/coins/single/history`.
+    "CREATE TABLE coin_snapshots (
+        code TEXT NOT NULL,
+        taken_at INTEGER NOT NULL,
+        rate REAL,
+        volume INTEGER,
+        cap INTEGER,
+        delta_hour REAL,
+        delta_day REAL,
+        delta_week REAL,
+        delta_month REAL,
+        delta_quarter REAL,
+        delta_year REAL,
+        PRIMARY KEY (code, taken_at)
+    ) WITHOUT ROWID;
+    CREATE TABLE history_points (
+        code TEXT NOT NULL,
+        date INTEGER NOT NULL,
+        rate REAL,
+        volume INTEGER,
+        cap INTEGER,
+        liquidity INTEGER,
+        PRIMARY KEY (code, date)
+    ) WITHOUT ROWID;",
+    // 2: Resumable history backfills, see `backfill.rs`.
+    "CREATE TABLE backfill_jobs (
+        id INTEGER PRIMARY KEY,
+        codes TEXT NOT NULL,
+        start INTEGER NOT NULL,
+        end INTEGER NOT NULL,
+        chunk_ms INTEGER NOT NULL,
+        credit_budget INTEGER NOT NULL,
+        credits_used INTEGER NOT NULL DEFAULT 0,
+        status TEXT NOT NULL,
+        last_error TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE backfill_chunks (
+        job_id INTEGER NOT NULL REFERENCES backfill_jobs (id),
+        code TEXT NOT NULL,
+        start INTEGER NOT NULL,
+        end INTEGER NOT NULL,
+        points INTEGER,
+        PRIMARY KEY (job_id, code, start)
+    ) WITHOUT ROWID;",
+    // 3: API keys created through `/v1/admin/keys`, see
+    // `auth/api_keys.rs`. Only the SHA-256 of a key is kept.
+    "CREATE TABLE api_keys (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        key_hash TEXT NOT NULL UNIQUE,
+        scopes TEXT NOT NULL,
+        requests_per_minute INTEGER NOT NULL,
+        burst INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        revoked_at INTEGER
+    );",
+];
+
+pub fn schema_version(
+    connection: &Connection,
+) -> rusqlite::Result<usize> {
+    connection
+        .pragma_query_value(None, "user_version", |row| {
+            row.get::<_, i64>(0)
+        })
+        .map(|version| version as usize)
+}
+
+/// Applies the migrations `connection` hasn't seen yet, each in
+/// its own transaction.
+pub fn migrate(
+    connection: &mut Connection,
+) -> rusqlite::Result<()> {
+    let version = schema_version(connection)?;
+    for (index, migration) in
+        MIGRATIONS.iter().enumerate().skip(version)
+    {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(
+            None,
+            "user_version",
+            (index + 1) as i64,
+        )?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_sets_version() {
+        let mut connection =
+            Connection::open_in_memory().unwrap();
+        migrate(&mut connection).unwrap();
+        assert_eq!(
+            schema_version(&connection).unwrap(),
+            MIGRATIONS.len()
+        );
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut connection =
+            Connection::open_in_memory().unwrap();
+        migrate(&mut connection).unwrap();
+        migrate(&mut connection).unwrap();
+    }
+}
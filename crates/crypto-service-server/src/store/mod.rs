@@ -0,0 +1,6 @@
+pub mod backfill;
+pub mod collector;
+pub mod migrations;
+pub mod models;
+pub mod quote_store;
+pub mod store_handlers;
@@ -0,0 +1,176 @@
+use crypto_service::{
+    coin_watch_service::models::Coin,
+    tabular::{Rows, Tabular},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+
+/// A [`Coin`] quote as recorded by the collector.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CoinSnapshot {
+    /// Unix milliseconds.
+    pub taken_at: i64,
+    #[serde(flatten)]
+    pub coin: Coin,
+}
+
+impl Tabular for CoinSnapshot {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "taken_at",
+            "code",
+            "rate",
+            "volume",
+            "cap",
+            "delta_hour",
+            "delta_day",
+            "delta_week",
+            "delta_month",
+            "delta_quarter",
+            "delta_year",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let taken_at = json!(self.taken_at);
+        Box::new(self.coin.into_rows().map(
+            move |mut row| {
+                row.insert(0, taken_at.clone());
+                row
+            },
+        ))
+    }
+}
+
+/// Query for the local history routes, all bounds in Unix
+/// milliseconds and inclusive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct LocalHistoryParams {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillStatus {
+    Pending,
+    Running,
+    Completed,
+    /// Stopped before spending more than its credit budget, can be
+    /// resumed with more credits.
+    BudgetExhausted,
+    /// Stopped on an upstream or store error, can be resumed.
+    Failed,
+}
+
+impl BackfillStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackfillStatus::Pending => "pending",
+            BackfillStatus::Running => "running",
+            BackfillStatus::Completed => "completed",
+            BackfillStatus::BudgetExhausted => {
+                "budget_exhausted"
+            }
+            BackfillStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        [
+            BackfillStatus::Pending,
+            BackfillStatus::Running,
+            BackfillStatus::Completed,
+            BackfillStatus::BudgetExhausted,
+            BackfillStatus::Failed,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == status)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackfillJob {
+    pub id: i64,
+    pub codes: Vec<String>,
+    /// Unix milliseconds.
+    pub start: i64,
+    /// Unix milliseconds.
+    pub end: i64,
+    pub chunk_ms: i64,
+    /// Upstream requests the job may make, one per chunk.
+    pub credit_budget: u32,
+    pub credits_used: u32,
+    pub status: BackfillStatus,
+    pub chunks_total: u32,
+    pub chunks_done: u32,
+    pub points_stored: u64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreateBackfillRequest {
+    pub codes: Vec<String>,
+    /// Unix milliseconds.
+    pub start: i64,
+    /// Unix milliseconds.
+    pub end: i64,
+    pub credit_budget: u32,
+    /// Longest range fetched with a single request.
+    pub chunk_secs: Option<i64>,
+    /// Longest stretch between stored points that isn't a gap.
+    pub max_gap_secs: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResumeBackfillRequest {
+    /// Added to the job's budget before resuming.
+    #[serde(default)]
+    pub additional_credits: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_service::coin_watch_service::models::Delta;
+
+    use super::*;
+
+    #[test]
+    fn backfill_status_str_matches_serde() {
+        let status = BackfillStatus::BudgetExhausted;
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            status.as_str()
+        );
+        assert_eq!(
+            BackfillStatus::parse(status.as_str()),
+            Some(status)
+        );
+    }
+
+    #[test]
+    fn snapshot_row_matches_columns() {
+        let snapshot = CoinSnapshot {
+            taken_at: 1,
+            coin: Coin::new(
+                Some("BTC".into()),
+                Some(1.0),
+                None,
+                None,
+                Delta::new(
+                    None, None, None, None, None, None,
+                ),
+            ),
+        };
+        let row = snapshot.into_rows().next().unwrap();
+        assert_eq!(
+            row.len(),
+            CoinSnapshot::columns().len()
+        );
+        assert_eq!(row[..2], [json!(1), json!("BTC")]);
+    }
+}
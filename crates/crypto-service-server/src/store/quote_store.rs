@@ -0,0 +1,462 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crypto_service::coin_watch_service::models::{
+    Coin, CoinHistory, Delta, History,
+};
+use rusqlite::{params, Connection};
+
+use super::{
+    migrations::migrate,
+    models::{CoinSnapshot, LocalHistoryParams},
+};
+
+/// Most rows returned by a single local query.
+pub const MAX_QUERY_ROWS: u32 = 10_000;
+
+/// Quotes and history points collected from Live Coin Watch, kept
+/// in an embedded SQLite database so history outlives upstream
+/// windows and credits.
+#[derive(Debug, Clone)]
+pub struct QuoteStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl QuoteStore {
+    /// Opens or creates the database at `path` and migrates it.
+    pub fn open(
+        path: impl AsRef<Path>,
+    ) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        // Lets readers carry on while the collector writes.
+        connection.query_row(
+            "PRAGMA journal_mode = WAL",
+            [],
+            |_| Ok(()),
+        )?;
+        Self::with_connection(connection)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(
+        mut connection: Connection,
+    ) -> rusqlite::Result<Self> {
+        migrate(&mut connection)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Records `coins` as quoted at `taken_at`, skipping coins
+    /// without a code. Returns the number of rows written.
+    pub fn record_snapshots(
+        &self,
+        coins: &[Coin],
+        taken_at: i64,
+    ) -> rusqlite::Result<usize> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let mut written = 0;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT OR REPLACE INTO coin_snapshots
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for coin in coins {
+                let Some(code) = &coin.code else {
+                    continue;
+                };
+                written += statement.execute(params![
+                    code.to_uppercase(),
+                    taken_at,
+                    coin.rate,
+                    coin.volume,
+                    coin.cap,
+                    coin.delta.hour,
+                    coin.delta.day,
+                    coin.delta.week,
+                    coin.delta.month,
+                    coin.delta.quarter,
+                    coin.delta.year,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(written)
+    }
+
+    /// Records history points for `code`, replacing points already
+    /// stored for the same date. Points without a date are skipped.
+    pub fn record_history(
+        &self,
+        code: &str,
+        points: &[History],
+    ) -> rusqlite::Result<usize> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let mut written = 0;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT OR REPLACE INTO history_points
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for point in points {
+                let Some(date) = point.date else {
+                    continue;
+                };
+                written += statement.execute(params![
+                    code.to_uppercase(),
+                    date,
+                    point.rate,
+                    point.volume,
+                    point.cap,
+                    point.liquidity,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(written)
+    }
+
+    /// Snapshots of `code` within the bounds, oldest first.
+    pub fn snapshots(
+        &self,
+        code: &str,
+        params: &LocalHistoryParams,
+    ) -> rusqlite::Result<Vec<CoinSnapshot>> {
+        let (start, end, limit) = bounds(params);
+        let connection = self.connection();
+        let mut statement = connection.prepare_cached(
+            "SELECT * FROM coin_snapshots
+             WHERE code = ?1 AND taken_at BETWEEN ?2 AND ?3
+             ORDER BY taken_at LIMIT ?4",
+        )?;
+        let rows = statement.query_map(
+            params![code.to_uppercase(), start, end, limit],
+            |row| {
+                Ok(CoinSnapshot {
+                    taken_at: row.get("taken_at")?,
+                    coin: Coin::new(
+                        row.get("code")?,
+                        row.get("rate")?,
+                        row.get("volume")?,
+                        row.get("cap")?,
+                        Delta::new(
+                            row.get("delta_hour")?,
+                            row.get("delta_day")?,
+                            row.get("delta_week")?,
+                            row.get("delta_month")?,
+                            row.get("delta_quarter")?,
+                            row.get("delta_year")?,
+                        ),
+                    ),
+                })
+            },
+        )?;
+        rows.collect()
+    }
+
+    /// History of `code` within the bounds, oldest first, in the
+    /// shape of a `/coins/single/history` response.
+    ///
+    /// Collector snapshots fill in dates without a history point,
+    /// so the result covers everything recorded locally.
+    pub fn history(
+        &self,
+        code: &str,
+        params: &LocalHistoryParams,
+    ) -> rusqlite::Result<CoinHistory> {
+        let code = code.to_uppercase();
+        let (start, end, limit) = bounds(params);
+        let connection = self.connection();
+        let mut statement = connection.prepare_cached(
+            "SELECT date, rate, volume, cap, liquidity
+             FROM history_points
+             WHERE code = ?1 AND date BETWEEN ?2 AND ?3
+             UNION ALL
+             SELECT taken_at, rate, volume, cap, NULL
+             FROM coin_snapshots
+             WHERE code = ?1 AND taken_at BETWEEN ?2 AND ?3
+               AND taken_at NOT IN (
+                 SELECT date FROM history_points WHERE code = ?1
+               )
+             ORDER BY 1 LIMIT ?4",
+        )?;
+        let history = statement
+            .query_map(
+                params![code, start, end, limit],
+                |row| {
+                    Ok(History {
+                        date: row.get(0)?,
+                        rate: row.get(1)?,
+                        volume: row.get(2)?,
+                        cap: row.get(3)?,
+                        liquidity: row.get(4)?,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<History>>>()?;
+
+        Ok(CoinHistory {
+            code: Some(code),
+            name: None,
+            symbol: None,
+            rank: None,
+            color: None,
+            png64: None,
+            webp64: None,
+            all_time_high_usd: None,
+            links: None,
+            history: Some(history),
+        })
+    }
+
+    /// Dates of everything stored for `code` between `start` and
+    /// `end`, history points and snapshots alike, sorted.
+    pub fn stored_dates(
+        &self,
+        code: &str,
+        start: i64,
+        end: i64,
+    ) -> rusqlite::Result<Vec<i64>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare_cached(
+            "SELECT date FROM history_points
+             WHERE code = ?1 AND date BETWEEN ?2 AND ?3
+             UNION
+             SELECT taken_at FROM coin_snapshots
+             WHERE code = ?1 AND taken_at BETWEEN ?2 AND ?3
+             ORDER BY 1",
+        )?;
+        let dates = statement
+            .query_map(
+                params![code.to_uppercase(), start, end],
+                |row| row.get(0),
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(dates)
+    }
+
+    pub(crate) fn connection(
+        &self,
+    ) -> MutexGuard<Connection> {
+        self.connection.lock().expect("Store lock poisoned")
+    }
+}
+
+fn bounds(params: &LocalHistoryParams) -> (i64, i64, u32) {
+    (
+        params.start.unwrap_or(0),
+        params.end.unwrap_or(i64::MAX),
+        params
+            .limit
+            .unwrap_or(MAX_QUERY_ROWS)
+            .min(MAX_QUERY_ROWS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(code: &str, rate: f64) -> Coin {
+        Coin::new(
+            Some(code.into()),
+            Some(rate),
+            Some(10),
+            None,
+            Delta::new(
+                Some(1.01),
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+        )
+    }
+
+    fn point(date: i64, rate: f64) -> History {
+        History {
+            date: Some(date),
+            rate: Some(rate),
+            volume: None,
+            cap: None,
+            liquidity: Some(5),
+        }
+    }
+
+    fn all() -> LocalHistoryParams {
+        LocalHistoryParams::default()
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        store
+            .record_snapshots(
+                &[coin("BTC", 2.0), coin("ETH", 1.0)],
+                200,
+            )
+            .unwrap();
+        store
+            .record_snapshots(&[coin("BTC", 1.0)], 100)
+            .unwrap();
+
+        assert_eq!(
+            store.snapshots("btc", &all()).unwrap(),
+            vec![
+                CoinSnapshot {
+                    taken_at: 100,
+                    coin: coin("BTC", 1.0)
+                },
+                CoinSnapshot {
+                    taken_at: 200,
+                    coin: coin("BTC", 2.0)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_codes_are_uppercased() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        store
+            .record_snapshots(&[coin("btc", 1.0)], 100)
+            .unwrap();
+        assert_eq!(
+            store.snapshots("BTC", &all()).unwrap(),
+            vec![CoinSnapshot {
+                taken_at: 100,
+                coin: coin("BTC", 1.0)
+            }]
+        );
+    }
+
+    #[test]
+    fn snapshots_are_bounded() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        for taken_at in 0..10 {
+            store
+                .record_snapshots(
+                    &[coin("BTC", 1.0)],
+                    taken_at,
+                )
+                .unwrap();
+        }
+        let params = LocalHistoryParams {
+            start: Some(2),
+            end: Some(8),
+            limit: Some(3),
+        };
+        let taken_at: Vec<i64> = store
+            .snapshots("BTC", &params)
+            .unwrap()
+            .iter()
+            .map(|snapshot| snapshot.taken_at)
+            .collect();
+        assert_eq!(taken_at, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn history_points_replace_same_date() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        store
+            .record_history(
+                "btc",
+                &[point(1, 1.0), point(2, 2.0)],
+            )
+            .unwrap();
+        store
+            .record_history("BTC", &[point(2, 3.0)])
+            .unwrap();
+
+        assert_eq!(
+            store.history("BTC", &all()).unwrap().history,
+            Some(vec![point(1, 1.0), point(2, 3.0)])
+        );
+    }
+
+    #[test]
+    fn history_is_filled_in_from_snapshots() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        store
+            .record_history("BTC", &[point(2, 2.0)])
+            .unwrap();
+        store
+            .record_snapshots(&[coin("BTC", 9.0)], 2)
+            .unwrap();
+        store
+            .record_snapshots(&[coin("BTC", 3.0)], 3)
+            .unwrap();
+
+        let history = store
+            .history("BTC", &all())
+            .unwrap()
+            .history
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], point(2, 2.0));
+        assert_eq!(history[1].rate, Some(3.0));
+        assert_eq!(history[1].liquidity, None);
+    }
+
+    #[test]
+    fn stored_dates_cover_both_tables() {
+        let store = QuoteStore::open_in_memory().unwrap();
+        store
+            .record_history(
+                "BTC",
+                &[point(1, 1.0), point(3, 1.0)],
+            )
+            .unwrap();
+        store
+            .record_snapshots(&[coin("BTC", 1.0)], 3)
+            .unwrap();
+        store
+            .record_snapshots(&[coin("BTC", 1.0)], 5)
+            .unwrap();
+
+        assert_eq!(
+            store.stored_dates("btc", 0, 4).unwrap(),
+            vec![1, 3]
+        );
+        assert_eq!(
+            store.stored_dates("BTC", 0, 10).unwrap(),
+            vec![1, 3, 5]
+        );
+    }
+
+    #[test]
+    fn open_persists_across_restarts() {
+        let path = std::env::temp_dir().join(format!(
+            "crypto-service-quotes-{}.db",
+            std::process::id()
+        ));
+        QuoteStore::open(&path)
+            .unwrap()
+            .record_snapshots(&[coin("BTC", 1.0)], 1)
+            .unwrap();
+
+        let reopened = QuoteStore::open(&path).unwrap();
+        assert_eq!(
+            reopened
+                .snapshots("BTC", &all())
+                .unwrap()
+                .len(),
+            1
+        );
+        drop(reopened);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!(
+                "{}{suffix}",
+                path.display()
+            ));
+        }
+    }
+}
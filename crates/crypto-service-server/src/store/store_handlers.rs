@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+
+use super::{
+    backfill::Backfill,
+    models::{
+        BackfillJob, BackfillStatus, CreateBackfillRequest,
+        LocalHistoryParams, ResumeBackfillRequest,
+    },
+};
+use crate::{
+    export::export_format::ExportFormat, state::AppState,
+};
+
+/// Quotes recorded by the watchlist collector.
+#[utoipa::path(
+    get,
+    path = "/v1/local/coins/{code}/snapshots",
+    tag = "local",
+    params(
+        ("code" = String, Path, description = "Coin code such as `BTC`"),
+        LocalHistoryParams,
+    ),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<CoinSnapshot>),
+    )
+)]
+pub async fn get_local_snapshots(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Path(code): Path<String>,
+    Query(params): Query<LocalHistoryParams>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .quote_store
+        .snapshots(&code, &params)
+        .map(|snapshots| format.respond(snapshots))
+        .map_err(internal_error)
+}
+
+/// Locally recorded history, for when the upstream window or
+/// credits run out.
+#[utoipa::path(
+    get,
+    path = "/v1/local/coins/{code}/history",
+    tag = "local",
+    params(
+        ("code" = String, Path, description = "Coin code such as `BTC`"),
+        LocalHistoryParams,
+    ),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = CoinHistory),
+    )
+)]
+pub async fn get_local_history(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Path(code): Path<String>,
+    Query(params): Query<LocalHistoryParams>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .quote_store
+        .history(&code, &params)
+        .map(|history| format.respond(history))
+        .map_err(internal_error)
+}
+
+pub async fn list_backfills(
+    State(state): State<AppState>,
+) -> Result<
+    (StatusCode, Json<Vec<BackfillJob>>),
+    (StatusCode, Json<String>),
+> {
+    Backfill::from_state(&state)
+        .jobs()
+        .map(|jobs| (StatusCode::OK, Json(jobs)))
+        .map_err(internal_error)
+}
+
+pub async fn get_backfill(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<
+    (StatusCode, Json<BackfillJob>),
+    (StatusCode, Json<String>),
+> {
+    match Backfill::from_state(&state).job(id) {
+        Ok(Some(job)) => Ok((StatusCode::OK, Json(job))),
+        Ok(None) => Err(no_backfill(id)),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Plans a backfill and starts it in the background.
+pub async fn create_backfill(
+    State(state): State<AppState>,
+    Json(body): Json<CreateBackfillRequest>,
+) -> Result<
+    (StatusCode, Json<BackfillJob>),
+    (StatusCode, Json<String>),
+> {
+    let backfill = Backfill::from_state(&state);
+    let job = backfill
+        .create_job(body)
+        .map_err(|e| (e.status(), Json(e.to_string())))?;
+    spawn_backfill(backfill, job.id);
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Restarts a stopped backfill, optionally with more credits.
+pub async fn resume_backfill(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    body: Option<Json<ResumeBackfillRequest>>,
+) -> Result<
+    (StatusCode, Json<BackfillJob>),
+    (StatusCode, Json<String>),
+> {
+    let backfill = Backfill::from_state(&state);
+    let job = backfill
+        .job(id)
+        .map_err(internal_error)?
+        .ok_or_else(|| no_backfill(id))?;
+    if matches!(
+        job.status,
+        BackfillStatus::Running | BackfillStatus::Completed
+    ) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(format!(
+                "Backfill {id} is {}",
+                job.status.as_str()
+            )),
+        ));
+    }
+
+    let Json(body) = body.unwrap_or_default();
+    backfill
+        .add_credits(id, body.additional_credits)
+        .map_err(internal_error)?;
+    spawn_backfill(backfill.clone(), id);
+    backfill
+        .job(id)
+        .map_err(internal_error)?
+        .map(|job| (StatusCode::ACCEPTED, Json(job)))
+        .ok_or_else(|| no_backfill(id))
+}
+
+pub fn spawn_backfill(backfill: Backfill, id: i64) {
+    tokio::spawn(async move {
+        if let Err(e) = backfill.run(id).await {
+            println!("Backfill {id} stopped: {e}");
+        }
+    });
+}
+
+fn no_backfill(id: i64) -> (StatusCode, Json<String>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(format!("No backfill with id '{id}'")),
+    )
+}
+
+fn internal_error(
+    e: rusqlite::Error,
+) -> (StatusCode, Json<String>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string()))
+}
@@ -0,0 +1,3 @@
+pub mod models;
+pub mod price_hub;
+pub mod stream_handler;
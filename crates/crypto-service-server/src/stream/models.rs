@@ -0,0 +1,67 @@
+use crypto_service::coin_watch_service::models::Coin;
+use serde::{Deserialize, Serialize};
+
+/// Text frames sent by `/v1/stream` clients.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { codes: Vec<String> },
+    Unsubscribe { codes: Vec<String> },
+}
+
+/// Text frames sent to `/v1/stream` clients.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// All codes the socket is subscribed to after a
+    /// (un)subscribe message.
+    Subscriptions {
+        codes: Vec<String>,
+    },
+    /// Coins whose rate or delta changed since the last poll, or
+    /// the latest known quotes right after subscribing.
+    Update {
+        coins: Vec<Coin>,
+    },
+    /// Sent periodically so clients can detect a dead connection.
+    Heartbeat {
+        timestamp: i64,
+    },
+    /// The socket fell behind and `skipped` updates were dropped,
+    /// it's followed by an update with the latest quotes.
+    Lagged {
+        skipped: u64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_subscribe() {
+        assert_eq!(
+            serde_json::from_str::<ClientMessage>(
+                r#"{"type":"subscribe","codes":["BTC"]}"#
+            )
+            .unwrap(),
+            ClientMessage::Subscribe {
+                codes: vec!["BTC".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn encode_lagged() {
+        assert_eq!(
+            serde_json::to_string(&ServerMessage::Lagged {
+                skipped: 3
+            })
+            .unwrap(),
+            r#"{"type":"lagged","skipped":3}"#
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::Json;
+use crypto_service::coin_watch_service::{
+    coin_watch_client::CoinWatchClient,
+    models::{Coin, CoinMapRequest},
+};
+use tokio::sync::broadcast;
+
+use crate::state::AppState;
+
+/// Updates buffered per socket before it's considered lagging.
+pub const UPDATE_BUFFER: usize = 64;
+
+#[derive(Debug, Default)]
+struct Subscriptions {
+    /// Number of sockets subscribed to each code.
+    counts: BTreeMap<String, usize>,
+    /// Last quote seen for each subscribed code.
+    latest: HashMap<String, Coin>,
+}
+
+/// Shares a single upstream poll between all `/v1/stream` sockets.
+///
+/// Sockets register the codes they're interested in, the poller
+/// fetches the union of them and broadcasts the coins that changed
+/// to every socket, which filter out what they didn't ask for.
+#[derive(Debug, Clone)]
+pub struct PriceHub {
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    updates: broadcast::Sender<Arc<Vec<Coin>>>,
+}
+
+impl Default for PriceHub {
+    fn default() -> Self {
+        Self {
+            subscriptions: Arc::default(),
+            updates: broadcast::channel(UPDATE_BUFFER).0,
+        }
+    }
+}
+
+impl PriceHub {
+    pub fn updates(
+        &self,
+    ) -> broadcast::Receiver<Arc<Vec<Coin>>> {
+        self.updates.subscribe()
+    }
+
+    pub fn subscribe(&self, codes: &[String]) {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .expect("Stream lock poisoned");
+        for code in codes {
+            *subscriptions
+                .counts
+                .entry(code.clone())
+                .or_default() += 1;
+        }
+    }
+
+    pub fn unsubscribe(&self, codes: &[String]) {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .expect("Stream lock poisoned");
+        for code in codes {
+            let Some(count) =
+                subscriptions.counts.get_mut(code)
+            else {
+                continue;
+            };
+            *count -= 1;
+            if *count == 0 {
+                subscriptions.counts.remove(code);
+                subscriptions.latest.remove(code);
+            }
+        }
+    }
+
+    /// Codes with at least one subscriber, sorted.
+    pub fn codes(&self) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .expect("Stream lock poisoned")
+            .counts
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Last known quotes for `codes`, leaving out the ones that
+    /// haven't been polled yet.
+    pub fn latest(&self, codes: &[String]) -> Vec<Coin> {
+        let subscriptions = self
+            .subscriptions
+            .lock()
+            .expect("Stream lock poisoned");
+        codes
+            .iter()
+            .filter_map(|code| {
+                subscriptions.latest.get(code)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Records `coins` as the latest quotes and broadcasts the
+    /// ones whose rate or delta changed, returning how many did.
+    pub fn publish(&self, coins: Vec<Coin>) -> usize {
+        let changed: Vec<Coin> = {
+            let mut subscriptions = self
+                .subscriptions
+                .lock()
+                .expect("Stream lock poisoned");
+            let Subscriptions { counts, latest } =
+                &mut *subscriptions;
+            coins
+                .into_iter()
+                .filter(|coin| {
+                    let Some(code) = &coin.code else {
+                        return false;
+                    };
+                    if !counts.contains_key(code) {
+                        return false;
+                    }
+                    let changed = latest.get(code).map_or(
+                        true,
+                        |last| {
+                            last.rate != coin.rate
+                                || last.delta != coin.delta
+                        },
+                    );
+                    latest
+                        .insert(code.clone(), coin.clone());
+                    changed
+                })
+                .collect()
+        };
+
+        let count = changed.len();
+        if count > 0 {
+            // Only fails when no socket is listening.
+            let _ = self.updates.send(Arc::new(changed));
+        }
+        count
+    }
+}
+
+/// Polls `/coins/map` for every code subscribed to on
+/// `/v1/stream` once per `interval` and publishes the changes.
+pub async fn run_price_stream(
+    state: AppState,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let codes = state.price_hub.codes();
+        if codes.is_empty() {
+            continue;
+        }
+
+        let coins = state
+            .api_client
+            .post::<Vec<Coin>, CoinWatchClient, CoinMapRequest>(
+                state.coin_watch_client.clone(),
+                "/coins/map",
+                CoinMapRequest::new(codes),
+            )
+            .await;
+
+        match coins {
+            Ok((_, Json(coins))) => {
+                state.price_hub.publish(coins);
+            }
+            Err((status, Json(message))) => println!(
+                "Failed to poll prices for stream: {status} {message}"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_service::coin_watch_service::models::Delta;
+
+    use super::*;
+
+    fn coin(code: &str, rate: f64) -> Coin {
+        Coin::new(
+            Some(code.into()),
+            Some(rate),
+            None,
+            None,
+            Delta::new(None, None, None, None, None, None),
+        )
+    }
+
+    fn codes(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|code| code.to_string()).collect()
+    }
+
+    #[test]
+    fn codes_are_union_of_subscriptions() {
+        let hub = PriceHub::default();
+        hub.subscribe(&codes(&["ETH", "BTC"]));
+        hub.subscribe(&codes(&["BTC"]));
+        assert_eq!(hub.codes(), codes(&["BTC", "ETH"]));
+    }
+
+    #[test]
+    fn code_stays_until_last_unsubscribe() {
+        let hub = PriceHub::default();
+        hub.subscribe(&codes(&["BTC"]));
+        hub.subscribe(&codes(&["BTC"]));
+        hub.unsubscribe(&codes(&["BTC"]));
+        assert_eq!(hub.codes(), codes(&["BTC"]));
+        hub.unsubscribe(&codes(&["BTC", "ETH"]));
+        assert!(hub.codes().is_empty());
+    }
+
+    #[test]
+    fn publish_broadcasts_only_changes() {
+        let hub = PriceHub::default();
+        let mut updates = hub.updates();
+        hub.subscribe(&codes(&["BTC", "ETH"]));
+
+        assert_eq!(
+            hub.publish(vec![
+                coin("BTC", 1.0),
+                coin("ETH", 2.0)
+            ]),
+            2
+        );
+        assert_eq!(
+            hub.publish(vec![
+                coin("BTC", 1.0),
+                coin("ETH", 3.0)
+            ]),
+            1
+        );
+        updates.try_recv().unwrap();
+        assert_eq!(
+            *updates.try_recv().unwrap(),
+            vec![coin("ETH", 3.0)]
+        );
+    }
+
+    #[test]
+    fn publish_ignores_unsubscribed_coins() {
+        let hub = PriceHub::default();
+        hub.subscribe(&codes(&["BTC"]));
+        assert_eq!(hub.publish(vec![coin("ETH", 2.0)]), 0);
+        assert!(hub.latest(&codes(&["ETH"])).is_empty());
+    }
+
+    #[test]
+    fn latest_is_dropped_with_last_subscriber() {
+        let hub = PriceHub::default();
+        hub.subscribe(&codes(&["BTC"]));
+        hub.publish(vec![coin("BTC", 1.0)]);
+        assert_eq!(
+            hub.latest(&codes(&["BTC"])),
+            vec![coin("BTC", 1.0)]
+        );
+
+        hub.unsubscribe(&codes(&["BTC"]));
+        hub.subscribe(&codes(&["BTC"]));
+        assert!(hub.latest(&codes(&["BTC"])).is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use crypto_service::coin_watch_service::models::Coin;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{
+    models::{ClientMessage, ServerMessage},
+    price_hub::PriceHub,
+};
+use crate::{
+    alerts::alert_engine::now_millis, state::AppState,
+};
+
+pub const HEARTBEAT_INTERVAL: Duration =
+    Duration::from_secs(30);
+pub const MAX_CODES_PER_SOCKET: usize = 100;
+
+/// Codes a single socket is subscribed to. They're released from
+/// the [`PriceHub`] when the session is dropped.
+#[derive(Debug)]
+pub struct StreamSession {
+    hub: PriceHub,
+    codes: BTreeSet<String>,
+}
+
+impl StreamSession {
+    pub fn new(hub: PriceHub) -> Self {
+        Self {
+            hub,
+            codes: BTreeSet::new(),
+        }
+    }
+
+    /// Applies a client message and returns the replies.
+    pub fn handle(
+        &mut self,
+        message: ClientMessage,
+    ) -> Vec<ServerMessage> {
+        match message {
+            ClientMessage::Subscribe { codes } => {
+                let added: Vec<String> = normalize(codes)
+                    .difference(&self.codes)
+                    .cloned()
+                    .collect();
+                if self.codes.len() + added.len()
+                    > MAX_CODES_PER_SOCKET
+                {
+                    return vec![ServerMessage::Error {
+                        message: format!(
+                            "A socket can subscribe to at most {MAX_CODES_PER_SOCKET} codes"
+                        ),
+                    }];
+                }
+                self.hub.subscribe(&added);
+                self.codes.extend(added.iter().cloned());
+
+                let mut replies =
+                    vec![self.subscriptions()];
+                let coins = self.hub.latest(&added);
+                if !coins.is_empty() {
+                    replies.push(ServerMessage::Update {
+                        coins,
+                    });
+                }
+                replies
+            }
+            ClientMessage::Unsubscribe { codes } => {
+                let removed: Vec<String> = normalize(codes)
+                    .intersection(&self.codes)
+                    .cloned()
+                    .collect();
+                self.hub.unsubscribe(&removed);
+                for code in &removed {
+                    self.codes.remove(code);
+                }
+                vec![self.subscriptions()]
+            }
+        }
+    }
+
+    /// The part of a broadcast update this socket subscribed to.
+    pub fn filter(
+        &self,
+        coins: &[Coin],
+    ) -> Option<ServerMessage> {
+        let coins: Vec<Coin> = coins
+            .iter()
+            .filter(|coin| {
+                coin.code.as_ref().is_some_and(|code| {
+                    self.codes.contains(code)
+                })
+            })
+            .cloned()
+            .collect();
+        (!coins.is_empty())
+            .then_some(ServerMessage::Update { coins })
+    }
+
+    /// Latest quotes for every subscribed code, sent after the
+    /// socket lagged so it doesn't keep stale prices.
+    pub fn resync(&self) -> ServerMessage {
+        let codes: Vec<String> =
+            self.codes.iter().cloned().collect();
+        ServerMessage::Update {
+            coins: self.hub.latest(&codes),
+        }
+    }
+
+    fn subscriptions(&self) -> ServerMessage {
+        ServerMessage::Subscriptions {
+            codes: self.codes.iter().cloned().collect(),
+        }
+    }
+}
+
+impl Drop for StreamSession {
+    fn drop(&mut self) {
+        let codes: Vec<String> =
+            self.codes.iter().cloned().collect();
+        self.hub.unsubscribe(&codes);
+    }
+}
+
+fn normalize(codes: Vec<String>) -> BTreeSet<String> {
+    codes
+        .into_iter()
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+/// Live prices over a WebSocket. Clients send
+/// [`ClientMessage`]s and receive [`ServerMessage`]s, both as
+/// JSON text frames.
+pub async fn stream(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> Response {
+    ws.on_upgrade(move |socket| {
+        handle_socket(socket, state.price_hub)
+    })
+}
+
+async fn handle_socket(
+    mut socket: WebSocket,
+    hub: PriceHub,
+) {
+    let mut updates = hub.updates();
+    let mut session = StreamSession::new(hub);
+    let mut heartbeat =
+        tokio::time::interval(HEARTBEAT_INTERVAL);
+
+    loop {
+        // A slow socket falls behind on `updates` rather than
+        // holding up the others, and is told so when it catches up.
+        let replies = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str(&text) {
+                        Ok(message) => session.handle(message),
+                        Err(e) => vec![ServerMessage::Error {
+                            message: format!("Invalid message: {e}"),
+                        }],
+                    }
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                // Pings are answered by axum.
+                Some(Ok(_)) => continue,
+            },
+            update = updates.recv() => match update {
+                Ok(coins) => {
+                    session.filter(&coins).into_iter().collect()
+                }
+                Err(RecvError::Lagged(skipped)) => vec![
+                    ServerMessage::Lagged { skipped },
+                    session.resync(),
+                ],
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => vec![ServerMessage::Heartbeat {
+                timestamp: now_millis(),
+            }],
+        };
+
+        for reply in replies {
+            let text = serde_json::to_string(&reply)
+                .expect(
+                    "Server messages serialize to JSON",
+                );
+            if socket
+                .send(Message::Text(text))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_service::coin_watch_service::models::Delta;
+
+    use super::*;
+
+    fn coin(code: &str) -> Coin {
+        Coin::new(
+            Some(code.into()),
+            Some(1.0),
+            None,
+            None,
+            Delta::new(None, None, None, None, None, None),
+        )
+    }
+
+    fn subscribe(codes: &[&str]) -> ClientMessage {
+        ClientMessage::Subscribe {
+            codes: codes
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn subscribe_normalizes_codes() {
+        let mut session =
+            StreamSession::new(PriceHub::default());
+        assert_eq!(
+            session.handle(subscribe(&[" btc", "BTC", ""])),
+            vec![ServerMessage::Subscriptions {
+                codes: vec!["BTC".into()]
+            }]
+        );
+    }
+
+    #[test]
+    fn subscribe_sends_latest_known_quotes() {
+        let hub = PriceHub::default();
+        let mut first = StreamSession::new(hub.clone());
+        first.handle(subscribe(&["BTC"]));
+        hub.publish(vec![coin("BTC")]);
+
+        let mut second = StreamSession::new(hub);
+        assert_eq!(
+            second.handle(subscribe(&["BTC"]))[1],
+            ServerMessage::Update {
+                coins: vec![coin("BTC")]
+            }
+        );
+    }
+
+    #[test]
+    fn subscribe_is_limited() {
+        let mut session =
+            StreamSession::new(PriceHub::default());
+        let codes: Vec<String> = (0..=MAX_CODES_PER_SOCKET)
+            .map(|i| format!("C{i}"))
+            .collect();
+        assert!(matches!(
+            session
+                .handle(ClientMessage::Subscribe { codes })
+                [0],
+            ServerMessage::Error { .. }
+        ));
+    }
+
+    #[test]
+    fn filter_keeps_subscribed_coins() {
+        let mut session =
+            StreamSession::new(PriceHub::default());
+        session.handle(subscribe(&["BTC"]));
+        assert_eq!(
+            session.filter(&[coin("BTC"), coin("ETH")]),
+            Some(ServerMessage::Update {
+                coins: vec![coin("BTC")]
+            })
+        );
+        assert_eq!(session.filter(&[coin("ETH")]), None);
+    }
+
+    #[test]
+    fn unsubscribe_releases_codes() {
+        let hub = PriceHub::default();
+        let mut session = StreamSession::new(hub.clone());
+        session.handle(subscribe(&["BTC", "ETH"]));
+        session.handle(ClientMessage::Unsubscribe {
+            codes: vec!["eth".into(), "DOGE".into()],
+        });
+        assert_eq!(hub.codes(), vec!["BTC".to_string()]);
+    }
+
+    #[test]
+    fn dropping_session_releases_codes() {
+        let hub = PriceHub::default();
+        let mut session = StreamSession::new(hub.clone());
+        session.handle(subscribe(&["BTC"]));
+        drop(session);
+        assert!(hub.codes().is_empty());
+    }
+}
@@ -0,0 +1,18 @@
+use axum::Router;
+
+/// Serves `app` on a free local port for the rest of the test,
+/// returning its base URL such as `http://127.0.0.1:49152`.
+pub async fn serve(app: Router) -> String {
+    let listener =
+        tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+    let base_url = format!(
+        "http://{}",
+        listener.local_addr().unwrap()
+    );
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap()
+    });
+    base_url
+}
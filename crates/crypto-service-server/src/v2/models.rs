@@ -0,0 +1,138 @@
+use crypto_service::coin_watch_service::models::{
+    CoinHistoryRequest, ListOfCoinsRequest, Order, Sort,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const DEFAULT_LIMIT: u32 = 50;
+/// Most coins Live Coin Watch returns for `/coins/list`.
+pub const MAX_LIMIT: u32 = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct CoinsParams {
+    pub limit: Option<u32>,
+    pub offset: Option<u8>,
+    pub sort: Option<Sort>,
+    pub order: Option<Order>,
+}
+
+impl CoinsParams {
+    pub fn request(&self) -> ListOfCoinsRequest {
+        ListOfCoinsRequest::new(
+            self.limit
+                .unwrap_or(DEFAULT_LIMIT)
+                .clamp(1, MAX_LIMIT),
+        )
+        .sorted(
+            self.sort.unwrap_or(Sort::Rank),
+            self.order.unwrap_or(Order::Ascending),
+        )
+        .with_offset(self.offset.unwrap_or(0))
+    }
+}
+
+/// How far back `/v2/coins/{code}/history` goes from now.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum HistoryRange {
+    #[serde(rename = "1d")]
+    Day,
+    #[default]
+    #[serde(rename = "7d")]
+    Week,
+    #[serde(rename = "30d")]
+    Month,
+    #[serde(rename = "90d")]
+    Quarter,
+    #[serde(rename = "1y")]
+    Year,
+}
+
+impl HistoryRange {
+    pub fn millis(&self) -> i64 {
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        match self {
+            HistoryRange::Day => DAY,
+            HistoryRange::Week => 7 * DAY,
+            HistoryRange::Month => 30 * DAY,
+            HistoryRange::Quarter => 90 * DAY,
+            HistoryRange::Year => 365 * DAY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct HistoryParams {
+    pub range: Option<HistoryRange>,
+}
+
+impl HistoryParams {
+    /// History of `code` for the range ending at `now`.
+    pub fn request(
+        &self,
+        code: &str,
+        now: i64,
+    ) -> CoinHistoryRequest {
+        let range = self.range.unwrap_or_default();
+        CoinHistoryRequest::new(
+            code.to_uppercase(),
+            (now - range.millis()).max(0) as u64,
+            now as u64,
+            true,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, to_value};
+
+    use super::*;
+
+    #[test]
+    fn coins_params_default_to_rank() {
+        assert_eq!(
+            to_value(CoinsParams::default().request())
+                .unwrap(),
+            to_value(ListOfCoinsRequest::new(
+                DEFAULT_LIMIT
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn coins_params_clamp_limit() {
+        let params = CoinsParams {
+            limit: Some(1000),
+            offset: Some(10),
+            sort: Some(Sort::Price),
+            order: Some(Order::Descending),
+        };
+        let request = to_value(params.request()).unwrap();
+        assert_eq!(request["limit"], MAX_LIMIT);
+        assert_eq!(request["offset"], 10);
+        assert_eq!(request["sort"], "price");
+        assert_eq!(request["order"], "descending");
+    }
+
+    #[test]
+    fn history_range_from_query() {
+        let params: HistoryParams = serde_json::from_value(
+            json!({ "range": "30d" }),
+        )
+        .unwrap();
+        assert_eq!(params.range, Some(HistoryRange::Month));
+    }
+
+    #[test]
+    fn history_request_ends_now() {
+        let request = to_value(
+            HistoryParams::default()
+                .request("btc", 8 * 86_400_000),
+        )
+        .unwrap();
+        assert_eq!(request["code"], "BTC");
+        assert_eq!(request["start"], 86_400_000);
+        assert_eq!(request["end"], 8 * 86_400_000);
+    }
+}
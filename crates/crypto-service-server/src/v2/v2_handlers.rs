@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+
+use super::models::{CoinsParams, HistoryParams};
+use crate::{
+    alerts::alert_engine::now_millis,
+    coin_watch::coin_watch_handlers::{
+        fetch_coin_history, fetch_coin_meta,
+        fetch_list_of_coins,
+    },
+    export::export_format::ExportFormat,
+    icons::models::IconsParams,
+    providers::models::SourceParams,
+    state::AppState,
+};
+
+/// `GET` counterpart of `POST /v1/coins/list`.
+#[utoipa::path(
+    get,
+    path = "/v2/coins",
+    tag = "coins",
+    params(CoinsParams, SourceParams),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<Coin>,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
+pub async fn list_coins(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(params): Query<CoinsParams>,
+    Query(source): Query<SourceParams>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let coins =
+        fetch_list_of_coins(&state, source.source, params.request())
+            .await?;
+    Ok(coins.respond(format))
+}
+
+/// `GET` counterpart of `POST /v1/coins/single`.
+#[utoipa::path(
+    get,
+    path = "/v2/coins/{code}",
+    tag = "coins",
+    params(
+        ("code" = String, Path, description = "Coin code such as `BTC`"),
+        IconsParams,
+        SourceParams,
+    ),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = CoinMeta,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
+pub async fn get_coin(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Path(code): Path<String>,
+    Query(params): Query<IconsParams>,
+    Query(source): Query<SourceParams>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let mut coin_meta =
+        fetch_coin_meta(&state, source.source, &code).await?;
+    params
+        .icons
+        .unwrap_or_default()
+        .apply(&mut coin_meta.value, &code);
+    Ok(coin_meta.respond(format))
+}
+
+/// `GET` counterpart of `POST /v1/coins/single/history`.
+#[utoipa::path(
+    get,
+    path = "/v2/coins/{code}/history",
+    tag = "coins",
+    params(
+        ("code" = String, Path, description = "Coin code such as `BTC`"),
+        HistoryParams,
+        SourceParams,
+    ),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = CoinHistory,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had usable data", body = String),
+    )
+)]
+pub async fn get_coin_history(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Path(code): Path<String>,
+    Query(params): Query<HistoryParams>,
+    Query(source): Query<SourceParams>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let history = fetch_coin_history(
+        &state,
+        source.source,
+        params.request(&code, now_millis()),
+    )
+    .await?;
+    Ok(history.respond(format))
+}
+
+/// Latest price of a coin from the first source with a fresh one.
+#[utoipa::path(
+    get,
+    path = "/v2/coins/{code}/quote",
+    tag = "coins",
+    params(
+        ("code" = String, Path, description = "Coin code such as `BTC`"),
+        SourceParams,
+    ),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Quote,
+            headers(("x-data-source" = String, description = "Source that answered"))),
+        (status = 304, description = "Matches `If-None-Match`"),
+        (status = 429, description = "The upstream's credits are used up", body = String),
+        (status = 502, description = "No source had a fresh price", body = String),
+    )
+)]
+pub async fn get_coin_quote(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Path(code): Path<String>,
+    Query(source): Query<SourceParams>,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let quote =
+        state.providers.quote(source.source, &code).await?;
+    Ok(quote.respond(format))
+}
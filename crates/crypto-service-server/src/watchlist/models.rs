@@ -0,0 +1,42 @@
+use crypto_service::coin_watch_service::models::Order;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::store::collector::parse_watchlist;
+
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Serialize,
+    Deserialize,
+    IntoParams,
+)]
+pub struct WatchlistParams {
+    /// Comma separated coin codes such as `btc,eth`.
+    pub coins: Option<String>,
+    /// Comma separated stock tickers such as `nvda,tsla`. Only five
+    /// that aren't among the day's top movers are quoted.
+    pub stocks: Option<String>,
+    /// By the day's change, biggest gain first when left out.
+    pub order: Option<Order>,
+}
+
+impl WatchlistParams {
+    pub fn coins(&self) -> Vec<String> {
+        parse_watchlist(
+            self.coins.as_deref().unwrap_or_default(),
+        )
+    }
+
+    pub fn stocks(&self) -> Vec<String> {
+        parse_watchlist(
+            self.stocks.as_deref().unwrap_or_default(),
+        )
+    }
+
+    pub fn descending(&self) -> bool {
+        self.order.unwrap_or(Order::Descending)
+            == Order::Descending
+    }
+}
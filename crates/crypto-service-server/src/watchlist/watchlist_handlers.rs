@@ -0,0 +1,200 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderValue,
+    response::Response,
+};
+use crypto_service::{
+    alphavantage_service::models::{
+        GlobalQuoteRequest, GlobalQuoteResponse,
+        StockQuote, TopAndBottomTrades,
+    },
+    asset::{sort_by_change, AssetSummary},
+    client_trait::Asset,
+};
+use futures::future::join_all;
+
+use super::models::WatchlistParams;
+use crate::{
+    alphavantage_api::alpha_handler::fetch,
+    export::export_format::ExportFormat, state::AppState,
+};
+
+/// Response header naming the coins and stocks that couldn't be
+/// priced, comma separated.
+pub const LEFT_OUT_HEADER: &str = "x-left-out";
+
+/// Most stocks quoted one by one per request. Each quote is an
+/// Alpha Vantage call, and the free tier has 25 a day.
+pub const MAX_STOCK_QUOTES: usize = 5;
+
+/// Stocks and coins side by side, ordered by the day's change.
+/// Stocks come from the latest top gainers, losers and most traded
+/// snapshot, up to [`MAX_STOCK_QUOTES`] other tickers from a quote
+/// each. What couldn't be priced is named in `x-left-out`.
+#[utoipa::path(
+    get,
+    path = "/v1/watchlist",
+    tag = "coins",
+    params(WatchlistParams),
+    responses(
+        (status = 200, description = "JSON, CSV or JSON Lines depending on `Accept`", body = Vec<AssetSummary>,
+            headers(("x-left-out" = String, description = "Coins and stocks that couldn't be priced"))),
+        (status = 304, description = "Matches `If-None-Match`"),
+    )
+)]
+pub async fn get_watchlist(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    Query(params): Query<WatchlistParams>,
+) -> Response {
+    let codes = params.coins();
+    let (mut assets, unlisted) = stock_summaries(
+        state.trades_feed.latest().as_deref(),
+        &params.stocks(),
+    );
+    let (unlisted, unquoted) = unlisted
+        .split_at(unlisted.len().min(MAX_STOCK_QUOTES));
+    let (coins, quotes) = tokio::join!(
+        join_all(codes.iter().map(|code| {
+            state.providers.coin_meta(None, code)
+        })),
+        join_all(
+            unlisted
+                .iter()
+                .map(|ticker| stock_quote(&state, ticker))
+        ),
+    );
+
+    let mut left_out = unquoted.to_vec();
+    for (ticker, quote) in unlisted.iter().zip(quotes) {
+        match quote {
+            Some(quote) => assets.push(quote.summary()),
+            None => left_out.push(ticker.clone()),
+        }
+    }
+    for (code, coin) in codes.iter().zip(coins) {
+        match coin {
+            Ok(coin_meta) => {
+                assets.push(coin_meta.value.summary())
+            }
+            Err(_) => left_out.push(code.clone()),
+        }
+    }
+    sort_by_change(&mut assets, params.descending());
+
+    let mut response = format.respond(assets);
+    if !left_out.is_empty() {
+        if let Ok(value) =
+            HeaderValue::from_str(&left_out.join(","))
+        {
+            response
+                .headers_mut()
+                .insert(LEFT_OUT_HEADER, value);
+        }
+    }
+    response
+}
+
+/// Summaries of `tickers` found in `trades`, once each even when a
+/// stock is both a top gainer and among the most traded, and the
+/// tickers it doesn't have, each once.
+fn stock_summaries(
+    trades: Option<&TopAndBottomTrades>,
+    tickers: &[String],
+) -> (Vec<AssetSummary>, Vec<String>) {
+    let mut summaries = vec![];
+    let mut unlisted = vec![];
+    for ticker in tickers {
+        let stock = trades.and_then(|trades| {
+            trades
+                .top_gainers
+                .iter()
+                .chain(&trades.top_losers)
+                .chain(&trades.most_actively_traded)
+                .find(|stock| {
+                    stock
+                        .ticker
+                        .eq_ignore_ascii_case(ticker)
+                })
+        });
+        match stock {
+            Some(stock) => summaries.push(stock.summary()),
+            None if !unlisted.contains(ticker) => {
+                unlisted.push(ticker.clone())
+            }
+            None => {}
+        }
+    }
+    (summaries, unlisted)
+}
+
+/// Latest trading day of `ticker`, `None` when Alpha Vantage
+/// doesn't know it or couldn't be asked.
+async fn stock_quote(
+    state: &AppState,
+    ticker: &str,
+) -> Option<StockQuote> {
+    fetch::<_, GlobalQuoteResponse>(
+        &state.api_client,
+        &state.alpha_client,
+        GlobalQuoteRequest::new(ticker.to_string()),
+    )
+    .await
+    .ok()?
+    .into_quote()
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_service::alphavantage_service::models::{
+        MostActivelyTraded, RawTrade,
+    };
+
+    use super::*;
+
+    fn stock(
+        ticker: &str,
+        change: &str,
+    ) -> MostActivelyTraded {
+        MostActivelyTraded::from_raw(
+            ticker.into(),
+            RawTrade {
+                price: "10.0".into(),
+                change_amount: "1.0".into(),
+                change_percentage: change.into(),
+                volume: "100".into(),
+            },
+        )
+    }
+
+    #[test]
+    fn stocks_found_once_in_any_list() {
+        let trades = TopAndBottomTrades {
+            metadata: String::new(),
+            last_updated: String::new(),
+            top_gainers: vec![stock("NVDA", "9.5%")],
+            top_losers: vec![stock("TSLA", "-4.0%")],
+            most_actively_traded: vec![stock(
+                "NVDA", "9.5%",
+            )],
+        };
+        let (summaries, unlisted) = stock_summaries(
+            Some(&trades),
+            &[
+                "NVDA".into(),
+                "TSLA".into(),
+                "AAPL".into(),
+                "AAPL".into(),
+            ],
+        );
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].change.day, Some(9.5));
+        assert_eq!(summaries[1].symbol, "TSLA");
+        assert_eq!(unlisted, vec!["AAPL"]);
+
+        let (summaries, unlisted) =
+            stock_summaries(None, &["NVDA".into()]);
+        assert!(summaries.is_empty());
+        assert_eq!(unlisted, vec!["NVDA"]);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod models;
+pub mod signature;
+pub mod webhook_dispatcher;
+pub mod webhook_handlers;
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    PriceAlert,
+    /// An upstream provider started failing.
+    ProviderOutage,
+    /// An upstream provider is answering again after an outage.
+    ProviderRecovered,
+    /// Sent by the admin route to check an endpoint is reachable.
+    Test,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::PriceAlert => "price_alert",
+            EventKind::ProviderOutage => "provider_outage",
+            EventKind::ProviderRecovered => {
+                "provider_recovered"
+            }
+            EventKind::Test => "test",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub id: String,
+    pub kind: EventKind,
+    /// Unix milliseconds.
+    pub created_at: i64,
+    pub payload: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookEndpoint {
+    pub id: String,
+    pub url: String,
+    /// Key for the payload signatures, never sent back by the API.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Kinds of events to deliver, all of them when empty.
+    pub events: Vec<EventKind>,
+}
+
+impl WebhookEndpoint {
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.events.is_empty()
+            || self.events.contains(&kind)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: String,
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Gave up after the last retry, can be replayed manually.
+    DeadLettered,
+    /// A test delivery that wasn't accepted, these aren't retried.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Delivery {
+    pub id: String,
+    pub endpoint_id: String,
+    pub event: Event,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    /// Unix milliseconds.
+    pub updated_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(events: Vec<EventKind>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: "1".into(),
+            url: "http://localhost".into(),
+            secret: "secret".into(),
+            events,
+        }
+    }
+
+    #[test]
+    fn endpoint_without_events_wants_all() {
+        assert!(endpoint(vec![])
+            .wants(EventKind::ProviderOutage));
+    }
+
+    #[test]
+    fn endpoint_filters_events() {
+        let endpoint =
+            endpoint(vec![EventKind::PriceAlert]);
+        assert!(endpoint.wants(EventKind::PriceAlert));
+        assert!(!endpoint.wants(EventKind::ProviderOutage));
+    }
+
+    #[test]
+    fn event_kind_str_matches_serde() {
+        assert_eq!(
+            serde_json::to_value(
+                EventKind::ProviderRecovered
+            )
+            .unwrap(),
+            EventKind::ProviderRecovered.as_str()
+        );
+    }
+
+    #[test]
+    fn endpoint_secret_is_not_serialized() {
+        assert!(!serde_json::to_string(&endpoint(vec![]))
+            .unwrap()
+            .contains("secret"));
+    }
+}
@@ -0,0 +1,674 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+use super::{
+    models::{
+        CreateWebhookRequest, Delivery, DeliveryStatus,
+        Event, EventKind, WebhookEndpoint,
+    },
+    signature::{
+        sign, DELIVERY_HEADER, EVENT_HEADER,
+        SIGNATURE_HEADER, TIMESTAMP_HEADER,
+    },
+};
+use crate::alerts::{
+    alert_engine::now_millis, models::AlertFiring,
+    notifier::Notifier,
+};
+
+/// Number of deliveries kept for the admin routes, oldest are
+/// dropped first. Dead letters are kept until replayed.
+pub const MAX_DELIVERY_LOG: usize = 500;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before the attempt following attempt number
+    /// `attempt`, doubling from `base_delay` up to `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(
+                2u32.saturating_pow(
+                    attempt.saturating_sub(1),
+                ),
+            )
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WebhookBook {
+    next_id: u64,
+    endpoints: Vec<WebhookEndpoint>,
+    deliveries: VecDeque<Delivery>,
+    dead_letters: Vec<Delivery>,
+}
+
+impl WebhookBook {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+/// Sends events to the registered webhook endpoints as signed
+/// JSON `POST`s, see [`sign`] for the signature scheme.
+///
+/// Deliveries that aren't answered with a 2xx are retried with
+/// exponential backoff and moved to the dead letters once the
+/// [`RetryPolicy`] runs out. Endpoints are only kept in memory.
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    book: Arc<RwLock<WebhookBook>>,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new(RetryPolicy::default())
+    }
+}
+
+impl WebhookDispatcher {
+    pub fn new(retry: RetryPolicy) -> Self {
+        Self {
+            book: Arc::default(),
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to build webhook client"),
+            retry,
+        }
+    }
+
+    pub fn endpoints(&self) -> Vec<WebhookEndpoint> {
+        self.book
+            .read()
+            .expect("Webhook lock poisoned")
+            .endpoints
+            .clone()
+    }
+
+    pub fn register(
+        &self,
+        request: CreateWebhookRequest,
+    ) -> WebhookEndpoint {
+        let mut book = self
+            .book
+            .write()
+            .expect("Webhook lock poisoned");
+        let endpoint = WebhookEndpoint {
+            id: book.next_id().to_string(),
+            url: request.url,
+            secret: request.secret,
+            events: request.events,
+        };
+        book.endpoints.push(endpoint.clone());
+        endpoint
+    }
+
+    /// Returns whether an endpoint with `id` existed.
+    pub fn unregister(&self, id: &str) -> bool {
+        let mut book = self
+            .book
+            .write()
+            .expect("Webhook lock poisoned");
+        let count = book.endpoints.len();
+        book.endpoints.retain(|endpoint| endpoint.id != id);
+        book.endpoints.len() != count
+    }
+
+    /// Recent deliveries, newest first.
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        let book = self
+            .book
+            .read()
+            .expect("Webhook lock poisoned");
+        book.deliveries.iter().rev().cloned().collect()
+    }
+
+    pub fn dead_letters(&self) -> Vec<Delivery> {
+        self.book
+            .read()
+            .expect("Webhook lock poisoned")
+            .dead_letters
+            .clone()
+    }
+
+    /// Creates an event and delivers it in the background to
+    /// every endpoint subscribed to `kind`.
+    pub fn publish(
+        &self,
+        kind: EventKind,
+        payload: Value,
+    ) -> Event {
+        let event = self.new_event(kind, payload);
+        for endpoint in self
+            .endpoints()
+            .into_iter()
+            .filter(|endpoint| endpoint.wants(kind))
+        {
+            let dispatcher = self.clone();
+            let event = event.clone();
+            tokio::spawn(async move {
+                dispatcher.deliver(endpoint, event).await
+            });
+        }
+        event
+    }
+
+    /// Delivers `event` to `endpoint`, retrying until it's
+    /// accepted or dead-lettered.
+    pub async fn deliver(
+        &self,
+        endpoint: WebhookEndpoint,
+        event: Event,
+    ) -> Delivery {
+        let mut delivery =
+            self.new_delivery(&endpoint, event);
+        loop {
+            self.attempt(&endpoint, &mut delivery).await;
+            if delivery.status == DeliveryStatus::Delivered
+            {
+                break;
+            }
+            if delivery.attempts >= self.retry.max_attempts
+            {
+                delivery.status =
+                    DeliveryStatus::DeadLettered;
+                self.book
+                    .write()
+                    .expect("Webhook lock poisoned")
+                    .dead_letters
+                    .push(delivery.clone());
+                break;
+            }
+            self.record(&delivery);
+            tokio::time::sleep(
+                self.retry.delay(delivery.attempts),
+            )
+            .await;
+        }
+        self.record(&delivery);
+        delivery
+    }
+
+    /// Sends a [`EventKind::Test`] event to the endpoint with `id`
+    /// once, without retrying. `None` if there's no such endpoint.
+    pub async fn send_test(
+        &self,
+        id: &str,
+    ) -> Option<Delivery> {
+        let endpoint = self
+            .endpoints()
+            .into_iter()
+            .find(|endpoint| endpoint.id == id)?;
+        let event = self.new_event(
+            EventKind::Test,
+            json!({ "message": "Test event from crypto-service" }),
+        );
+        let mut delivery =
+            self.new_delivery(&endpoint, event);
+        self.attempt(&endpoint, &mut delivery).await;
+        if delivery.status != DeliveryStatus::Delivered {
+            delivery.status = DeliveryStatus::Failed;
+        }
+        self.record(&delivery);
+        Some(delivery)
+    }
+
+    /// Makes one more attempt at the dead letter with `id`, which
+    /// goes back to the dead letters if it fails again. `None` if
+    /// there's no such dead letter or its endpoint was removed.
+    pub async fn replay(
+        &self,
+        id: &str,
+    ) -> Option<Delivery> {
+        let (endpoint, mut delivery) = {
+            let mut book = self
+                .book
+                .write()
+                .expect("Webhook lock poisoned");
+            let index = book
+                .dead_letters
+                .iter()
+                .position(|delivery| delivery.id == id)?;
+            let endpoint = book
+                .endpoints
+                .iter()
+                .find(|endpoint| {
+                    endpoint.id
+                        == book.dead_letters[index]
+                            .endpoint_id
+                })?
+                .clone();
+            (endpoint, book.dead_letters.remove(index))
+        };
+
+        self.attempt(&endpoint, &mut delivery).await;
+        if delivery.status != DeliveryStatus::Delivered {
+            self.book
+                .write()
+                .expect("Webhook lock poisoned")
+                .dead_letters
+                .push(delivery.clone());
+        }
+        self.record(&delivery);
+        Some(delivery)
+    }
+
+    /// Sends `delivery` once and updates it with the outcome.
+    async fn attempt(
+        &self,
+        endpoint: &WebhookEndpoint,
+        delivery: &mut Delivery,
+    ) {
+        let body = serde_json::to_vec(&delivery.event)
+            .expect("Events serialize to JSON");
+        let timestamp = now_millis();
+        let response = self
+            .http
+            .post(&endpoint.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(
+                SIGNATURE_HEADER,
+                sign(&endpoint.secret, timestamp, &body),
+            )
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(
+                EVENT_HEADER,
+                delivery.event.kind.as_str(),
+            )
+            .header(DELIVERY_HEADER, &delivery.id)
+            .body(body)
+            .send()
+            .await;
+
+        delivery.attempts += 1;
+        delivery.updated_at = now_millis();
+        match response {
+            Ok(response) => {
+                let status = response.status();
+                delivery.last_status_code =
+                    Some(status.as_u16());
+                if status.is_success() {
+                    delivery.status =
+                        DeliveryStatus::Delivered;
+                    delivery.last_error = None;
+                } else {
+                    delivery.last_error = Some(format!(
+                        "Endpoint responded with {status}"
+                    ));
+                }
+            }
+            Err(e) => {
+                delivery.last_status_code = None;
+                delivery.last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn new_event(
+        &self,
+        kind: EventKind,
+        payload: Value,
+    ) -> Event {
+        let id = self
+            .book
+            .write()
+            .expect("Webhook lock poisoned")
+            .next_id();
+        Event {
+            id: format!("evt-{id}"),
+            kind,
+            created_at: now_millis(),
+            payload,
+        }
+    }
+
+    fn new_delivery(
+        &self,
+        endpoint: &WebhookEndpoint,
+        event: Event,
+    ) -> Delivery {
+        let id = self
+            .book
+            .write()
+            .expect("Webhook lock poisoned")
+            .next_id();
+        Delivery {
+            id: format!("dlv-{id}"),
+            endpoint_id: endpoint.id.clone(),
+            event,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_status_code: None,
+            last_error: None,
+            updated_at: now_millis(),
+        }
+    }
+
+    /// Adds or updates `delivery` in the delivery log.
+    fn record(&self, delivery: &Delivery) {
+        let mut book = self
+            .book
+            .write()
+            .expect("Webhook lock poisoned");
+        match book
+            .deliveries
+            .iter_mut()
+            .rev()
+            .find(|logged| logged.id == delivery.id)
+        {
+            Some(logged) => *logged = delivery.clone(),
+            None => {
+                if book.deliveries.len() == MAX_DELIVERY_LOG
+                {
+                    book.deliveries.pop_front();
+                }
+                book.deliveries.push_back(delivery.clone());
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookDispatcher {
+    async fn notify(&self, firing: &AlertFiring) {
+        self.publish(EventKind::PriceAlert, json!(firing));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    };
+
+    use axum::{
+        body::Bytes,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+
+    use super::*;
+    use crate::{
+        test_support, webhooks::signature::verify,
+    };
+
+    /// Local HTTP stand-in for a webhook receiver that fails the
+    /// first `failures` requests with a 500.
+    #[derive(Debug, Clone, Default)]
+    struct Receiver {
+        failures: Arc<AtomicU32>,
+        received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        receiver
+            .received
+            .lock()
+            .unwrap()
+            .push((headers, body));
+        let failing = receiver
+            .failures
+            .fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |failures| failures.checked_sub(1),
+            )
+            .is_ok();
+        match failing {
+            true => StatusCode::INTERNAL_SERVER_ERROR,
+            false => StatusCode::NO_CONTENT,
+        }
+    }
+
+    async fn spawn_receiver(
+        failures: u32,
+    ) -> (Receiver, String) {
+        let receiver = Receiver {
+            failures: Arc::new(AtomicU32::new(failures)),
+            ..Default::default()
+        };
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver.clone());
+        let url = format!(
+            "{}/hook",
+            test_support::serve(app).await
+        );
+        (receiver, url)
+    }
+
+    fn dispatcher() -> WebhookDispatcher {
+        WebhookDispatcher::new(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        })
+    }
+
+    fn register(
+        dispatcher: &WebhookDispatcher,
+        url: String,
+    ) -> WebhookEndpoint {
+        dispatcher.register(CreateWebhookRequest {
+            url,
+            secret: "secret".into(),
+            events: vec![],
+        })
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(
+            policy.delay(9),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn unregister() {
+        let dispatcher = dispatcher();
+        let endpoint = register(
+            &dispatcher,
+            "http://localhost".into(),
+        );
+        assert!(dispatcher.unregister(&endpoint.id));
+        assert!(!dispatcher.unregister(&endpoint.id));
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_event() {
+        let (receiver, url) = spawn_receiver(0).await;
+        let dispatcher = dispatcher();
+        let endpoint = register(&dispatcher, url);
+        let event = dispatcher
+            .new_event(EventKind::Test, json!({ "a": 1 }));
+
+        let delivery = dispatcher
+            .deliver(endpoint, event.clone())
+            .await;
+        assert_eq!(
+            delivery.status,
+            DeliveryStatus::Delivered
+        );
+        assert_eq!(delivery.attempts, 1);
+
+        let received = receiver.received.lock().unwrap();
+        let (headers, body) = &received[0];
+        let header = |name| {
+            headers.get(name).unwrap().to_str().unwrap()
+        };
+        assert!(verify(
+            "secret",
+            header(TIMESTAMP_HEADER).parse().unwrap(),
+            body,
+            header(SIGNATURE_HEADER),
+        ));
+        assert_eq!(header(EVENT_HEADER), "test");
+        assert_eq!(header(DELIVERY_HEADER), delivery.id);
+        assert_eq!(
+            serde_json::from_slice::<Event>(body).unwrap(),
+            event
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_until_accepted() {
+        let (_, url) = spawn_receiver(2).await;
+        let dispatcher = dispatcher();
+        let endpoint = register(&dispatcher, url);
+        let event = dispatcher
+            .new_event(EventKind::Test, json!({}));
+
+        let delivery =
+            dispatcher.deliver(endpoint, event).await;
+        assert_eq!(
+            delivery.status,
+            DeliveryStatus::Delivered
+        );
+        assert_eq!(delivery.attempts, 3);
+        assert!(dispatcher.dead_letters().is_empty());
+        assert_eq!(dispatcher.deliveries(), vec![delivery]);
+    }
+
+    #[tokio::test]
+    async fn dead_letters_and_replays() {
+        let (receiver, url) = spawn_receiver(4).await;
+        let dispatcher = dispatcher();
+        let endpoint = register(&dispatcher, url);
+        let event = dispatcher
+            .new_event(EventKind::Test, json!({}));
+
+        let delivery =
+            dispatcher.deliver(endpoint, event).await;
+        assert_eq!(
+            delivery.status,
+            DeliveryStatus::DeadLettered
+        );
+        assert_eq!(delivery.last_status_code, Some(500));
+        assert_eq!(
+            dispatcher.dead_letters(),
+            vec![delivery.clone()]
+        );
+
+        // Still failing, so it stays dead-lettered.
+        let replayed =
+            dispatcher.replay(&delivery.id).await.unwrap();
+        assert_eq!(
+            replayed.status,
+            DeliveryStatus::DeadLettered
+        );
+        assert_eq!(dispatcher.dead_letters().len(), 1);
+
+        let replayed =
+            dispatcher.replay(&delivery.id).await.unwrap();
+        assert_eq!(
+            replayed.status,
+            DeliveryStatus::Delivered
+        );
+        assert_eq!(replayed.attempts, 5);
+        assert!(dispatcher.dead_letters().is_empty());
+        assert_eq!(
+            receiver.received.lock().unwrap().len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_unknown_delivery() {
+        assert!(dispatcher()
+            .replay("dlv-1")
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_test_delivery_is_not_retried() {
+        let (receiver, url) = spawn_receiver(1).await;
+        let dispatcher = dispatcher();
+        let endpoint = register(&dispatcher, url);
+
+        let delivery = dispatcher
+            .send_test(&endpoint.id)
+            .await
+            .unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(
+            receiver.received.lock().unwrap().len(),
+            1
+        );
+        assert!(dispatcher.dead_letters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn publish_skips_unsubscribed_endpoints() {
+        let (receiver, url) = spawn_receiver(0).await;
+        let dispatcher = dispatcher();
+        dispatcher.register(CreateWebhookRequest {
+            url,
+            secret: "secret".into(),
+            events: vec![EventKind::PriceAlert],
+        });
+
+        dispatcher
+            .publish(EventKind::ProviderOutage, json!({}));
+        dispatcher
+            .publish(EventKind::PriceAlert, json!({}));
+        for _ in 0..100 {
+            if !dispatcher.deliveries().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10))
+                .await;
+        }
+
+        let received = receiver.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0].0.get(EVENT_HEADER).unwrap(),
+            "price_alert"
+        );
+    }
+}
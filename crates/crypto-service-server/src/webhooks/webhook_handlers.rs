@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use super::models::{
+    CreateWebhookRequest, Delivery, WebhookEndpoint,
+};
+use crate::state::AppState;
+
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<WebhookEndpoint>>) {
+    (StatusCode::OK, Json(state.webhooks.endpoints()))
+}
+
+pub async fn register_webhook(
+    State(state): State<AppState>,
+    Json(body): Json<CreateWebhookRequest>,
+) -> Result<
+    (StatusCode, Json<WebhookEndpoint>),
+    (StatusCode, Json<String>),
+> {
+    let is_http =
+        reqwest::Url::parse(&body.url).is_ok_and(|url| {
+            matches!(url.scheme(), "http" | "https")
+        });
+    if !is_http || body.secret.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("A webhook needs an http(s) URL and a secret".into()),
+        ));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(state.webhooks.register(body)),
+    ))
+}
+
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<String>)> {
+    match state.webhooks.unregister(&id) {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err((
+            StatusCode::NOT_FOUND,
+            Json(format!("No webhook with id '{id}'")),
+        )),
+    }
+}
+
+pub async fn test_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<
+    (StatusCode, Json<Delivery>),
+    (StatusCode, Json<String>),
+> {
+    state
+        .webhooks
+        .send_test(&id)
+        .await
+        .map(|delivery| (StatusCode::OK, Json(delivery)))
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(format!("No webhook with id '{id}'")),
+        ))
+}
+
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<Delivery>>) {
+    (StatusCode::OK, Json(state.webhooks.deliveries()))
+}
+
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<Delivery>>) {
+    (StatusCode::OK, Json(state.webhooks.dead_letters()))
+}
+
+pub async fn replay_dead_letter(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<
+    (StatusCode, Json<Delivery>),
+    (StatusCode, Json<String>),
+> {
+    state
+        .webhooks
+        .replay(&id)
+        .await
+        .map(|delivery| (StatusCode::OK, Json(delivery)))
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(format!(
+                "No dead letter with id '{id}' for a registered webhook"
+            )),
+        ))
+}
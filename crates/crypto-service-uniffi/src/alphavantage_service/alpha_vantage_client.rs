@@ -0,0 +1,108 @@
+use core::fmt::Debug;
+use std::{collections::HashMap, env};
+
+use serde::Serialize;
+
+use crate::client_trait::{Client, QueryItems};
+
+pub const BASE_URL: &str =
+    "https://www.alphavantage.co/query";
+
+/// Every Alpha Vantage call is a `GET` of [`BASE_URL`] with the
+/// function and key among the query parameters.
+#[derive(PartialEq, Debug, Clone)]
+pub struct AlphaVantageClient {
+    pub base_url: String,
+    key: String,
+}
+
+impl Default for AlphaVantageClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlphaVantageClient {
+    /// Reads `ALPHA_VANTAGE_KEY`. Without it the client is built
+    /// anyway, see [`AlphaVantageClient::has_key`].
+    pub fn new() -> Self {
+        Self::new_with_key(
+            env::var("ALPHA_VANTAGE_KEY")
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn new_with_key(key: String) -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            key,
+        }
+    }
+
+    /// Alpha Vantage answers every call without a key with an error.
+    pub fn has_key(&self) -> bool {
+        !self.key.is_empty()
+    }
+
+    /// `query` with this client's key added.
+    pub fn keyed<T>(&self, query: T) -> KeyedQuery<T> {
+        KeyedQuery {
+            key: self.key.clone(),
+            query,
+        }
+    }
+}
+
+impl Client for AlphaVantageClient {
+    fn get_base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn get_headers(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+}
+
+/// A query with the key Alpha Vantage expects as the `apikey`
+/// parameter rather than in a header.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KeyedQuery<T> {
+    #[serde(skip)]
+    key: String,
+    #[serde(flatten)]
+    query: T,
+}
+
+impl<T> QueryItems for KeyedQuery<T>
+where
+    T: QueryItems<Query = String>,
+{
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = self.query.get_all_queries();
+        queries.insert("apikey", self.key.clone());
+        queries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphavantage_service::models::GlobalQuoteRequest;
+
+    #[test]
+    fn key_goes_into_the_query() {
+        let client =
+            AlphaVantageClient::new_with_key("demo".into());
+        let query = client
+            .keyed(GlobalQuoteRequest::new("ibm".into()));
+        let queries = query.get_all_queries();
+        assert_eq!(queries["apikey"], "demo");
+        assert_eq!(queries["function"], "GLOBAL_QUOTE");
+        assert_eq!(queries["symbol"], "IBM");
+        assert!(client.get_headers().is_empty());
+    }
+}
@@ -1 +1,2 @@
+pub mod alpha_vantage_client;
 pub mod models;
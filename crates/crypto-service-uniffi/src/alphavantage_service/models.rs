@@ -1,6 +1,16 @@
+use std::collections::{BTreeMap, HashMap};
+
 use serde::{Serialize, Deserialize};
+use uniffi::{Enum, Record};
+
+use crate::{
+    asset::{AssetClass, ChangePercentages},
+    asset_registry::{self, AssetProvider},
+    client_trait::{Asset, QueryItems},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct TopAndBottomTrades {
     pub metadata: String,
     pub last_updated: String,
@@ -9,11 +19,1062 @@ pub struct TopAndBottomTrades {
     pub most_actively_traded: Vec<MostActivelyTraded>,
 }
 
+/// A stock in one of the `TOP_GAINERS_LOSERS` lists. Alpha Vantage
+/// sends every number as a string, these are parsed leniently and
+/// `None` where that fails.
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(from = "TradeFields")]
 pub struct MostActivelyTraded {
     pub ticker: String,
+    pub price: Option<f64>,
+    pub change_amount: Option<f64>,
+    /// In percent, `12.34` for `"12.34%"`.
+    pub change_percentage: Option<f64>,
+    pub volume: Option<i64>,
+    /// The numbers as Alpha Vantage sent them.
+    pub raw: RawTrade,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, uniffi::Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RawTrade {
     pub price: String,
     pub change_amount: String,
     pub change_percentage: String,
     pub volume: String,
-}
\ No newline at end of file
+}
+
+impl MostActivelyTraded {
+    pub fn from_raw(ticker: String, raw: RawTrade) -> Self {
+        Self {
+            ticker,
+            price: parse_lenient(&raw.price),
+            change_amount: parse_lenient(&raw.change_amount),
+            change_percentage: parse_lenient(
+                &raw.change_percentage,
+            ),
+            volume: parse_lenient(&raw.volume)
+                .map(|volume| volume as i64),
+            raw,
+        }
+    }
+}
+
+/// Parses a number the way Alpha Vantage writes them, such as
+/// `"12.34%"` or `"1,000"`. `None` when empty or not a number.
+pub fn parse_lenient(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .replace(',', "")
+        .parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite())
+}
+
+/// Either a payload from Alpha Vantage or a [`MostActivelyTraded`]
+/// serialized by us, whose `raw` is kept as is.
+#[derive(Deserialize)]
+struct TradeFields {
+    ticker: String,
+    #[serde(default)]
+    price: Option<Lenient>,
+    #[serde(default)]
+    change_amount: Option<Lenient>,
+    #[serde(default)]
+    change_percentage: Option<Lenient>,
+    #[serde(default)]
+    volume: Option<Lenient>,
+    raw: Option<RawTrade>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Lenient {
+    Number(f64),
+    String(String),
+}
+
+impl Lenient {
+    fn raw(value: Option<Lenient>) -> String {
+        match value {
+            Some(Lenient::Number(number)) => number.to_string(),
+            Some(Lenient::String(string)) => string,
+            None => String::new(),
+        }
+    }
+}
+
+impl From<TradeFields> for MostActivelyTraded {
+    fn from(fields: TradeFields) -> Self {
+        let raw = fields.raw.unwrap_or_else(|| RawTrade {
+            price: Lenient::raw(fields.price),
+            change_amount: Lenient::raw(fields.change_amount),
+            change_percentage: Lenient::raw(
+                fields.change_percentage,
+            ),
+            volume: Lenient::raw(fields.volume),
+        });
+        MostActivelyTraded::from_raw(fields.ticker, raw)
+    }
+}
+
+/// Only the day's change is known for a stock.
+impl Asset for MostActivelyTraded {
+    fn symbol(&self) -> String {
+        self.ticker.clone()
+    }
+
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    fn asset_class(&self) -> AssetClass {
+        AssetClass::Equity
+    }
+
+    fn quote(&self) -> Option<f64> {
+        self.price
+    }
+
+    fn change(&self) -> ChangePercentages {
+        ChangePercentages {
+            day: self.change_percentage,
+            ..Default::default()
+        }
+    }
+}
+
+/// Alpha Vantage answers failed calls with `200 OK` and a message
+/// in place of the data.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum AlphaVantageResponse<T> {
+    Data(T),
+    /// The call was invalid, most often an unknown symbol.
+    Error {
+        #[serde(rename = "Error Message")]
+        message: String,
+    },
+    /// The key's calls are used up for now.
+    Limited {
+        #[serde(rename = "Information", alias = "Note")]
+        message: String,
+    },
+}
+
+impl<T> AlphaVantageResponse<T> {
+    pub fn into_result(self) -> Result<T, String> {
+        match self {
+            AlphaVantageResponse::Data(data) => Ok(data),
+            AlphaVantageResponse::Error { message }
+            | AlphaVantageResponse::Limited { message } => {
+                Err(message)
+            }
+        }
+    }
+}
+
+/// Alpha Vantage numbers its keys, as in `"05. price"`. The value of
+/// the one called `name`, whatever its number.
+fn field<'a>(
+    fields: &'a HashMap<String, String>,
+    name: &str,
+) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(key, _)| {
+            key.split_once(". ")
+                .is_some_and(|(_, key)| key == name)
+        })
+        .map(|(_, value)| value.as_str())
+}
+
+fn number(
+    fields: &HashMap<String, String>,
+    name: &str,
+) -> Option<f64> {
+    field(fields, name).and_then(parse_lenient)
+}
+
+fn text(fields: &HashMap<String, String>, name: &str) -> String {
+    field(fields, name).unwrap_or_default().to_string()
+}
+
+/// Query of `TOP_GAINERS_LOSERS`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TopGainersLosersRequest {}
+
+impl QueryItems for TopGainersLosersRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "function",
+            "TOP_GAINERS_LOSERS".to_string(),
+        );
+        queries
+    }
+}
+
+/// Query of `GLOBAL_QUOTE` for a ticker such as `IBM`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct GlobalQuoteRequest {
+    pub symbol: String,
+}
+
+impl GlobalQuoteRequest {
+    pub fn new(symbol: String) -> Self {
+        Self { symbol }
+    }
+}
+
+impl QueryItems for GlobalQuoteRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert("function", "GLOBAL_QUOTE".to_string());
+        queries.insert("symbol", self.symbol.to_uppercase());
+        queries
+    }
+}
+
+/// How much of a time series to return.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OutputSize {
+    /// The latest 100 bars.
+    #[default]
+    Compact,
+    /// Up to 20 years of days, or a month of intraday bars.
+    Full,
+}
+
+impl OutputSize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputSize::Compact => "compact",
+            OutputSize::Full => "full",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum IntradayInterval {
+    #[serde(rename = "1min")]
+    OneMinute,
+    #[default]
+    #[serde(rename = "5min")]
+    FiveMinutes,
+    #[serde(rename = "15min")]
+    FifteenMinutes,
+    #[serde(rename = "30min")]
+    ThirtyMinutes,
+    #[serde(rename = "60min")]
+    SixtyMinutes,
+}
+
+impl IntradayInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntradayInterval::OneMinute => "1min",
+            IntradayInterval::FiveMinutes => "5min",
+            IntradayInterval::FifteenMinutes => "15min",
+            IntradayInterval::ThirtyMinutes => "30min",
+            IntradayInterval::SixtyMinutes => "60min",
+        }
+    }
+}
+
+/// Query of `TIME_SERIES_DAILY`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct DailySeriesRequest {
+    pub symbol: String,
+    pub outputsize: Option<OutputSize>,
+}
+
+impl QueryItems for DailySeriesRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "function",
+            "TIME_SERIES_DAILY".to_string(),
+        );
+        queries.insert("symbol", self.symbol.to_uppercase());
+        queries.insert(
+            "outputsize",
+            self.outputsize.unwrap_or_default().as_str().into(),
+        );
+        queries
+    }
+}
+
+/// Query of `TIME_SERIES_INTRADAY`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct IntradaySeriesRequest {
+    pub symbol: String,
+    pub interval: IntradayInterval,
+    pub outputsize: Option<OutputSize>,
+}
+
+impl QueryItems for IntradaySeriesRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "function",
+            "TIME_SERIES_INTRADAY".to_string(),
+        );
+        queries.insert("symbol", self.symbol.to_uppercase());
+        queries
+            .insert("interval", self.interval.as_str().into());
+        queries.insert(
+            "outputsize",
+            self.outputsize.unwrap_or_default().as_str().into(),
+        );
+        queries
+    }
+}
+
+/// Query of `SYMBOL_SEARCH`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct SymbolSearchRequest {
+    pub keywords: String,
+}
+
+impl QueryItems for SymbolSearchRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert("function", "SYMBOL_SEARCH".to_string());
+        queries.insert("keywords", self.keywords.clone());
+        queries
+    }
+}
+
+/// Latest trading day of a stock from `GLOBAL_QUOTE`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StockQuote {
+    pub symbol: String,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub price: Option<f64>,
+    pub volume: Option<i64>,
+    /// Such as `2024-03-28`.
+    pub latest_trading_day: String,
+    pub previous_close: Option<f64>,
+    pub change: Option<f64>,
+    /// In percent, `0.34` for `"0.3428%"`.
+    pub change_percent: Option<f64>,
+}
+
+/// Body of `GLOBAL_QUOTE`, whose quote is empty for unknown
+/// symbols.
+#[derive(Deserialize, Debug)]
+pub struct GlobalQuoteResponse {
+    #[serde(rename = "Global Quote")]
+    pub quote: HashMap<String, String>,
+}
+
+impl GlobalQuoteResponse {
+    pub fn into_quote(self) -> Option<StockQuote> {
+        let quote = &self.quote;
+        Some(StockQuote {
+            symbol: field(quote, "symbol")?.to_string(),
+            open: number(quote, "open"),
+            high: number(quote, "high"),
+            low: number(quote, "low"),
+            price: number(quote, "price"),
+            volume: number(quote, "volume")
+                .map(|volume| volume as i64),
+            latest_trading_day: text(
+                quote,
+                "latest trading day",
+            ),
+            previous_close: number(quote, "previous close"),
+            change: number(quote, "change"),
+            change_percent: number(quote, "change percent"),
+        })
+    }
+}
+
+impl Asset for StockQuote {
+    fn symbol(&self) -> String {
+        self.symbol.clone()
+    }
+
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    fn asset_class(&self) -> AssetClass {
+        AssetClass::Equity
+    }
+
+    fn quote(&self) -> Option<f64> {
+        self.price
+    }
+
+    fn change(&self) -> ChangePercentages {
+        ChangePercentages {
+            day: self.change_percent,
+            ..Default::default()
+        }
+    }
+}
+
+/// One bar of a stock's time series.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StockBar {
+    /// Such as `2024-03-28`, or `2024-03-28 19:55:00` for intraday
+    /// bars, in the series' time zone.
+    pub date: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
+}
+
+/// A stock's daily or intraday bars, oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StockSeries {
+    pub symbol: String,
+    /// Such as `5min`, `None` for daily bars.
+    pub interval: Option<String>,
+    pub last_refreshed: String,
+    pub time_zone: String,
+    pub bars: Vec<StockBar>,
+}
+
+/// Body of the time series functions, such as `TIME_SERIES_DAILY`
+/// or `FX_DAILY`, keyed by date under a name such as
+/// `Time Series (5min)`.
+#[derive(Deserialize, Debug)]
+pub struct TimeSeriesResponse {
+    #[serde(rename = "Meta Data")]
+    pub meta: HashMap<String, String>,
+    #[serde(flatten)]
+    pub series: HashMap<
+        String,
+        BTreeMap<String, HashMap<String, String>>,
+    >,
+}
+
+impl TimeSeriesResponse {
+    /// Bars with a value missing are left out.
+    pub fn into_series(self) -> StockSeries {
+        let (meta, bars) = self.into_parts();
+        let bars = bars
+            .into_iter()
+            .filter_map(|(date, bar)| {
+                Some(StockBar {
+                    open: number(&bar, "open")?,
+                    high: number(&bar, "high")?,
+                    low: number(&bar, "low")?,
+                    close: number(&bar, "close")?,
+                    volume: number(&bar, "volume")? as i64,
+                    date,
+                })
+            })
+            .collect();
+        StockSeries {
+            symbol: text(&meta, "Symbol"),
+            interval: field(&meta, "Interval")
+                .map(str::to_string),
+            last_refreshed: text(&meta, "Last Refreshed"),
+            time_zone: text(&meta, "Time Zone"),
+            bars,
+        }
+    }
+
+    /// Bars with a value missing are left out.
+    pub fn into_fx_series(self) -> FxSeries {
+        let (meta, bars) = self.into_parts();
+        let bars = bars
+            .into_iter()
+            .filter_map(|(date, bar)| {
+                Some(FxBar {
+                    open: number(&bar, "open")?,
+                    high: number(&bar, "high")?,
+                    low: number(&bar, "low")?,
+                    close: number(&bar, "close")?,
+                    date,
+                })
+            })
+            .collect();
+        FxSeries {
+            from_symbol: text(&meta, "From Symbol"),
+            to_symbol: text(&meta, "To Symbol"),
+            last_refreshed: text(&meta, "Last Refreshed"),
+            time_zone: text(&meta, "Time Zone"),
+            bars,
+        }
+    }
+
+    /// Reads both of Alpha Vantage's layouts, `"1a. open (EUR)"`
+    /// next to `"1b. open (USD)"`, and plain `"1. open"` in the
+    /// market's currency. Bars with a price missing are left out.
+    pub fn into_digital_currency_series(
+        self,
+    ) -> DigitalCurrencySeries {
+        let (meta, bars) = self.into_parts();
+        let market = text(&meta, "Market Code");
+        let bars = bars
+            .into_iter()
+            .filter_map(|(date, bar)| {
+                let priced = |name: &str, currency: &str| {
+                    number(&bar, &format!("{name} ({currency})"))
+                };
+                let in_market = |name: &str| {
+                    priced(name, &market)
+                        .or_else(|| number(&bar, name))
+                };
+                Some(DigitalCurrencyBar {
+                    open: in_market("open")?,
+                    high: in_market("high")?,
+                    low: in_market("low")?,
+                    close: in_market("close")?,
+                    volume: number(&bar, "volume"),
+                    close_usd: priced("close", "USD"),
+                    market_cap_usd: priced("market cap", "USD"),
+                    date,
+                })
+            })
+            .collect();
+        DigitalCurrencySeries {
+            symbol: text(&meta, "Digital Currency Code"),
+            name: text(&meta, "Digital Currency Name"),
+            market,
+            last_refreshed: text(&meta, "Last Refreshed"),
+            time_zone: text(&meta, "Time Zone"),
+            bars,
+        }
+    }
+
+    /// The metadata and the bars by date, oldest first.
+    fn into_parts(
+        self,
+    ) -> (
+        HashMap<String, String>,
+        BTreeMap<String, HashMap<String, String>>,
+    ) {
+        let bars = self
+            .series
+            .into_values()
+            .next()
+            .unwrap_or_default();
+        (self.meta, bars)
+    }
+}
+
+/// A ticker matching a `SYMBOL_SEARCH`, best match first.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SymbolMatch {
+    pub symbol: String,
+    pub name: String,
+    /// Such as `Equity` or `ETF`.
+    pub asset_type: String,
+    pub region: String,
+    pub market_open: String,
+    pub market_close: String,
+    pub timezone: String,
+    pub currency: String,
+    /// From 0 to 1.
+    pub match_score: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SymbolSearchResponse {
+    #[serde(rename = "bestMatches")]
+    pub best_matches: Vec<HashMap<String, String>>,
+}
+
+impl SymbolSearchResponse {
+    pub fn into_matches(self) -> Vec<SymbolMatch> {
+        self.best_matches
+            .iter()
+            .map(|found| SymbolMatch {
+                symbol: text(found, "symbol"),
+                name: text(found, "name"),
+                asset_type: text(found, "type"),
+                region: text(found, "region"),
+                market_open: text(found, "marketOpen"),
+                market_close: text(found, "marketClose"),
+                timezone: text(found, "timezone"),
+                currency: text(found, "currency"),
+                match_score: number(found, "matchScore"),
+            })
+            .collect()
+    }
+}
+
+/// Query of `CURRENCY_EXCHANGE_RATE`, between any two physical or
+/// digital currencies such as `BTC` and `EUR`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct CurrencyExchangeRateRequest {
+    pub from_currency: String,
+    pub to_currency: String,
+}
+
+impl QueryItems for CurrencyExchangeRateRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "function",
+            "CURRENCY_EXCHANGE_RATE".to_string(),
+        );
+        queries.insert(
+            "from_currency",
+            currency_code(&self.from_currency),
+        );
+        queries.insert(
+            "to_currency",
+            currency_code(&self.to_currency),
+        );
+        queries
+    }
+}
+
+/// Query of `FX_DAILY` for a pair of physical currencies such as
+/// `EUR` and `USD`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct FxDailyRequest {
+    pub from_symbol: String,
+    pub to_symbol: String,
+    pub outputsize: Option<OutputSize>,
+}
+
+impl QueryItems for FxDailyRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert("function", "FX_DAILY".to_string());
+        queries.insert(
+            "from_symbol",
+            self.from_symbol.to_uppercase(),
+        );
+        queries
+            .insert("to_symbol", self.to_symbol.to_uppercase());
+        queries.insert(
+            "outputsize",
+            self.outputsize.unwrap_or_default().as_str().into(),
+        );
+        queries
+    }
+}
+
+/// Query of `DIGITAL_CURRENCY_DAILY`, a coin such as `BTC` priced in
+/// a physical currency such as `EUR`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct DigitalCurrencyDailyRequest {
+    pub symbol: String,
+    pub market: String,
+}
+
+impl QueryItems for DigitalCurrencyDailyRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "function",
+            "DIGITAL_CURRENCY_DAILY".to_string(),
+        );
+        queries.insert("symbol", currency_code(&self.symbol));
+        queries.insert("market", self.market.to_uppercase());
+        queries
+    }
+}
+
+/// What Alpha Vantage calls a coin, from the asset registry.
+/// Physical currencies aren't in it and are only uppercased, as
+/// are coins merged from listings, which never name a ticker.
+fn currency_code(code: &str) -> String {
+    asset_registry::identifier(code, AssetProvider::AlphaVantage)
+        .unwrap_or_else(|| code.to_uppercase())
+}
+
+/// Rate between two currencies from `CURRENCY_EXCHANGE_RATE`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CurrencyExchangeRate {
+    pub from_code: String,
+    pub from_name: String,
+    pub to_code: String,
+    pub to_name: String,
+    /// Units of `to_code` per unit of `from_code`.
+    pub rate: f64,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub last_refreshed: String,
+    pub time_zone: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CurrencyExchangeRateResponse {
+    #[serde(rename = "Realtime Currency Exchange Rate")]
+    pub rate: HashMap<String, String>,
+}
+
+impl CurrencyExchangeRateResponse {
+    /// `None` without a rate.
+    pub fn into_rate(self) -> Option<CurrencyExchangeRate> {
+        let rate = &self.rate;
+        Some(CurrencyExchangeRate {
+            from_code: text(rate, "From_Currency Code"),
+            from_name: text(rate, "From_Currency Name"),
+            to_code: text(rate, "To_Currency Code"),
+            to_name: text(rate, "To_Currency Name"),
+            rate: number(rate, "Exchange Rate")?,
+            bid: number(rate, "Bid Price"),
+            ask: number(rate, "Ask Price"),
+            last_refreshed: text(rate, "Last Refreshed"),
+            time_zone: text(rate, "Time Zone"),
+        })
+    }
+}
+
+/// One day of a currency pair.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FxBar {
+    pub date: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// Daily bars of a currency pair, oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FxSeries {
+    pub from_symbol: String,
+    pub to_symbol: String,
+    pub last_refreshed: String,
+    pub time_zone: String,
+    pub bars: Vec<FxBar>,
+}
+
+/// One day of a coin, priced in the series' market.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DigitalCurrencyBar {
+    pub date: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// In units of the coin.
+    pub volume: Option<f64>,
+    /// Only sent in Alpha Vantage's older layout.
+    pub close_usd: Option<f64>,
+    /// Only sent in Alpha Vantage's older layout.
+    pub market_cap_usd: Option<f64>,
+}
+
+/// Daily bars of a coin, oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DigitalCurrencySeries {
+    pub symbol: String,
+    pub name: String,
+    /// The currency the bars are priced in, such as `EUR`.
+    pub market: String,
+    pub last_refreshed: String,
+    pub time_zone: String,
+    pub bars: Vec<DigitalCurrencyBar>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOP_GAINERS_LOSERS: &str = include_str!(
+        "../../tests/fixtures/alphavantage/top_gainers_losers.json"
+    );
+    const GLOBAL_QUOTE: &str = include_str!(
+        "../../tests/fixtures/alphavantage/global_quote.json"
+    );
+    const TIME_SERIES_DAILY: &str = include_str!(
+        "../../tests/fixtures/alphavantage/time_series_daily.json"
+    );
+    const TIME_SERIES_INTRADAY: &str = include_str!(
+        "../../tests/fixtures/alphavantage/time_series_intraday.json"
+    );
+    const SYMBOL_SEARCH: &str = include_str!(
+        "../../tests/fixtures/alphavantage/symbol_search.json"
+    );
+    const CURRENCY_EXCHANGE_RATE: &str = include_str!(
+        "../../tests/fixtures/alphavantage/currency_exchange_rate.json"
+    );
+    const FX_DAILY: &str = include_str!(
+        "../../tests/fixtures/alphavantage/fx_daily.json"
+    );
+    const DIGITAL_CURRENCY_DAILY: &str = include_str!(
+        "../../tests/fixtures/alphavantage/digital_currency_daily.json"
+    );
+
+    #[test]
+    fn lenient_numbers() {
+        assert_eq!(parse_lenient("12.34%"), Some(12.34));
+        assert_eq!(parse_lenient("-85.7143%"), Some(-85.7143));
+        assert_eq!(parse_lenient(" 1,024 "), Some(1024.0));
+        assert_eq!(parse_lenient(""), None);
+        assert_eq!(parse_lenient("None"), None);
+        assert_eq!(parse_lenient("NaN"), None);
+    }
+
+    #[test]
+    fn captured_payload_is_typed() {
+        let trades: TopAndBottomTrades =
+            serde_json::from_str(TOP_GAINERS_LOSERS).unwrap();
+        let gainer = &trades.top_gainers[0];
+        assert_eq!(gainer.ticker, "SMFL");
+        assert_eq!(gainer.price, Some(3.51));
+        assert_eq!(gainer.change_amount, Some(2.64));
+        assert_eq!(gainer.change_percentage, Some(303.4483));
+        assert_eq!(gainer.volume, Some(28427683));
+        assert_eq!(gainer.raw.change_percentage, "303.4483%");
+
+        let loser = &trades.top_losers[0];
+        assert_eq!(loser.change_percentage, Some(-85.7143));
+        assert_eq!(loser.volume, None);
+        assert_eq!(loser.raw.volume, "");
+        assert_eq!(trades.most_actively_traded.len(), 2);
+    }
+
+    #[test]
+    fn round_trips_with_raw_values() {
+        let trades: TopAndBottomTrades =
+            serde_json::from_str(TOP_GAINERS_LOSERS).unwrap();
+        let json = serde_json::to_value(&trades).unwrap();
+        assert_eq!(json["top_gainers"][0]["price"], 3.51);
+
+        let again: TopAndBottomTrades =
+            serde_json::from_value(json).unwrap();
+        assert_eq!(again, trades);
+    }
+
+    #[test]
+    fn numbers_instead_of_strings() {
+        let trade: MostActivelyTraded =
+            serde_json::from_value(serde_json::json!({
+                "ticker": "NVDA",
+                "price": 903.56,
+                "change_amount": 16.36,
+                "change_percentage": 1.8439,
+                "volume": null
+            }))
+            .unwrap();
+        assert_eq!(trade.price, Some(903.56));
+        assert_eq!(trade.raw.price, "903.56");
+        assert_eq!(trade.volume, None);
+    }
+
+    #[test]
+    fn global_quote() {
+        let quote = serde_json::from_str::<GlobalQuoteResponse>(GLOBAL_QUOTE)
+            .unwrap()
+            .into_quote()
+            .unwrap();
+        assert_eq!(quote.symbol, "IBM");
+        assert_eq!(quote.price, Some(190.96));
+        assert_eq!(quote.volume, Some(3742212));
+        assert_eq!(quote.latest_trading_day, "2024-03-28");
+        assert_eq!(quote.change_percent, Some(0.3428));
+        assert_eq!(quote.summary().change.day, Some(0.3428));
+    }
+
+    #[test]
+    fn unknown_symbol_has_no_quote() {
+        let response: GlobalQuoteResponse =
+            serde_json::from_str(r#"{"Global Quote": {}}"#).unwrap();
+        assert_eq!(response.into_quote(), None);
+    }
+
+    #[test]
+    fn daily_series_is_sorted() {
+        let series = serde_json::from_str::<TimeSeriesResponse>(
+            TIME_SERIES_DAILY,
+        )
+        .unwrap()
+        .into_series();
+        assert_eq!(series.symbol, "IBM");
+        assert_eq!(series.interval, None);
+        assert_eq!(series.time_zone, "US/Eastern");
+        let dates: Vec<&str> =
+            series.bars.iter().map(|bar| bar.date.as_str()).collect();
+        assert_eq!(dates, ["2024-03-26", "2024-03-27", "2024-03-28"]);
+        assert_eq!(series.bars[2].close, 190.96);
+        assert_eq!(series.bars[2].volume, 3742212);
+    }
+
+    #[test]
+    fn intraday_series_keeps_its_interval() {
+        let series = serde_json::from_str::<TimeSeriesResponse>(
+            TIME_SERIES_INTRADAY,
+        )
+        .unwrap()
+        .into_series();
+        assert_eq!(series.interval, Some("5min".into()));
+        assert_eq!(series.bars.len(), 2);
+        assert_eq!(series.bars[0].date, "2024-03-28 19:50:00");
+    }
+
+    #[test]
+    fn symbol_search() {
+        let matches = serde_json::from_str::<SymbolSearchResponse>(
+            SYMBOL_SEARCH,
+        )
+        .unwrap()
+        .into_matches();
+        assert_eq!(matches[0].symbol, "TSCO.LON");
+        assert_eq!(matches[0].asset_type, "Equity");
+        assert_eq!(matches[0].currency, "GBX");
+        assert_eq!(matches[0].match_score, Some(0.7273));
+    }
+
+    #[test]
+    fn errors_and_limits_instead_of_data() {
+        let error: AlphaVantageResponse<TimeSeriesResponse> =
+            serde_json::from_str(
+                r#"{"Error Message": "Invalid API call."}"#,
+            )
+            .unwrap();
+        assert!(matches!(
+            error,
+            AlphaVantageResponse::Error { .. }
+        ));
+
+        let limited: AlphaVantageResponse<GlobalQuoteResponse> =
+            serde_json::from_str(
+                r#"{"Information": "Our standard API rate limit is 25 requests per day."}"#,
+            )
+            .unwrap();
+        assert!(limited.into_result().unwrap_err().contains("25"));
+    }
+
+    #[test]
+    fn currency_exchange_rate() {
+        let rate = serde_json::from_str::<CurrencyExchangeRateResponse>(
+            CURRENCY_EXCHANGE_RATE,
+        )
+        .unwrap()
+        .into_rate()
+        .unwrap();
+        assert_eq!(rate.from_code, "BTC");
+        assert_eq!(rate.to_name, "Euro");
+        assert_eq!(rate.rate, 64356.12);
+        assert_eq!(rate.bid, Some(64356.11));
+        assert_eq!(rate.time_zone, "UTC");
+    }
+
+    #[test]
+    fn fx_daily_series() {
+        let series =
+            serde_json::from_str::<TimeSeriesResponse>(FX_DAILY)
+                .unwrap()
+                .into_fx_series();
+        assert_eq!(series.from_symbol, "EUR");
+        assert_eq!(series.to_symbol, "USD");
+        assert_eq!(series.bars.len(), 2);
+        assert_eq!(series.bars[0].date, "2024-03-27");
+        assert_eq!(series.bars[1].close, 1.0789);
+    }
+
+    #[test]
+    fn digital_currency_keys_are_normalized() {
+        let series = serde_json::from_str::<TimeSeriesResponse>(
+            DIGITAL_CURRENCY_DAILY,
+        )
+        .unwrap()
+        .into_digital_currency_series();
+        assert_eq!(series.symbol, "BTC");
+        assert_eq!(series.market, "EUR");
+        let bar = &series.bars[1];
+        assert_eq!(bar.date, "2024-03-28");
+        assert_eq!(bar.open, 64211.5);
+        assert_eq!(bar.close, 65682.3);
+        assert_eq!(bar.close_usd, Some(70744.96));
+        assert_eq!(bar.volume, Some(22461.7));
+        assert_eq!(bar.market_cap_usd, Some(22461.7));
+    }
+
+    #[test]
+    fn digital_currency_in_the_newer_layout() {
+        let series = serde_json::from_str::<TimeSeriesResponse>(
+            r#"{
+                "Meta Data": {
+                    "2. Digital Currency Code": "ETH",
+                    "4. Market Code": "USD"
+                },
+                "Time Series (Digital Currency Daily)": {
+                    "2024-03-28": {
+                        "1. open": "3500.10",
+                        "2. high": "3610.00",
+                        "3. low": "3460.25",
+                        "4. close": "3560.95",
+                        "5. volume": "180432.5"
+                    }
+                }
+            }"#,
+        )
+        .unwrap()
+        .into_digital_currency_series();
+        assert_eq!(series.bars[0].close, 3560.95);
+        assert_eq!(series.bars[0].close_usd, None);
+    }
+
+    #[test]
+    fn coins_are_named_through_the_registry() {
+        let request = DigitalCurrencyDailyRequest {
+            symbol: "btc".into(),
+            market: "eur".into(),
+        };
+        let queries = request.get_all_queries();
+        assert_eq!(queries["symbol"], "BTC");
+        assert_eq!(queries["market"], "EUR");
+    }
+}
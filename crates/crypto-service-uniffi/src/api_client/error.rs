@@ -50,6 +50,21 @@ pub enum RustSideError {
     #[error("Failed to propagate FFI operation result back to dispatcher")]
     FailedToPropagateResultFromFFIOperationBackToDispatcher,
 
+    #[error("Unable to build a request URL from the base URL, path and query")]
+    InvalidRequestUrl,
+
+    #[error("No rate for coin '{code}'")]
+    NoRateForCoin { code: String },
+
+    #[error("Data source '{data_source}' doesn't offer this")]
+    UnsupportedByDataSource { data_source: String },
+
+    #[error("Alpha Vantage answered: {message}")]
+    AlphaVantage { message: String },
+
+    #[error("No stock '{symbol}'")]
+    UnknownSymbol { symbol: String },
+
     #[error("HTTP Body of response from Swift was nil")]
     ResponseBodyWasNil,
 
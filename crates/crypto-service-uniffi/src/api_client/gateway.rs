@@ -1,11 +1,33 @@
 use crate::{
-    client_trait::Client, coin_watch_service::{coin_watch_client::CoinWatchClient, models::{
+    alphavantage_service::{alpha_vantage_client::AlphaVantageClient, models::{
+        AlphaVantageResponse, CurrencyExchangeRate, CurrencyExchangeRateRequest,
+        CurrencyExchangeRateResponse, DailySeriesRequest,
+        DigitalCurrencyDailyRequest, DigitalCurrencySeries, FxDailyRequest,
+        FxSeries, GlobalQuoteRequest,
+        GlobalQuoteResponse, IntradayInterval, IntradaySeriesRequest,
+        OutputSize, StockQuote, StockSeries, SymbolMatch,
+        SymbolSearchRequest, SymbolSearchResponse, TimeSeriesResponse,
+        TopAndBottomTrades, TopGainersLosersRequest,
+    }},
+    asset_registry::{self, AssetIdentity},
+    binance_service::{binance_client::BinanceClient, models::{
+        candles_into_history, usdt_pair, Candle, DepthRequest,
+        ExchangeInfo, ExchangeInfoRequest, Kline, KlinesRequest,
+        OrderBook, Ticker, TickerRequest,
+    }},
+    client_trait::{Client, QueryItems}, coin_watch_service::{coin_watch_client::CoinWatchClient, models::{
         CoinHistory, CoinHistoryRequest, CoinMeta,
         CoinMetaRequest, ListOfCoinsRequest,
-    }}, network_antenna::network_antenna::{FFINetworkingRequest, FFINetworkingResponse, NetworkAntenna}
+    }}, coingecko_service::{coingecko_client::{CoinGeckoClient, CoinGeckoPlan}, models::{
+        coin_id, simple_price_quote, CoinDetail, MAX_PER_PAGE, CoinDetailQuery, Market,
+        MarketChart, MarketChartQuery, MarketsQuery, SimplePrice,
+        SimplePriceQuery,
+    }}, consensus::{ConsensusQuote, DEFAULT_MAX_DEVIATION},
+    data_source::{DataSource, Quote}, network_antenna::network_antenna::{FFINetworkingRequest, FFINetworkingResponse, NetworkAntenna}
 };
 use serde::{Deserialize, Serialize};
 use serde_json::to_vec;
+use std::collections::HashMap;
 use std::convert::identity;
 use std::sync::Arc;
 use uniffi::{export, Object, Record};
@@ -15,6 +37,8 @@ use super::error::{FFIBridgeError, RustSideError};
 #[derive(Object)]
 pub struct Gateway {
     pub network_antenna: Arc<dyn NetworkAntenna>,
+    /// Used by the methods that don't take a [`DataSource`].
+    pub source: DataSource,
 }
 
 #[derive(Record)]
@@ -22,6 +46,9 @@ pub struct ClientKeys {
     pub binance: String,
     pub coin_watch: String,
     pub alpha: String,
+    /// Empty for the keyless public CoinGecko API.
+    pub coingecko: String,
+    pub coingecko_plan: CoinGeckoPlan,
 }
 
 #[export]
@@ -33,63 +60,565 @@ impl Gateway {
     pub fn new(
         network_antenna: Arc<dyn NetworkAntenna>,
     ) -> Self {
-        Self { network_antenna }
+        Self::with_source(network_antenna, DataSource::default())
+    }
+
+    /// Like [`Gateway::new`], but fetching coins from `source` unless
+    /// a method is told otherwise.
+    #[uniffi::constructor]
+    pub fn with_source(
+        network_antenna: Arc<dyn NetworkAntenna>,
+        source: DataSource,
+    ) -> Self {
+        Self {
+            network_antenna,
+            source,
+        }
     }
 
     pub async fn get_list_of_coins(
         &self,
         limit: u32,
     ) -> Result<Vec<CoinMeta>, FFIBridgeError> {
-        let external_client = CoinWatchClient::new_with_key(
-            self.network_antenna.get_api_keys().coin_watch,
-        );
-        let request = ListOfCoinsRequest::new(limit);
-
-        self.post::<_, Vec<CoinMeta>, Vec<CoinMeta>, _, _, _>(
-            "/coins/list",
-            request,
-            res_id,
-            external_client,
-        )
-        .await
+        self.get_list_of_coins_from(limit, self.source).await
     }
 
     pub async fn get_coin_meta_info(
         &self,
         request: CoinMetaRequest,
     ) -> Result<CoinMeta, FFIBridgeError> {
-        let external_client = CoinWatchClient::new_with_key(
-            self.network_antenna.get_api_keys().coin_watch,
+        self.get_coin_meta_info_from(request, self.source).await
+    }
+
+    pub async fn get_coin_history_info(
+        &self,
+        request: CoinHistoryRequest,
+    ) -> Result<CoinHistory, FFIBridgeError> {
+        self.get_coin_history_info_from(request, self.source)
+            .await
+    }
+
+    pub async fn get_list_of_coins_from(
+        &self,
+        limit: u32,
+        source: DataSource,
+    ) -> Result<Vec<CoinMeta>, FFIBridgeError> {
+        let request = ListOfCoinsRequest::new(limit);
+        match source {
+            DataSource::LiveCoinWatch => {
+                self.post::<_, Vec<CoinMeta>, Vec<CoinMeta>, _, _, _>(
+                    "/coins/list",
+                    request,
+                    res_id,
+                    self.coin_watch_client(),
+                )
+                .await
+            }
+            DataSource::CoinGecko => {
+                self.get::<_, Vec<Market>, Vec<CoinMeta>, _, _, _>(
+                    "/coins/markets",
+                    MarketsQuery::from(&request),
+                    |markets| {
+                        res_id(
+                            markets
+                                .into_iter()
+                                .map(CoinMeta::from)
+                                .collect(),
+                        )
+                    },
+                    self.coingecko_client(),
+                )
+                .await
+            }
+            DataSource::Binance | DataSource::CoinApi => {
+                Err(unsupported(source))
+            }
+        }
+    }
+
+    pub async fn get_coin_meta_info_from(
+        &self,
+        request: CoinMetaRequest,
+        source: DataSource,
+    ) -> Result<CoinMeta, FFIBridgeError> {
+        match source {
+            DataSource::LiveCoinWatch => {
+                self.post::<_, CoinMeta, CoinMeta, _, _, _>(
+                    "/coins/single",
+                    request,
+                    res_id,
+                    self.coin_watch_client(),
+                )
+                .await
+            }
+            DataSource::CoinGecko => {
+                let id = coin_id(&request.code).ok_or_else(|| unsupported(source))?;
+                self.get::<_, CoinDetail, CoinMeta, _, _, _>(
+                    &format!("/coins/{id}"),
+                    CoinDetailQuery::default(),
+                    |detail| res_id(CoinMeta::from(detail)),
+                    self.coingecko_client(),
+                )
+                .await
+            }
+            DataSource::Binance => {
+                let pair = usdt_pair(&request.code).ok_or_else(|| unsupported(source))?;
+                self.get::<_, Ticker, CoinMeta, _, _, _>(
+                    "/ticker/24hr",
+                    TickerRequest::new(pair),
+                    |ticker| res_id(ticker.coin_meta(&request.code)),
+                    self.binance_client(),
+                )
+                .await
+            }
+            DataSource::CoinApi => Err(unsupported(source)),
+        }
+    }
+
+    pub async fn get_coin_history_info_from(
+        &self,
+        request: CoinHistoryRequest,
+        source: DataSource,
+    ) -> Result<CoinHistory, FFIBridgeError> {
+        match source {
+            DataSource::LiveCoinWatch => {
+                self.post::<_, CoinHistory, CoinHistory, _, _, _>(
+                    "/coins/single/history",
+                    request,
+                    res_id,
+                    self.coin_watch_client(),
+                )
+                .await
+            }
+            DataSource::CoinGecko => {
+                let id = coin_id(request.code()).ok_or_else(|| unsupported(source))?;
+                let detail = if request.meta() {
+                    Some(
+                        self.get::<_, CoinDetail, CoinDetail, _, _, _>(
+                            &format!("/coins/{id}"),
+                            CoinDetailQuery::default(),
+                            res_id,
+                            self.coingecko_client(),
+                        )
+                        .await?,
+                    )
+                } else {
+                    None
+                };
+                let chart = self
+                    .get::<_, MarketChart, MarketChart, _, _, _>(
+                        &format!("/coins/{id}/market_chart/range"),
+                        MarketChartQuery::from(&request),
+                        res_id,
+                        self.coingecko_client(),
+                    )
+                    .await?;
+                Ok(chart.into_coin_history(request.code(), detail))
+            }
+            DataSource::Binance => {
+                let klines = KlinesRequest::for_history(&request).ok_or_else(|| unsupported(source))?;
+                self.get::<_, Vec<Kline>, CoinHistory, _, _, _>(
+                    "/klines",
+                    klines,
+                    |klines| {
+                        res_id(candles_into_history(
+                            request.code(),
+                            klines.into_iter().map(Candle::from).collect(),
+                        ))
+                    },
+                    self.binance_client(),
+                )
+                .await
+            }
+            DataSource::CoinApi => Err(unsupported(source)),
+        }
+    }
+
+    /// Latest USD price of `code` from `source`. Live Coin Watch
+    /// doesn't timestamp its rates, so they're stamped with now.
+    pub async fn get_quote_from(
+        &self,
+        code: String,
+        source: DataSource,
+    ) -> Result<Quote, FFIBridgeError> {
+        let no_rate = || RustSideError::NoRateForCoin {
+            code: code.to_uppercase(),
+        };
+        match source {
+            DataSource::LiveCoinWatch => {
+                self.post::<_, CoinMeta, Quote, _, _, _>(
+                    "/coins/single",
+                    CoinMetaRequest::new(code.to_uppercase()),
+                    |meta| {
+                        meta.rate
+                            .map(|rate| Quote {
+                                code: code.to_uppercase(),
+                                rate,
+                                time: chrono::Utc::now().timestamp_millis(),
+                                source,
+                            })
+                            .ok_or_else(no_rate)
+                    },
+                    self.coin_watch_client(),
+                )
+                .await
+            }
+            DataSource::CoinGecko => {
+                let id = coin_id(&code).ok_or_else(|| unsupported(source))?;
+                self.get::<_, HashMap<String, SimplePrice>, Quote, _, _, _>(
+                    "/simple/price",
+                    SimplePriceQuery::new(id),
+                    |prices| {
+                        simple_price_quote(&code, &prices)
+                            .ok_or_else(no_rate)
+                    },
+                    self.coingecko_client(),
+                )
+                .await
+            }
+            DataSource::Binance => {
+                let pair = usdt_pair(&code).ok_or_else(|| unsupported(source))?;
+                self.get::<_, Ticker, Quote, _, _, _>(
+                    "/ticker/24hr",
+                    TickerRequest::new(pair),
+                    |ticker| res_id(ticker.quote(&code)),
+                    self.binance_client(),
+                )
+                .await
+            }
+            DataSource::CoinApi => Err(unsupported(source)),
+        }
+    }
+
+    /// Median of the quotes of Live Coin Watch, CoinGecko and
+    /// Binance, fetched concurrently. Sources that fail are left
+    /// out, the first error is returned when all of them do.
+    pub async fn get_consensus_quote(
+        &self,
+        code: String,
+        max_deviation: Option<f64>,
+    ) -> Result<ConsensusQuote, FFIBridgeError> {
+        let (live_coin_watch, coingecko, binance) = tokio::join!(
+            self.get_quote_from(code.clone(), DataSource::LiveCoinWatch),
+            self.get_quote_from(code.clone(), DataSource::CoinGecko),
+            self.get_quote_from(code.clone(), DataSource::Binance),
         );
+        let (quotes, errors): (Vec<_>, Vec<_>) =
+            [live_coin_watch, coingecko, binance]
+                .into_iter()
+                .partition(Result::is_ok);
+        ConsensusQuote::from_quotes(
+            &code,
+            quotes.into_iter().filter_map(Result::ok).collect(),
+            max_deviation.unwrap_or(DEFAULT_MAX_DEVIATION),
+        )
+        .ok_or_else(|| {
+            errors
+                .into_iter()
+                .find_map(Result::err)
+                .unwrap_or(RustSideError::NoRateForCoin { code }.into())
+        })
+    }
 
-        self.post::<_, CoinMeta, CoinMeta, _, _, _>(
-            "/coins/single",
+    /// Binance 24 hour statistics of a pair such as `BTCUSDT`.
+    pub async fn get_binance_ticker(
+        &self,
+        symbol: String,
+    ) -> Result<Ticker, FFIBridgeError> {
+        self.get::<_, Ticker, Ticker, _, _, _>(
+            "/ticker/24hr",
+            TickerRequest::new(symbol),
+            res_id,
+            self.binance_client(),
+        )
+        .await
+    }
+
+    pub async fn get_binance_candles(
+        &self,
+        request: KlinesRequest,
+    ) -> Result<Vec<Candle>, FFIBridgeError> {
+        self.get::<_, Vec<Kline>, Vec<Candle>, _, _, _>(
+            "/klines",
             request,
+            |klines| {
+                res_id(klines.into_iter().map(Candle::from).collect())
+            },
+            self.binance_client(),
+        )
+        .await
+    }
+
+    pub async fn get_binance_order_book(
+        &self,
+        symbol: String,
+        limit: Option<u32>,
+    ) -> Result<OrderBook, FFIBridgeError> {
+        self.get::<_, OrderBook, OrderBook, _, _, _>(
+            "/depth",
+            DepthRequest::new(symbol, limit),
             res_id,
-            external_client,
+            self.binance_client(),
         )
         .await
     }
 
-    pub async fn get_coin_history_info(
+    pub async fn get_binance_exchange_info(
         &self,
-        request: CoinHistoryRequest,
-    ) -> Result<CoinHistory, FFIBridgeError> {
-        let external_client = CoinWatchClient::new_with_key(
-            self.network_antenna.get_api_keys().coin_watch,
+        symbol: Option<String>,
+    ) -> Result<ExchangeInfo, FFIBridgeError> {
+        self.get::<_, ExchangeInfo, ExchangeInfo, _, _, _>(
+            "/exchangeInfo",
+            ExchangeInfoRequest { symbol },
+            res_id,
+            self.binance_client(),
+        )
+        .await
+    }
+
+    /// Every asset the registry knows and what each provider calls
+    /// it.
+    pub fn get_asset_identities(&self) -> Vec<AssetIdentity> {
+        asset_registry::shared().read().unwrap().assets()
+    }
+
+    pub fn get_asset_identity(
+        &self,
+        code: String,
+    ) -> Option<AssetIdentity> {
+        asset_registry::shared()
+            .read()
+            .unwrap()
+            .get(&code)
+            .cloned()
+    }
+
+    /// Fills the registry in from CoinGecko's top coins and the
+    /// pairs Binance trades, returning how many identifiers were
+    /// added. Fails only when neither listing could be fetched.
+    pub async fn refresh_asset_registry(
+        &self,
+    ) -> Result<u32, FFIBridgeError> {
+        let (markets, exchange_info) = tokio::join!(
+            self.get::<_, Vec<Market>, Vec<Market>, _, _, _>(
+                "/coins/markets",
+                MarketsQuery::new(MAX_PER_PAGE),
+                res_id,
+                self.coingecko_client(),
+            ),
+            self.get_binance_exchange_info(None),
         );
+        if let (Err(error), Err(_)) = (&markets, &exchange_info) {
+            return Err(error.clone());
+        }
+        let mut registry = asset_registry::shared().write().unwrap();
+        let mut added = 0;
+        if let Ok(markets) = markets {
+            added += registry.merge_coingecko(&markets);
+        }
+        if let Ok(exchange_info) = exchange_info {
+            added += registry.merge_binance(&exchange_info);
+        }
+        Ok(added)
+    }
 
-        self.post::<_, CoinHistory, CoinHistory, _, _, _>(
-            "/coins/single/history",
-            request,
-            res_id,
-            external_client,
+    /// Alpha Vantage's top gainers, losers and most actively traded
+    /// US stocks.
+    pub async fn get_top_gainers_and_losers(
+        &self,
+    ) -> Result<TopAndBottomTrades, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<TopAndBottomTrades>, _, _, _, _>(
+            "",
+            client.keyed(TopGainersLosersRequest::default()),
+            alpha_vantage_data,
+            client,
+        )
+        .await
+    }
+
+    /// Latest trading day of a stock such as `IBM`.
+    pub async fn get_stock_quote(
+        &self,
+        symbol: String,
+    ) -> Result<StockQuote, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<GlobalQuoteResponse>, _, _, _, _>(
+            "",
+            client.keyed(GlobalQuoteRequest::new(symbol.clone())),
+            |response| {
+                alpha_vantage_data(response)?
+                    .into_quote()
+                    .ok_or(RustSideError::UnknownSymbol {
+                        symbol: symbol.to_uppercase(),
+                    })
+            },
+            client,
+        )
+        .await
+    }
+
+    /// Daily bars of a stock, oldest first.
+    pub async fn get_stock_daily(
+        &self,
+        symbol: String,
+        outputsize: Option<OutputSize>,
+    ) -> Result<StockSeries, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<TimeSeriesResponse>, _, _, _, _>(
+            "",
+            client.keyed(DailySeriesRequest { symbol, outputsize }),
+            |response| {
+                alpha_vantage_data(response)
+                    .map(TimeSeriesResponse::into_series)
+            },
+            client,
+        )
+        .await
+    }
+
+    /// Intraday bars of a stock, oldest first.
+    pub async fn get_stock_intraday(
+        &self,
+        symbol: String,
+        interval: IntradayInterval,
+        outputsize: Option<OutputSize>,
+    ) -> Result<StockSeries, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<TimeSeriesResponse>, _, _, _, _>(
+            "",
+            client.keyed(IntradaySeriesRequest {
+                symbol,
+                interval,
+                outputsize,
+            }),
+            |response| {
+                alpha_vantage_data(response)
+                    .map(TimeSeriesResponse::into_series)
+            },
+            client,
+        )
+        .await
+    }
+
+    /// Tickers matching `keywords`, best match first.
+    pub async fn search_stock_symbols(
+        &self,
+        keywords: String,
+    ) -> Result<Vec<SymbolMatch>, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<SymbolSearchResponse>, _, _, _, _>(
+            "",
+            client.keyed(SymbolSearchRequest { keywords }),
+            |response| {
+                alpha_vantage_data(response)
+                    .map(SymbolSearchResponse::into_matches)
+            },
+            client,
+        )
+        .await
+    }
+
+    /// Alpha Vantage's rate between two physical or digital
+    /// currencies, such as `BTC` to `EUR`.
+    pub async fn get_currency_exchange_rate(
+        &self,
+        from_currency: String,
+        to_currency: String,
+    ) -> Result<CurrencyExchangeRate, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        let code = from_currency.to_uppercase();
+        self.get::<_, AlphaVantageResponse<CurrencyExchangeRateResponse>, _, _, _, _>(
+            "",
+            client.keyed(CurrencyExchangeRateRequest {
+                from_currency,
+                to_currency,
+            }),
+            |response| {
+                alpha_vantage_data(response)?.into_rate().ok_or(
+                    RustSideError::NoRateForCoin { code: code.clone() },
+                )
+            },
+            client,
+        )
+        .await
+    }
+
+    /// Daily bars of a currency pair such as `EUR` to `USD`, oldest
+    /// first.
+    pub async fn get_fx_daily(
+        &self,
+        from_symbol: String,
+        to_symbol: String,
+        outputsize: Option<OutputSize>,
+    ) -> Result<FxSeries, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<TimeSeriesResponse>, _, _, _, _>(
+            "",
+            client.keyed(FxDailyRequest {
+                from_symbol,
+                to_symbol,
+                outputsize,
+            }),
+            |response| {
+                alpha_vantage_data(response)
+                    .map(TimeSeriesResponse::into_fx_series)
+            },
+            client,
+        )
+        .await
+    }
+
+    /// Daily bars of a coin priced in `market`, such as `EUR`,
+    /// oldest first.
+    pub async fn get_digital_currency_daily(
+        &self,
+        symbol: String,
+        market: String,
+    ) -> Result<DigitalCurrencySeries, FFIBridgeError> {
+        let client = self.alpha_vantage_client();
+        self.get::<_, AlphaVantageResponse<TimeSeriesResponse>, _, _, _, _>(
+            "",
+            client.keyed(DigitalCurrencyDailyRequest { symbol, market }),
+            |response| {
+                alpha_vantage_data(response)
+                    .map(TimeSeriesResponse::into_digital_currency_series)
+            },
+            client,
         )
         .await
     }
 }
 
 impl Gateway {
+    fn alpha_vantage_client(&self) -> AlphaVantageClient {
+        AlphaVantageClient::new_with_key(
+            self.network_antenna.get_api_keys().alpha,
+        )
+    }
+
+    fn coin_watch_client(&self) -> CoinWatchClient {
+        CoinWatchClient::new_with_key(
+            self.network_antenna.get_api_keys().coin_watch,
+        )
+    }
+
+    /// Keyless when `ClientKeys.binance` is empty, public market
+    /// data doesn't need one.
+    fn binance_client(&self) -> BinanceClient {
+        BinanceClient::new_with_key(
+            self.network_antenna.get_api_keys().binance,
+        )
+    }
+
+    fn coingecko_client(&self) -> CoinGeckoClient {
+        let keys = self.network_antenna.get_api_keys();
+        CoinGeckoClient::new_with_key(
+            keys.coingecko,
+            keys.coingecko_plan,
+        )
+    }
+
     fn model_from_response<U>(
         &self,
         response: FFINetworkingResponse,
@@ -149,6 +678,19 @@ impl Gateway {
             headers: client.get_headers(),
         };
 
+        self.dispatch(request, map).await
+    }
+
+    async fn dispatch<U, V, F, E>(
+        &self,
+        request: FFINetworkingRequest,
+        map: F,
+    ) -> Result<V, FFIBridgeError>
+    where
+        U: for<'a> Deserialize<'a> + std::fmt::Debug,
+        F: Fn(U) -> Result<V, E>,
+        E: Into<FFIBridgeError>,
+    {
         // Let Swift side make network request and await response
         // let response = self.networking_dispatcher.dispatch(request).await?;
         let response = self
@@ -165,6 +707,37 @@ impl Gateway {
         map(model).map_err(|e| e.into())
     }
 
+    /// Sends `query` as the query string of a `GET` without a body.
+    pub(crate) async fn get<T, U, V, F, E, C>(
+        &self,
+        path: &str,
+        query: T,
+        map: F,
+        client: C,
+    ) -> Result<V, FFIBridgeError>
+    where
+        T: QueryItems<Query = String> + std::fmt::Debug,
+        U: for<'a> Deserialize<'a> + std::fmt::Debug,
+        F: Fn(U) -> Result<V, E>,
+        E: Into<FFIBridgeError>,
+        C: Client,
+    {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}{}", client.get_base_url(), path),
+            query.get_all_queries(),
+        )
+        .map_err(|_| RustSideError::InvalidRequestUrl)?;
+
+        let request = FFINetworkingRequest {
+            url: url.to_string(),
+            body: vec![],
+            method: "GET".to_owned(),
+            headers: client.get_headers(),
+        };
+
+        self.dispatch(request, map).await
+    }
+
     pub(crate) async fn post<T, U, V, F, E, C>(
         &self,
         path: &str,
@@ -190,6 +763,25 @@ const fn res_id<T>(x: T) -> Result<T, FFIBridgeError> {
     identity::<Result<T, FFIBridgeError>>(Ok(x))
 }
 
+/// CoinAPI needs a key `ClientKeys` doesn't carry, and Binance has
+/// no ranked listing of coins.
+/// The data of an Alpha Vantage response, which reports errors
+/// and exhausted keys in a `200 OK` body.
+fn alpha_vantage_data<T>(
+    response: AlphaVantageResponse<T>,
+) -> Result<T, RustSideError> {
+    response
+        .into_result()
+        .map_err(|message| RustSideError::AlphaVantage { message })
+}
+
+fn unsupported(source: DataSource) -> FFIBridgeError {
+    RustSideError::UnsupportedByDataSource {
+        data_source: source.as_str().to_owned(),
+    }
+    .into()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -211,7 +803,7 @@ mod tests {
         )]
         fn make_request<'life0, 'async_trait>(
             &'life0 self,
-            request: FFINetworkingRequest,
+            _request: FFINetworkingRequest,
         ) -> ::core::pin::Pin<
             Box<
                 dyn ::core::future::Future<
@@ -237,6 +829,274 @@ mod tests {
     #[test]
     fn new_gateway_client() {
         let test_antenna = TestAntenna::new();
-        let gateway = Gateway::new(Arc::new(test_antenna));
+        let _gateway = Gateway::new(Arc::new(test_antenna));
+    }
+
+    /// Answers every request with `body` and keeps the requests.
+    struct RecordingAntenna {
+        body: &'static str,
+        requests: std::sync::Mutex<Vec<FFINetworkingRequest>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NetworkAntenna for RecordingAntenna {
+        async fn make_request(
+            &self,
+            request: FFINetworkingRequest,
+        ) -> Result<FFINetworkingResponse, FFINetworkingError>
+        {
+            self.requests.lock().unwrap().push(request);
+            Ok(FFINetworkingResponse {
+                status_code: 200,
+                body: self.body.as_bytes().to_vec(),
+            })
+        }
+
+        fn get_api_keys(&self) -> ClientKeys {
+            ClientKeys {
+                binance: String::new(),
+                coin_watch: "lcw".into(),
+                alpha: String::new(),
+                coingecko: "CG-demo".into(),
+                coingecko_plan: CoinGeckoPlan::Public,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn coin_meta_from_coingecko() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"{"id":"bitcoin","symbol":"btc","name":"Bitcoin","market_data":{"current_price":{"usd":64000.0}}}"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::with_source(
+            antenna.clone(),
+            DataSource::CoinGecko,
+        );
+
+        let meta = gateway
+            .get_coin_meta_info(CoinMetaRequest::new(
+                "BTC".into(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(meta.code, Some("BTC".into()));
+        assert_eq!(meta.rate, Some(64000.0));
+        let requests = antenna.requests.lock().unwrap();
+        assert_eq!(requests[0].method, "GET");
+        assert!(requests[0].body.is_empty());
+        assert!(requests[0].url.starts_with(
+            "https://api.coingecko.com/api/v3/coins/bitcoin?"
+        ));
+        assert!(requests[0].url.contains("tickers=false"));
+        assert_eq!(
+            requests[0].headers.get("x-cg-demo-api-key").unwrap(),
+            "CG-demo"
+        );
+    }
+
+    #[tokio::test]
+    async fn binance_candles_from_klines() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"[[1499040000000,"1.0","2.0","0.5","1.5","10.0",1499043599999,"15.0",3,"5.0","7.5","0"]]"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::new(antenna.clone());
+
+        let candles = gateway
+            .get_binance_candles(KlinesRequest::new(
+                "btcusdt".into(),
+                crate::binance_service::models::KlineInterval::OneDay,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(candles[0].close, 1.5);
+        let requests = antenna.requests.lock().unwrap();
+        assert!(requests[0]
+            .url
+            .starts_with("https://api.binance.com/api/v3/klines?"));
+        assert!(requests[0].url.contains("interval=1d"));
+        assert!(!requests[0].headers.contains_key("x-mbx-apikey"));
+    }
+
+    #[tokio::test]
+    async fn source_per_call_overrides_default() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: "[]",
+            requests: Default::default(),
+        });
+        let gateway = Gateway::with_source(
+            antenna.clone(),
+            DataSource::CoinGecko,
+        );
+
+        gateway
+            .get_list_of_coins_from(10, DataSource::LiveCoinWatch)
+            .await
+            .unwrap();
+
+        let requests = antenna.requests.lock().unwrap();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            requests[0].url,
+            "https://api.livecoinwatch.com/coins/list"
+        );
+    }
+
+    #[tokio::test]
+    async fn coin_meta_from_binance_ticker() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"{"symbol":"ETHUSDT","priceChange":"10","priceChangePercent":"1.0","weightedAvgPrice":"3000","prevClosePrice":"2990","lastPrice":"3000.5","bidPrice":"3000","askPrice":"3001","openPrice":"2990","highPrice":"3010","lowPrice":"2980","volume":"100","quoteVolume":"300000","openTime":1,"closeTime":2,"count":5}"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::with_source(
+            antenna.clone(),
+            DataSource::Binance,
+        );
+
+        let meta = gateway
+            .get_coin_meta_info(CoinMetaRequest::new(
+                "eth".into(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(meta.rate, Some(3000.5));
+        assert!(antenna.requests.lock().unwrap()[0]
+            .url
+            .contains("symbol=ETHUSDT"));
+
+        assert_eq!(
+            gateway
+                .get_list_of_coins_from(10, DataSource::Binance)
+                .await
+                .unwrap_err(),
+            FFIBridgeError::from(
+                RustSideError::UnsupportedByDataSource {
+                    data_source: "binance".into(),
+                }
+            )
+        );
+
+        // Tether has no Tether pair, so nothing is asked for.
+        assert_eq!(
+            gateway
+                .get_quote_from("usdt".into(), DataSource::Binance)
+                .await
+                .unwrap_err(),
+            FFIBridgeError::from(
+                RustSideError::UnsupportedByDataSource {
+                    data_source: "binance".into(),
+                }
+            )
+        );
+        assert_eq!(antenna.requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn consensus_leaves_out_failing_sources() {
+        // Only CoinGecko can make sense of this body.
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"{"bitcoin":{"usd":64000.0,"last_updated_at":1711929600}}"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::new(antenna.clone());
+
+        let consensus = gateway
+            .get_consensus_quote("btc".into(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(consensus.rate, 64000.0);
+        assert_eq!(consensus.sources, vec![DataSource::CoinGecko]);
+        assert!(!consensus.disagreement);
+        assert_eq!(antenna.requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_the_listing_that_came_through() {
+        // Binance's `/exchangeInfo` can't be read from this body.
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"[{"id":"gateway-test-coin","symbol":"gtc","name":"Gateway Test Coin"}]"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::new(antenna.clone());
+
+        assert_eq!(gateway.refresh_asset_registry().await, Ok(1));
+
+        let identity =
+            gateway.get_asset_identity("gtc".into()).unwrap();
+        assert_eq!(
+            identity.coingecko,
+            Some("gateway-test-coin".into())
+        );
+        assert_eq!(identity.binance, None);
+        assert_eq!(antenna.requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stock_quote_from_alpha_vantage() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"{"Global Quote":{"01. symbol":"IBM","05. price":"190.9600","07. latest trading day":"2024-03-28"}}"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::new(antenna.clone());
+
+        let quote =
+            gateway.get_stock_quote("ibm".into()).await.unwrap();
+
+        assert_eq!(quote.price, Some(190.96));
+        assert_eq!(quote.latest_trading_day, "2024-03-28");
+        let requests = antenna.requests.lock().unwrap();
+        assert!(requests[0]
+            .url
+            .starts_with("https://www.alphavantage.co/query?"));
+        assert!(requests[0].url.contains("function=GLOBAL_QUOTE"));
+        assert!(requests[0].url.contains("symbol=IBM"));
+    }
+
+    #[tokio::test]
+    async fn alpha_vantage_errors_in_the_body() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"{"Error Message":"Invalid API call."}"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::new(antenna);
+
+        assert_eq!(
+            gateway
+                .get_stock_daily("XYZ".into(), None)
+                .await
+                .unwrap_err(),
+            RustSideError::AlphaVantage {
+                message: "Invalid API call.".into()
+            }
+            .into()
+        );
+    }
+
+    #[tokio::test]
+    async fn currency_exchange_rate_from_alpha_vantage() {
+        let antenna = Arc::new(RecordingAntenna {
+            body: r#"{"Realtime Currency Exchange Rate":{"1. From_Currency Code":"BTC","3. To_Currency Code":"EUR","5. Exchange Rate":"64356.12000000"}}"#,
+            requests: Default::default(),
+        });
+        let gateway = Gateway::new(antenna.clone());
+
+        let rate = gateway
+            .get_currency_exchange_rate("btc".into(), "eur".into())
+            .await
+            .unwrap();
+
+        assert_eq!(rate.rate, 64356.12);
+        assert_eq!(rate.bid, None);
+        let requests = antenna.requests.lock().unwrap();
+        assert!(requests[0]
+            .url
+            .contains("function=CURRENCY_EXCHANGE_RATE"));
+        assert!(requests[0].url.contains("from_currency=BTC"));
+        assert!(requests[0].url.contains("to_currency=EUR"));
     }
 }
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use uniffi::{Enum, Record};
+
+use crate::{
+    client_trait::Asset,
+    coin_watch_service::models::{Coin, CoinMeta, Delta},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AssetClass {
+    Equity,
+    Crypto,
+}
+
+impl AssetClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetClass::Equity => "equity",
+            AssetClass::Crypto => "crypto",
+        }
+    }
+}
+
+/// Change over each window in percent, `None` where the source
+/// doesn't report it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChangePercentages {
+    pub hour: Option<f64>,
+    pub day: Option<f64>,
+    pub week: Option<f64>,
+    pub month: Option<f64>,
+    pub year: Option<f64>,
+}
+
+impl From<&Delta> for ChangePercentages {
+    /// Live Coin Watch reports change as a multiplier where `1.05`
+    /// is up 5%.
+    fn from(delta: &Delta) -> Self {
+        let percent = |delta: Option<f64>| {
+            delta.map(|delta| (delta - 1.0) * 100.0)
+        };
+        Self {
+            hour: percent(delta.hour),
+            day: percent(delta.day),
+            week: percent(delta.week),
+            month: percent(delta.month),
+            year: percent(delta.year),
+        }
+    }
+}
+
+/// An [`Asset`] in a shape shared by stocks and coins, so they can
+/// be listed together.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AssetSummary {
+    pub id: String,
+    pub symbol: String,
+    pub name: Option<String>,
+    pub asset_class: AssetClass,
+    /// Latest price in USD.
+    pub quote: Option<f64>,
+    pub change: ChangePercentages,
+}
+
+/// Orders `assets` by their change over the last day, those without
+/// one last either way.
+pub fn sort_by_change(
+    assets: &mut [AssetSummary],
+    descending: bool,
+) {
+    assets.sort_by(|a, b| {
+        match (a.change.day, b.change.day) {
+            (Some(a), Some(b)) if descending => {
+                b.total_cmp(&a)
+            }
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    });
+}
+
+impl Asset for Coin {
+    fn symbol(&self) -> String {
+        self.code.clone().unwrap_or_default()
+    }
+
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    fn asset_class(&self) -> AssetClass {
+        AssetClass::Crypto
+    }
+
+    fn quote(&self) -> Option<f64> {
+        self.rate
+    }
+
+    fn change(&self) -> ChangePercentages {
+        ChangePercentages::from(&self.delta)
+    }
+}
+
+impl Asset for CoinMeta {
+    fn symbol(&self) -> String {
+        self.code
+            .clone()
+            .or(self.symbol.clone())
+            .unwrap_or_default()
+    }
+
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn asset_class(&self) -> AssetClass {
+        AssetClass::Crypto
+    }
+
+    fn quote(&self) -> Option<f64> {
+        self.rate
+    }
+
+    fn change(&self) -> ChangePercentages {
+        self.delta
+            .as_ref()
+            .map(ChangePercentages::from)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphavantage_service::models::{
+        MostActivelyTraded, RawTrade,
+    };
+
+    fn stock(ticker: &str, change: &str) -> AssetSummary {
+        MostActivelyTraded::from_raw(
+            ticker.into(),
+            RawTrade {
+                price: "10.5".into(),
+                change_amount: "0.5".into(),
+                change_percentage: change.into(),
+                volume: "1000".into(),
+            },
+        )
+        .summary()
+    }
+
+    fn coin(code: &str, day: Option<f64>) -> AssetSummary {
+        Coin::new(
+            Some(code.into()),
+            Some(64000.0),
+            None,
+            None,
+            Delta::new(None, day, None, None, None, None),
+        )
+        .summary()
+    }
+
+    #[test]
+    fn stocks_and_coins_share_a_summary() {
+        let stock = stock("nvda", "4.5%");
+        assert_eq!(stock.id, "NVDA");
+        assert_eq!(stock.asset_class, AssetClass::Equity);
+        assert_eq!(stock.quote, Some(10.5));
+        assert_eq!(stock.change.day, Some(4.5));
+
+        let coin = coin("BTC", Some(1.02));
+        assert_eq!(coin.asset_class, AssetClass::Crypto);
+        assert!(
+            (coin.change.day.unwrap() - 2.0).abs() < 1e-9
+        );
+        assert_eq!(coin.change.hour, None);
+    }
+
+    #[test]
+    fn sorted_by_change_unknown_last() {
+        let mut assets = vec![
+            coin("ETH", None),
+            stock("NVDA", "-3.0%"),
+            coin("BTC", Some(1.02)),
+            stock("TSLA", "4.5%"),
+        ];
+        let symbols = |assets: &[AssetSummary]| {
+            assets
+                .iter()
+                .map(|asset| asset.symbol.clone())
+                .collect::<Vec<_>>()
+        };
+
+        sort_by_change(&mut assets, true);
+        assert_eq!(
+            symbols(&assets),
+            ["TSLA", "BTC", "NVDA", "ETH"]
+        );
+        sort_by_change(&mut assets, false);
+        assert_eq!(
+            symbols(&assets),
+            ["NVDA", "BTC", "TSLA", "ETH"]
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use std::{
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use uniffi::{Enum, Record};
+
+use crate::{
+    binance_service::models::ExchangeInfo,
+    coinapi_service::models::CoinApiAsset,
+    coingecko_service::models::Market,
+    data_source::DataSource,
+};
+
+/// Identities the registry starts out with, see `data/assets.json`.
+const BUNDLED: &str = include_str!("../data/assets.json");
+
+/// Upstream that names assets its own way.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AssetProvider {
+    LiveCoinWatch,
+    CoinGecko,
+    Binance,
+    CoinApi,
+    AlphaVantage,
+}
+
+impl From<DataSource> for AssetProvider {
+    fn from(source: DataSource) -> Self {
+        match source {
+            DataSource::LiveCoinWatch => {
+                AssetProvider::LiveCoinWatch
+            }
+            DataSource::CoinGecko => {
+                AssetProvider::CoinGecko
+            }
+            DataSource::Binance => AssetProvider::Binance,
+            DataSource::CoinApi => AssetProvider::CoinApi,
+        }
+    }
+}
+
+/// What each provider calls an asset. `None` where a provider
+/// isn't known to list it.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AssetIdentity {
+    /// Canonical id, the Live Coin Watch style code such as `BTC`.
+    pub id: String,
+    pub name: Option<String>,
+    pub live_coin_watch: Option<String>,
+    /// Id such as `bitcoin`.
+    pub coingecko: Option<String>,
+    /// Pair against Tether such as `BTCUSDT`.
+    pub binance: Option<String>,
+    pub coinapi: Option<String>,
+    /// Ticker such as `BTC`.
+    pub alpha_vantage: Option<String>,
+}
+
+impl AssetIdentity {
+    fn new(id: String, name: Option<String>) -> Self {
+        Self {
+            id,
+            name,
+            live_coin_watch: None,
+            coingecko: None,
+            binance: None,
+            coinapi: None,
+            alpha_vantage: None,
+        }
+    }
+
+    pub fn identifier(
+        &self,
+        provider: AssetProvider,
+    ) -> Option<&str> {
+        match provider {
+            AssetProvider::LiveCoinWatch => {
+                &self.live_coin_watch
+            }
+            AssetProvider::CoinGecko => &self.coingecko,
+            AssetProvider::Binance => &self.binance,
+            AssetProvider::CoinApi => &self.coinapi,
+            AssetProvider::AlphaVantage => {
+                &self.alpha_vantage
+            }
+        }
+        .as_deref()
+    }
+
+    fn identifier_mut(
+        &mut self,
+        provider: AssetProvider,
+    ) -> &mut Option<String> {
+        match provider {
+            AssetProvider::LiveCoinWatch => {
+                &mut self.live_coin_watch
+            }
+            AssetProvider::CoinGecko => &mut self.coingecko,
+            AssetProvider::Binance => &mut self.binance,
+            AssetProvider::CoinApi => &mut self.coinapi,
+            AssetProvider::AlphaVantage => {
+                &mut self.alpha_vantage
+            }
+        }
+    }
+}
+
+/// Identifier a provider most likely uses for `code` when the
+/// registry doesn't know.
+fn conventional(
+    code: &str,
+    provider: AssetProvider,
+) -> String {
+    let code = code.to_uppercase();
+    match provider {
+        AssetProvider::CoinGecko => code.to_lowercase(),
+        AssetProvider::Binance => format!("{code}USDT"),
+        _ => code,
+    }
+}
+
+/// Maps canonical asset ids to each provider's identifier. Seeded
+/// from the bundled data and filled in from provider listings,
+/// which never replace an identifier already known.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AssetRegistry {
+    assets: BTreeMap<String, AssetIdentity>,
+}
+
+impl AssetRegistry {
+    pub fn bundled() -> Self {
+        Self::from_json(BUNDLED)
+            .expect("bundled assets.json to be valid")
+    }
+
+    pub fn from_json(
+        json: &str,
+    ) -> Result<Self, serde_json::Error> {
+        let identities: Vec<AssetIdentity> =
+            serde_json::from_str(json)?;
+        Ok(Self {
+            assets: identities
+                .into_iter()
+                .map(|identity| {
+                    (identity.id.to_uppercase(), identity)
+                })
+                .collect(),
+        })
+    }
+
+    pub fn get(
+        &self,
+        code: &str,
+    ) -> Option<&AssetIdentity> {
+        self.assets.get(&code.to_uppercase())
+    }
+
+    /// Every known asset, ordered by id.
+    pub fn assets(&self) -> Vec<AssetIdentity> {
+        self.assets.values().cloned().collect()
+    }
+
+    /// What `provider` calls the asset with the canonical id
+    /// `code`, its naming convention when the registry doesn't
+    /// know the asset. `None` for a known asset `provider` doesn't
+    /// list, such as Tether on Binance.
+    pub fn identifier(
+        &self,
+        code: &str,
+        provider: AssetProvider,
+    ) -> Option<String> {
+        match self.get(code) {
+            Some(identity) => identity
+                .identifier(provider)
+                .map(str::to_string),
+            None => Some(conventional(code, provider)),
+        }
+    }
+
+    /// Canonical id of the asset `provider` calls `identifier`.
+    pub fn canonical(
+        &self,
+        provider: AssetProvider,
+        identifier: &str,
+    ) -> Option<String> {
+        self.assets
+            .values()
+            .find(|identity| {
+                identity.identifier(provider).is_some_and(
+                    |known| {
+                        known.eq_ignore_ascii_case(
+                            identifier,
+                        )
+                    },
+                )
+            })
+            .map(|identity| identity.id.clone())
+    }
+
+    /// Adds the CoinGecko id of each market, and the market itself
+    /// when its symbol is new. Markets come by market cap, so the
+    /// biggest coin keeps a symbol several coins share. Returns how
+    /// many identifiers were added.
+    pub fn merge_coingecko(
+        &mut self,
+        markets: &[Market],
+    ) -> u32 {
+        let mut added = 0;
+        for market in markets {
+            if self
+                .canonical(
+                    AssetProvider::CoinGecko,
+                    &market.id,
+                )
+                .is_some()
+            {
+                continue;
+            }
+            let code = market.symbol.to_uppercase();
+            let identity = self
+                .assets
+                .entry(code.clone())
+                .or_insert_with(|| {
+                    AssetIdentity::new(
+                        code,
+                        Some(market.name.clone()),
+                    )
+                });
+            if identity.coingecko.is_none() {
+                identity.coingecko =
+                    Some(market.id.clone());
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Adds the Tether pairs Binance is trading for known assets.
+    pub fn merge_binance(
+        &mut self,
+        info: &ExchangeInfo,
+    ) -> u32 {
+        self.fill(
+            AssetProvider::Binance,
+            info.symbols
+                .iter()
+                .filter(|symbol| {
+                    symbol.quote_asset == "USDT"
+                        && symbol.status == "TRADING"
+                })
+                .map(|symbol| {
+                    (&symbol.base_asset, &symbol.symbol)
+                }),
+        )
+    }
+
+    /// Adds the CoinAPI ids of known crypto assets.
+    pub fn merge_coinapi(
+        &mut self,
+        assets: &[CoinApiAsset],
+    ) -> u32 {
+        self.fill(
+            AssetProvider::CoinApi,
+            assets
+                .iter()
+                .filter(|asset| asset.type_is_crypto)
+                .map(|asset| {
+                    (&asset.asset_id, &asset.asset_id)
+                }),
+        )
+    }
+
+    /// Sets `provider`'s identifier of known assets that lack one,
+    /// from `(code, identifier)` pairs.
+    fn fill<'a>(
+        &mut self,
+        provider: AssetProvider,
+        identifiers: impl Iterator<
+            Item = (&'a String, &'a String),
+        >,
+    ) -> u32 {
+        let mut added = 0;
+        for (code, identifier) in identifiers {
+            let Some(identity) =
+                self.assets.get_mut(&code.to_uppercase())
+            else {
+                continue;
+            };
+            let slot = identity.identifier_mut(provider);
+            if slot.is_none() {
+                *slot = Some(identifier.clone());
+                added += 1;
+            }
+        }
+        added
+    }
+}
+
+/// The registry every crate-wide lookup goes through, starting out
+/// as [`AssetRegistry::bundled`].
+pub fn shared() -> &'static RwLock<AssetRegistry> {
+    static SHARED: OnceLock<RwLock<AssetRegistry>> =
+        OnceLock::new();
+    SHARED.get_or_init(|| {
+        RwLock::new(AssetRegistry::bundled())
+    })
+}
+
+/// [`AssetRegistry::identifier`] of the shared registry.
+pub fn identifier(
+    code: &str,
+    provider: AssetProvider,
+) -> Option<String> {
+    shared().read().unwrap().identifier(code, provider)
+}
+
+/// [`AssetRegistry::canonical`] of the shared registry.
+pub fn canonical(
+    provider: AssetProvider,
+    identifier: &str,
+) -> Option<String> {
+    shared().read().unwrap().canonical(provider, identifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binance_service::models::SymbolInfo;
+
+    fn market(id: &str, symbol: &str) -> Market {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "symbol": symbol,
+            "name": id,
+        }))
+        .unwrap()
+    }
+
+    fn pair(
+        base: &str,
+        quote: &str,
+        status: &str,
+    ) -> SymbolInfo {
+        SymbolInfo {
+            symbol: format!("{base}{quote}"),
+            status: status.into(),
+            base_asset: base.into(),
+            quote_asset: quote.into(),
+            base_asset_precision: 8,
+            quote_asset_precision: 8,
+        }
+    }
+
+    #[test]
+    fn bundled_identifiers() {
+        let registry = AssetRegistry::bundled();
+        assert_eq!(
+            registry.identifier(
+                "avax",
+                AssetProvider::CoinGecko
+            ),
+            Some("avalanche-2".into())
+        );
+        assert_eq!(
+            registry
+                .identifier("BTC", AssetProvider::Binance),
+            Some("BTCUSDT".into())
+        );
+        assert_eq!(
+            registry
+                .identifier("usdt", AssetProvider::Binance),
+            None
+        );
+        assert_eq!(
+            registry.canonical(
+                AssetProvider::CoinGecko,
+                "ripple"
+            ),
+            Some("XRP".into())
+        );
+    }
+
+    #[test]
+    fn unknown_falls_back_to_convention() {
+        let registry = AssetRegistry::default();
+        assert_eq!(
+            registry.identifier(
+                "pepe",
+                AssetProvider::CoinGecko
+            ),
+            Some("pepe".into())
+        );
+        assert_eq!(
+            registry
+                .identifier("pepe", AssetProvider::Binance),
+            Some("PEPEUSDT".into())
+        );
+        assert_eq!(
+            registry.canonical(
+                AssetProvider::CoinGecko,
+                "pepe"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn coingecko_adds_new_assets_but_keeps_known_ids() {
+        let mut registry = AssetRegistry::bundled();
+        let added = registry.merge_coingecko(&[
+            market("bitcoin", "btc"),
+            market("pepe", "pepe"),
+            market("pepe-fork", "pepe"),
+        ]);
+        assert_eq!(added, 1);
+        assert_eq!(
+            registry.identifier(
+                "PEPE",
+                AssetProvider::CoinGecko
+            ),
+            Some("pepe".into())
+        );
+        assert_eq!(
+            registry.identifier(
+                "BTC",
+                AssetProvider::CoinGecko
+            ),
+            Some("bitcoin".into())
+        );
+    }
+
+    #[test]
+    fn binance_fills_trading_tether_pairs_of_known_assets()
+    {
+        let mut registry = AssetRegistry::bundled();
+        let added = registry.merge_binance(&ExchangeInfo {
+            timezone: "UTC".into(),
+            server_time: 0,
+            symbols: vec![
+                pair("XMR", "USDT", "TRADING"),
+                pair("USDT", "TRY", "TRADING"),
+                pair("BTC", "USDT", "TRADING"),
+                pair("PEPE", "USDT", "TRADING"),
+            ],
+        });
+        assert_eq!(added, 1);
+        assert_eq!(
+            registry.get("XMR").unwrap().binance,
+            Some("XMRUSDT".into())
+        );
+        assert!(registry.get("PEPE").is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use core::fmt::Debug;
+use std::{collections::HashMap, env};
+
+use crate::client_trait::Client;
+
+pub const BASE_URL: &str = "https://api.binance.com/api/v3";
+
+/// Client for Binance's public market data. None of it needs a
+/// key, but calls made with one count against the key's own limits.
+#[derive(PartialEq, Debug, Clone)]
+pub struct BinanceClient {
+    pub headers: HashMap<String, String>,
+    pub base_url: String,
+}
+
+impl Default for BinanceClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinanceClient {
+    /// Reads `BINANCE_API_KEY`, calling without a key when it's not
+    /// set.
+    pub fn new() -> Self {
+        Self::new_with_key(
+            env::var("BINANCE_API_KEY").unwrap_or_default(),
+        )
+    }
+
+    pub fn new_with_key(key: String) -> Self {
+        let mut headers = HashMap::new();
+        headers.insert(
+            "content-type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        );
+        if !key.is_empty() {
+            headers.insert("x-mbx-apikey".to_string(), key);
+        }
+        Self {
+            headers,
+            base_url: BASE_URL.to_string(),
+        }
+    }
+}
+
+impl Client for BinanceClient {
+    fn get_base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn get_headers(&self) -> HashMap<String, String> {
+        self.headers.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_base_url_binance_client() {
+        assert_eq!(
+            BinanceClient::new_with_key(String::new())
+                .get_base_url(),
+            "https://api.binance.com/api/v3"
+        )
+    }
+
+    #[test]
+    fn get_headers_binance_client() {
+        let binance_client =
+            BinanceClient::new_with_key("TEST".into());
+
+        assert_eq!(
+            binance_client
+                .get_headers()
+                .get("content-type")
+                .unwrap(),
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(
+            binance_client
+                .get_headers()
+                .get("x-mbx-apikey")
+                .unwrap(),
+            "TEST"
+        )
+    }
+
+    #[test]
+    fn no_key_header_without_key() {
+        assert!(
+            !BinanceClient::new_with_key(String::new())
+                .get_headers()
+                .contains_key("x-mbx-apikey")
+        );
+    }
+}
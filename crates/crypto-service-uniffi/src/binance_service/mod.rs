@@ -0,0 +1,2 @@
+pub mod binance_client;
+pub mod models;
@@ -0,0 +1,771 @@
+use std::collections::HashMap;
+
+use serde::{
+    de::IgnoredAny, Deserialize, Deserializer, Serialize,
+};
+use uniffi::{Enum, Record};
+
+use crate::{
+    asset_registry::{self, AssetProvider},
+    client_trait::QueryItems,
+    coin_watch_service::models::{
+        CoinHistory, CoinHistoryRequest, CoinMeta, Delta,
+        History,
+    },
+    data_source::{DataSource, Quote},
+};
+
+/// Most candles `/klines` returns per call.
+pub const MAX_KLINES: u32 = 1000;
+/// Most levels per side `/depth` returns.
+pub const MAX_DEPTH: u32 = 5000;
+
+/// Binance sends prices and quantities as strings to keep their
+/// precision.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+fn number_from_string<'de, D>(
+    deserializer: D,
+) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number),
+        NumberOrString::String(string) => {
+            string.parse().map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+/// Binance pair quoting `code` in Tether, which stands in for USD
+/// since Binance has no USD pairs. `None` for coins Binance has no
+/// such pair for, Tether itself among them.
+pub fn usdt_pair(code: &str) -> Option<String> {
+    asset_registry::identifier(code, AssetProvider::Binance)
+}
+
+/// Query of `/ticker/24hr` for a pair such as `BTCUSDT`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct TickerRequest {
+    pub symbol: String,
+}
+
+impl TickerRequest {
+    pub fn new(symbol: String) -> Self {
+        Self { symbol }
+    }
+}
+
+impl QueryItems for TickerRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries
+            .insert("symbol", self.symbol.to_uppercase());
+        queries
+    }
+}
+
+/// Rolling 24 hour statistics of a pair.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Ticker {
+    pub symbol: String,
+    #[serde(deserialize_with = "number_from_string")]
+    pub price_change: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub price_change_percent: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub weighted_avg_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub prev_close_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub last_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub bid_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub ask_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub open_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub high_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub low_price: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub volume: f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub quote_volume: f64,
+    pub open_time: i64,
+    pub close_time: i64,
+    /// Number of trades in the window.
+    pub count: u64,
+}
+
+impl Ticker {
+    /// Last price of the [`usdt_pair`] of `code`.
+    pub fn quote(&self, code: &str) -> Quote {
+        Quote {
+            code: code.to_uppercase(),
+            rate: self.last_price,
+            time: self.close_time,
+            source: DataSource::Binance,
+        }
+    }
+
+    /// What a ticker knows of `code`, its price and 24 hour change
+    /// as a Live Coin Watch style multiplier.
+    pub fn coin_meta(&self, code: &str) -> CoinMeta {
+        CoinMeta {
+            name: None,
+            symbol: Some(code.to_uppercase()),
+            rank: None,
+            color: None,
+            png64: None,
+            webp64: None,
+            all_time_high_usd: None,
+            code: Some(code.to_uppercase()),
+            rate: Some(self.last_price),
+            delta: Some(Delta::new(
+                None,
+                Some(
+                    1.0 + self.price_change_percent / 100.0,
+                ),
+                None,
+                None,
+                None,
+                None,
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum KlineInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[default]
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "12h")]
+    TwelveHours,
+    #[serde(rename = "1d")]
+    OneDay,
+    #[serde(rename = "1w")]
+    OneWeek,
+    #[serde(rename = "1M")]
+    OneMonth,
+}
+
+impl KlineInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KlineInterval::OneMinute => "1m",
+            KlineInterval::FiveMinutes => "5m",
+            KlineInterval::FifteenMinutes => "15m",
+            KlineInterval::ThirtyMinutes => "30m",
+            KlineInterval::OneHour => "1h",
+            KlineInterval::FourHours => "4h",
+            KlineInterval::TwelveHours => "12h",
+            KlineInterval::OneDay => "1d",
+            KlineInterval::OneWeek => "1w",
+            KlineInterval::OneMonth => "1M",
+        }
+    }
+
+    /// Length of a candle, taking a month as 30 days.
+    pub fn millis(&self) -> u64 {
+        const MINUTE: u64 = 60_000;
+        match self {
+            KlineInterval::OneMinute => MINUTE,
+            KlineInterval::FiveMinutes => 5 * MINUTE,
+            KlineInterval::FifteenMinutes => 15 * MINUTE,
+            KlineInterval::ThirtyMinutes => 30 * MINUTE,
+            KlineInterval::OneHour => 60 * MINUTE,
+            KlineInterval::FourHours => 240 * MINUTE,
+            KlineInterval::TwelveHours => 720 * MINUTE,
+            KlineInterval::OneDay => 1440 * MINUTE,
+            KlineInterval::OneWeek => 7 * 1440 * MINUTE,
+            KlineInterval::OneMonth => 30 * 1440 * MINUTE,
+        }
+    }
+
+    /// Finest interval covering `span` milliseconds in a single
+    /// call of `/klines`.
+    pub fn for_span(span: u64) -> Self {
+        [
+            KlineInterval::OneMinute,
+            KlineInterval::FiveMinutes,
+            KlineInterval::FifteenMinutes,
+            KlineInterval::ThirtyMinutes,
+            KlineInterval::OneHour,
+            KlineInterval::FourHours,
+            KlineInterval::TwelveHours,
+            KlineInterval::OneDay,
+            KlineInterval::OneWeek,
+        ]
+        .into_iter()
+        .find(|interval| {
+            span <= interval.millis() * MAX_KLINES as u64
+        })
+        .unwrap_or(KlineInterval::OneMonth)
+    }
+}
+
+/// Query of `/klines`. Times are milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct KlinesRequest {
+    pub symbol: String,
+    pub interval: KlineInterval,
+    pub limit: Option<u32>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+}
+
+impl KlinesRequest {
+    pub fn new(
+        symbol: String,
+        interval: KlineInterval,
+    ) -> Self {
+        Self {
+            symbol,
+            interval,
+            limit: None,
+            start_time: None,
+            end_time: None,
+        }
+    }
+
+    /// Candles of the [`usdt_pair`] of a Live Coin Watch style
+    /// history request, as fine as one call allows. `None` when
+    /// there is no such pair.
+    pub fn for_history(
+        request: &CoinHistoryRequest,
+    ) -> Option<Self> {
+        let span =
+            request.end().saturating_sub(request.start());
+        Some(Self {
+            limit: Some(MAX_KLINES),
+            start_time: Some(request.start()),
+            end_time: Some(request.end()),
+            ..KlinesRequest::new(
+                usdt_pair(request.code())?,
+                KlineInterval::for_span(span),
+            )
+        })
+    }
+}
+
+impl QueryItems for KlinesRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries
+            .insert("symbol", self.symbol.to_uppercase());
+        queries.insert(
+            "interval",
+            self.interval.as_str().into(),
+        );
+        if let Some(limit) = self.limit {
+            queries.insert(
+                "limit",
+                limit.clamp(1, MAX_KLINES).to_string(),
+            );
+        }
+        if let Some(start_time) = self.start_time {
+            queries.insert(
+                "startTime",
+                start_time.to_string(),
+            );
+        }
+        if let Some(end_time) = self.end_time {
+            queries.insert("endTime", end_time.to_string());
+        }
+        queries
+    }
+}
+
+/// One entry of `/klines`, an array of open time, OHLC, volume,
+/// close time, quote volume, trades, taker buy volumes and an
+/// unused field.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Kline(
+    pub i64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    pub i64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    pub u64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub  f64,
+    pub IgnoredAny,
+);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Candle {
+    pub open_time: i64,
+    pub close_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub quote_volume: f64,
+    pub trades: u64,
+}
+
+impl From<Kline> for Candle {
+    fn from(kline: Kline) -> Self {
+        Self {
+            open_time: kline.0,
+            open: kline.1,
+            high: kline.2,
+            low: kline.3,
+            close: kline.4,
+            volume: kline.5,
+            close_time: kline.6,
+            quote_volume: kline.7,
+            trades: kline.8,
+        }
+    }
+}
+
+/// History of `code` with a point per candle, at its open time
+/// with its closing price and USD volume.
+pub fn candles_into_history(
+    code: &str,
+    candles: Vec<Candle>,
+) -> CoinHistory {
+    CoinHistory {
+        code: Some(code.to_uppercase()),
+        name: None,
+        symbol: Some(code.to_uppercase()),
+        rank: None,
+        color: None,
+        png64: None,
+        webp64: None,
+        all_time_high_usd: None,
+        links: None,
+        history: Some(
+            candles
+                .into_iter()
+                .map(|candle| History {
+                    date: Some(candle.open_time),
+                    rate: Some(candle.close),
+                    volume: Some(
+                        candle.quote_volume as i64,
+                    ),
+                    cap: None,
+                    liquidity: None,
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Query of `/depth`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct DepthRequest {
+    pub symbol: String,
+    pub limit: Option<u32>,
+}
+
+impl DepthRequest {
+    pub fn new(symbol: String, limit: Option<u32>) -> Self {
+        Self { symbol, limit }
+    }
+}
+
+impl QueryItems for DepthRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries
+            .insert("symbol", self.symbol.to_uppercase());
+        if let Some(limit) = self.limit {
+            queries.insert(
+                "limit",
+                limit.clamp(1, MAX_DEPTH).to_string(),
+            );
+        }
+        queries
+    }
+}
+
+/// A `[price, quantity]` pair of `/depth`.
+#[derive(Deserialize)]
+struct RawLevel(
+    #[serde(deserialize_with = "number_from_string")] f64,
+    #[serde(deserialize_with = "number_from_string")] f64,
+);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(from = "RawLevel")]
+pub struct OrderBookLevel {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+impl From<RawLevel> for OrderBookLevel {
+    fn from(RawLevel(price, quantity): RawLevel) -> Self {
+        Self { price, quantity }
+    }
+}
+
+/// Bids from the highest price down, asks from the lowest up.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBook {
+    pub last_update_id: u64,
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+}
+
+impl OrderBook {
+    pub fn best_bid(&self) -> Option<OrderBookLevel> {
+        self.bids.first().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<OrderBookLevel> {
+        self.asks.first().copied()
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(
+            self.best_ask()?.price - self.best_bid()?.price,
+        )
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        Some(
+            (self.best_ask()?.price
+                + self.best_bid()?.price)
+                / 2.0,
+        )
+    }
+}
+
+/// Query of `/exchangeInfo`, all pairs when `symbol` is left out.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct ExchangeInfoRequest {
+    pub symbol: Option<String>,
+}
+
+impl QueryItems for ExchangeInfoRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        if let Some(symbol) = &self.symbol {
+            queries.insert("symbol", symbol.to_uppercase());
+        }
+        queries
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeInfo {
+    pub timezone: String,
+    pub server_time: i64,
+    pub symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub status: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub base_asset_precision: u32,
+    pub quote_asset_precision: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKER: &str = r#"{
+        "symbol": "BNBBTC",
+        "priceChange": "-94.99999800",
+        "priceChangePercent": "-95.960",
+        "weightedAvgPrice": "0.29628482",
+        "prevClosePrice": "0.10002000",
+        "lastPrice": "4.00000200",
+        "lastQty": "200.00000000",
+        "bidPrice": "4.00000000",
+        "bidQty": "100.00000000",
+        "askPrice": "4.00000200",
+        "askQty": "100.00000000",
+        "openPrice": "99.00000000",
+        "highPrice": "100.00000000",
+        "lowPrice": "0.10000000",
+        "volume": "8913.30000000",
+        "quoteVolume": "15.30000000",
+        "openTime": 1499783499040,
+        "closeTime": 1499869899040,
+        "firstId": 28385,
+        "lastId": 28460,
+        "count": 76
+    }"#;
+
+    const KLINES: &str = r#"[[
+        1499040000000,
+        "0.01634790",
+        "0.80000000",
+        "0.01575800",
+        "0.01577100",
+        "148976.11427815",
+        1499644799999,
+        "2434.19055334",
+        308,
+        "1756.87402397",
+        "28.46694368",
+        "0"
+    ]]"#;
+
+    const DEPTH: &str = r#"{
+        "lastUpdateId": 1027024,
+        "bids": [["4.00000000", "431.00000000"], ["3.99000000", "12.00000000"]],
+        "asks": [["4.00000200", "12.00000000"]]
+    }"#;
+
+    const EXCHANGE_INFO: &str = r#"{
+        "timezone": "UTC",
+        "serverTime": 1565246363776,
+        "rateLimits": [],
+        "exchangeFilters": [],
+        "symbols": [{
+            "symbol": "ETHBTC",
+            "status": "TRADING",
+            "baseAsset": "ETH",
+            "baseAssetPrecision": 8,
+            "quoteAsset": "BTC",
+            "quotePrecision": 8,
+            "quoteAssetPrecision": 8,
+            "orderTypes": ["LIMIT", "MARKET"],
+            "filters": []
+        }]
+    }"#;
+
+    #[test]
+    fn deserialize_ticker() {
+        let ticker: Ticker =
+            serde_json::from_str(TICKER).unwrap();
+        assert_eq!(ticker.last_price, 4.000002);
+        assert_eq!(ticker.price_change_percent, -95.96);
+        assert_eq!(ticker.count, 76);
+    }
+
+    #[test]
+    fn ticker_serializes_numbers() {
+        let ticker: Ticker =
+            serde_json::from_str(TICKER).unwrap();
+        let value = serde_json::to_value(&ticker).unwrap();
+        assert_eq!(value["lastPrice"], 4.000002);
+        assert_eq!(
+            serde_json::from_value::<Ticker>(value)
+                .unwrap(),
+            ticker
+        );
+    }
+
+    #[test]
+    fn kline_into_candle() {
+        let klines: Vec<Kline> =
+            serde_json::from_str(KLINES).unwrap();
+        let candle = Candle::from(klines[0].clone());
+        assert_eq!(candle.open_time, 1499040000000);
+        assert_eq!(candle.close_time, 1499644799999);
+        assert_eq!(candle.open, 0.0163479);
+        assert_eq!(candle.high, 0.8);
+        assert_eq!(candle.trades, 308);
+    }
+
+    #[test]
+    fn klines_queries_use_binance_names() {
+        let request = KlinesRequest {
+            limit: Some(5000),
+            start_time: Some(1),
+            ..KlinesRequest::new(
+                "btcusdt".into(),
+                KlineInterval::OneMonth,
+            )
+        };
+        let queries = request.get_all_queries();
+        assert_eq!(queries["symbol"], "BTCUSDT");
+        assert_eq!(queries["interval"], "1M");
+        assert_eq!(queries["limit"], "1000");
+        assert_eq!(queries["startTime"], "1");
+        assert!(!queries.contains_key("endTime"));
+    }
+
+    #[test]
+    fn interval_from_query() {
+        assert_eq!(
+            serde_json::from_str::<KlineInterval>(
+                r#""15m""#
+            )
+            .unwrap(),
+            KlineInterval::FifteenMinutes
+        );
+    }
+
+    #[test]
+    fn deserialize_order_book() {
+        let order_book: OrderBook =
+            serde_json::from_str(DEPTH).unwrap();
+        assert_eq!(order_book.bids.len(), 2);
+        assert_eq!(
+            order_book.best_bid(),
+            Some(OrderBookLevel {
+                price: 4.0,
+                quantity: 431.0
+            })
+        );
+        assert!(
+            (order_book.spread().unwrap() - 0.000002).abs()
+                < 1e-12
+        );
+        assert_eq!(order_book.mid_price(), Some(4.000001));
+    }
+
+    #[test]
+    fn empty_order_book_has_no_spread() {
+        let order_book = OrderBook {
+            last_update_id: 1,
+            bids: vec![],
+            asks: vec![],
+        };
+        assert_eq!(order_book.spread(), None);
+    }
+
+    #[test]
+    fn deserialize_exchange_info() {
+        let info: ExchangeInfo =
+            serde_json::from_str(EXCHANGE_INFO).unwrap();
+        assert_eq!(
+            info.symbols[0],
+            SymbolInfo {
+                symbol: "ETHBTC".into(),
+                status: "TRADING".into(),
+                base_asset: "ETH".into(),
+                quote_asset: "BTC".into(),
+                base_asset_precision: 8,
+                quote_asset_precision: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn ticker_as_quote_and_meta() {
+        let ticker: Ticker =
+            serde_json::from_str(TICKER).unwrap();
+        let quote = ticker.quote("bnb");
+        assert_eq!(quote.code, "BNB");
+        assert_eq!(quote.rate, 4.000002);
+        assert_eq!(quote.time, 1499869899040);
+        assert_eq!(quote.source, DataSource::Binance);
+
+        let meta = ticker.coin_meta("bnb");
+        assert_eq!(meta.code, Some("BNB".into()));
+        let day = meta.delta.unwrap().day.unwrap();
+        assert!((day - 0.0404).abs() < 1e-9);
+    }
+
+    #[test]
+    fn history_request_into_klines() {
+        let day = 86_400_000;
+        let request = KlinesRequest::for_history(
+            &CoinHistoryRequest::new(
+                "eth".into(),
+                day,
+                2 * day,
+                false,
+            ),
+        )
+        .unwrap();
+        assert_eq!(request.symbol, "ETHUSDT");
+        assert_eq!(
+            request.interval,
+            KlineInterval::FiveMinutes
+        );
+        assert_eq!(request.end_time, Some(2 * day));
+        assert_eq!(
+            KlineInterval::for_span(10 * 365 * day),
+            KlineInterval::OneWeek
+        );
+        assert!(KlinesRequest::for_history(
+            &CoinHistoryRequest::new(
+                "usdt".into(),
+                day,
+                2 * day,
+                false
+            )
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn candles_into_coin_history() {
+        let klines: Vec<Kline> =
+            serde_json::from_str(KLINES).unwrap();
+        let history = candles_into_history(
+            "btc",
+            klines.into_iter().map(Candle::from).collect(),
+        );
+        let points = history.history.unwrap();
+        assert_eq!(history.code, Some("BTC".into()));
+        assert_eq!(points[0].date, Some(1499040000000));
+        assert_eq!(points[0].rate, Some(0.015771));
+        assert_eq!(points[0].volume, Some(2434));
+    }
+}
@@ -1,7 +1,36 @@
 use core::fmt::Debug;
 use std::collections::HashMap;
 
-pub trait Asset: Send {}
+use crate::asset::{
+    AssetClass, AssetSummary, ChangePercentages,
+};
+
+/// A stock or a coin, priced in USD.
+pub trait Asset: Send {
+    fn symbol(&self) -> String;
+    fn name(&self) -> Option<String>;
+    fn asset_class(&self) -> AssetClass;
+    /// Latest price in USD.
+    fn quote(&self) -> Option<f64>;
+    fn change(&self) -> ChangePercentages;
+
+    /// Canonical id, see [`crate::asset_registry`]. Tickers and
+    /// Live Coin Watch codes are already canonical.
+    fn id(&self) -> String {
+        self.symbol().to_uppercase()
+    }
+
+    fn summary(&self) -> AssetSummary {
+        AssetSummary {
+            id: self.id(),
+            symbol: self.symbol().to_uppercase(),
+            name: self.name(),
+            asset_class: self.asset_class(),
+            quote: self.quote(),
+            change: self.change(),
+        }
+    }
+}
 
 pub trait Client: Debug {
     fn get_base_url(&self) -> String;
@@ -70,62 +99,4 @@ mod tests {
             "banan"
         )
     }
-
-    // #[test]
-    // fn get_base_url_binance_client() {
-    //     std::env::set_var("BINANCE_API_KEY", "TEST");
-
-    //     let binance_client = BinanceClient::new();
-    //     assert_eq!(
-    //         binance_client.get_base_url(),
-    //         String::from_str("https://api.binance.com/api/v3/").unwrap()
-    //     )
-    // }
-
-    // #[test]
-    // fn get_headers_binance_client() {
-    //     std::env::set_var("BINANCE_API_KEY", "TEST");
-
-    //     let binance_client = BinanceClient::new();
-
-    //     assert!(binance_client.get_headers().contains_key("X-MBX-APIKEY"));
-    //     assert!(binance_client.get_headers().contains_key("Content-Type"));
-    //     assert_eq!(
-    //         binance_client.get_headers().get("Content-Type").unwrap(),
-    //         "application/x-www-form-urlencoded"
-    //     );
-    //     assert_eq!(
-    //         binance_client.get_headers().get("X-MBX-APIKEY").unwrap(),
-    //         "TEST"
-    //     )
-    // }
-
-    // #[test]
-    // fn get_base_url_coinapi_client() {
-    //     std::env::set_var("COINAPI_API_KEY", "TEST");
-
-    //     let binance_client = CoinApiClient::new();
-    //     assert_eq!(
-    //         binance_client.get_base_url(),
-    //         String::from_str("https://rest.coinapi.io/v1/").unwrap()
-    //     )
-    // }
-
-    // #[test]
-    // fn get_headers_coinapi_client() {
-    //     std::env::set_var("COINAPI_API_KEY", "TEST");
-
-    //     let binance_client = CoinApiClient::new();
-
-    //     assert!(binance_client.get_headers().contains_key("X-CoinAPI-Key"));
-    //     assert!(binance_client.get_headers().contains_key("ACCEPT"));
-    //     assert_eq!(
-    //         binance_client.get_headers().get("ACCEPT").unwrap(),
-    //         "application/json"
-    //     );
-    //     assert_eq!(
-    //         binance_client.get_headers().get("X-CoinAPI-Key").unwrap(),
-    //         "TEST"
-    //     )
-    // }
 }
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use uniffi::{Enum, Record};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Record)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ListOfCoinsRequest {
     currency: String,
     sort: String,
@@ -14,6 +15,7 @@ pub struct ListOfCoinsRequest {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Record)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CoinHistoryRequest {
     currency: String,
     code: String,
@@ -37,9 +39,61 @@ impl CoinHistoryRequest {
             meta,
         }
     }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn meta(&self) -> bool {
+        self.meta
+    }
+}
+
+/// Request for `/coins/map`, which returns a [`Coin`] for each of
+/// the given `codes`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record)]
+pub struct CoinMapRequest {
+    currency: String,
+    codes: Vec<String>,
+    sort: String,
+    order: String,
+    offset: u32,
+    limit: u32,
+    meta: bool,
+}
+
+impl CoinMapRequest {
+    pub fn new(codes: Vec<String>) -> Self {
+        Self {
+            currency: "USD".into(),
+            limit: codes.len() as u32,
+            codes,
+            sort: "rank".into(),
+            order: "ascending".into(),
+            offset: 0,
+            meta: false,
+        }
+    }
+}
+
+/// Response of `/credits`, which doesn't cost any credits itself.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Credits {
+    pub daily_credits_remaining: u64,
+    pub daily_credits_limit: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Enum)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Enum, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum Sort {
     Rank,
@@ -50,6 +104,36 @@ pub enum Sort {
     Age,
 }
 
+impl Sort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sort::Rank => "rank",
+            Sort::Price => "price",
+            Sort::Volume => "volume",
+            Sort::Code => "code",
+            Sort::Name => "name",
+            Sort::Age => "age",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Enum, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    Ascending,
+    Descending,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::Ascending => "ascending",
+            Order::Descending => "descending",
+        }
+    }
+}
+
 impl ListOfCoinsRequest {
     pub fn new(limit: u32) -> Self {
         Self {
@@ -61,9 +145,37 @@ impl ListOfCoinsRequest {
             meta: true,
         }
     }
+
+    pub fn sorted(mut self, sort: Sort, order: Order) -> Self {
+        self.sort = sort.as_str().into();
+        self.order = order.as_str().into();
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u8) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    pub fn offset(&self) -> u8 {
+        self.offset
+    }
+
+    pub fn sort(&self) -> &str {
+        &self.sort
+    }
+
+    pub fn order(&self) -> &str {
+        &self.order
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Record)]
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Coin {
     pub code: Option<String>,
     pub rate: Option<f64>,
@@ -72,7 +184,8 @@ pub struct Coin {
     pub delta: Delta,
 }
 
-#[derive(Debug, Serialize, Deserialize, Record, Clone)]
+#[derive(Debug, Serialize, Deserialize, Record, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Delta {
     pub hour: Option<f64>,
     pub day: Option<f64>,
@@ -121,6 +234,7 @@ impl Coin {
 }
 
 #[derive(Debug, Serialize, Deserialize, Record)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CoinMetaRequest {
     pub currency: String,
     pub code: String,
@@ -138,6 +252,7 @@ impl CoinMetaRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Record)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CoinMeta {
     pub name: Option<String>,
@@ -154,6 +269,7 @@ pub struct CoinMeta {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Record)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CoinHistory {
     pub code: Option<String>,
@@ -169,7 +285,8 @@ pub struct CoinHistory {
     pub history: Option<Vec<History>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Record)]
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct History {
     pub date: Option<i64>,
     pub rate: Option<f64>,
@@ -178,13 +295,15 @@ pub struct History {
     pub liquidity: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Record)]
+#[derive(Debug, Serialize, Deserialize, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Links {
     pub website: Option<String>,
     pub whitepaper: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Record)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct AggregatedCoinInformation {
     pub name: String,
     pub symbol: String,
@@ -216,8 +335,8 @@ impl AggregatedCoinInformation {
 mod tests {
     use crate::coin_watch_service::models::{
         AggregatedCoinInformation, Coin,
-        CoinHistoryRequest, CoinMetaRequest, Delta,
-        ListOfCoinsRequest,
+        CoinHistoryRequest, CoinMapRequest, CoinMetaRequest,
+        Credits, Delta, ListOfCoinsRequest, Order, Sort,
     };
 
     #[test]
@@ -234,6 +353,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn new_coin_map_request_limit_covers_codes() {
+        assert_eq!(
+            CoinMapRequest::new(vec![
+                "BTC".into(),
+                "ETH".into()
+            ])
+            .limit,
+            2
+        );
+    }
+
+    #[test]
+    fn deserialize_credits() {
+        assert_eq!(
+            serde_json::from_str::<Credits>(
+                r#"{"dailyCreditsRemaining":9997,"dailyCreditsLimit":10000}"#
+            )
+            .unwrap(),
+            Credits {
+                daily_credits_remaining: 9997,
+                daily_credits_limit: 10000,
+            }
+        );
+    }
+
     #[test]
     fn new_list_of_coins_request_limit() {
         assert_eq!(ListOfCoinsRequest::new(15).limit, 15);
@@ -263,6 +408,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sorted_list_of_coins_request() {
+        let request = ListOfCoinsRequest::new(15)
+            .sorted(Sort::Volume, Order::Descending)
+            .with_offset(30);
+        assert_eq!(
+            (
+                request.sort.as_str(),
+                request.order.as_str(),
+                request.offset
+            ),
+            ("volume", "descending", 30)
+        );
+    }
+
     #[test]
     fn new_delta_hour() {
         assert_eq!(
@@ -0,0 +1,103 @@
+use core::fmt::Debug;
+use std::{collections::HashMap, env};
+
+use crate::client_trait::Client;
+
+pub const BASE_URL: &str = "https://rest.coinapi.io/v1";
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct CoinApiClient {
+    pub headers: HashMap<String, String>,
+    pub base_url: String,
+}
+
+impl Default for CoinApiClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CoinApiClient {
+    /// Reads `COINAPI_API_KEY`. Without it the client is built
+    /// anyway, see [`CoinApiClient::has_key`].
+    pub fn new() -> Self {
+        Self::new_with_key(
+            env::var("COINAPI_API_KEY").unwrap_or_default(),
+        )
+    }
+
+    pub fn new_with_key(key: String) -> Self {
+        let mut headers = HashMap::new();
+        headers.insert(
+            "accept".to_string(),
+            "application/json".to_string(),
+        );
+        if !key.is_empty() {
+            headers
+                .insert("x-coinapi-key".to_string(), key);
+        }
+        Self {
+            headers,
+            base_url: BASE_URL.to_string(),
+        }
+    }
+
+    /// CoinAPI refuses every call without a key.
+    pub fn has_key(&self) -> bool {
+        self.headers.contains_key("x-coinapi-key")
+    }
+}
+
+impl Client for CoinApiClient {
+    fn get_base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn get_headers(&self) -> HashMap<String, String> {
+        self.headers.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_base_url_coinapi_client() {
+        assert_eq!(
+            CoinApiClient::new_with_key("TEST".into())
+                .get_base_url(),
+            "https://rest.coinapi.io/v1"
+        )
+    }
+
+    #[test]
+    fn get_headers_coinapi_client() {
+        let coinapi_client =
+            CoinApiClient::new_with_key("TEST".into());
+
+        assert_eq!(
+            coinapi_client
+                .get_headers()
+                .get("accept")
+                .unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            coinapi_client
+                .get_headers()
+                .get("x-coinapi-key")
+                .unwrap(),
+            "TEST"
+        );
+        assert!(coinapi_client.has_key());
+    }
+
+    #[test]
+    fn no_key_without_env() {
+        assert!(
+            !CoinApiClient::new_with_key(String::new())
+                .has_key()
+        );
+    }
+}
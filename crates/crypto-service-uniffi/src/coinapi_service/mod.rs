@@ -0,0 +1,2 @@
+pub mod coinapi_client;
+pub mod models;
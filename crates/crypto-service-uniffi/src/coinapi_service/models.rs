@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+use uniffi::Record;
+
+use crate::{
+    client_trait::QueryItems,
+    coin_watch_service::models::CoinMeta,
+    data_source::{DataSource, Quote},
+};
+
+/// CoinAPI flags crypto assets with `1` and fiat with `0`.
+fn bool_from_int<'de, D>(
+    deserializer: D,
+) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum IntOrBool {
+        Int(u8),
+        Bool(bool),
+    }
+    Ok(match IntOrBool::deserialize(deserializer)? {
+        IntOrBool::Int(int) => int != 0,
+        IntOrBool::Bool(bool) => bool,
+    })
+}
+
+/// Query of `/assets`, every asset when `filter_asset_id` is left
+/// out.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct AssetsRequest {
+    /// Comma separated asset ids such as `BTC,ETH`.
+    pub filter_asset_id: Option<String>,
+}
+
+impl QueryItems for AssetsRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        if let Some(filter) = &self.filter_asset_id {
+            queries.insert(
+                "filter_asset_id",
+                filter.to_uppercase(),
+            );
+        }
+        queries
+    }
+}
+
+/// One entry of `/assets`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CoinApiAsset {
+    pub asset_id: String,
+    pub name: Option<String>,
+    #[serde(deserialize_with = "bool_from_int")]
+    pub type_is_crypto: bool,
+    pub data_start: Option<String>,
+    pub data_end: Option<String>,
+    pub data_symbols_count: Option<u64>,
+    pub volume_1day_usd: Option<f64>,
+    pub price_usd: Option<f64>,
+    pub id_icon: Option<String>,
+}
+
+/// Query of `/symbols`. Filters are comma separated and combine.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct SymbolsRequest {
+    pub filter_symbol_id: Option<String>,
+    pub filter_exchange_id: Option<String>,
+    pub filter_asset_id: Option<String>,
+}
+
+impl QueryItems for SymbolsRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        for (name, filter) in [
+            ("filter_symbol_id", &self.filter_symbol_id),
+            (
+                "filter_exchange_id",
+                &self.filter_exchange_id,
+            ),
+            ("filter_asset_id", &self.filter_asset_id),
+        ] {
+            if let Some(filter) = filter {
+                queries.insert(name, filter.to_uppercase());
+            }
+        }
+        queries
+    }
+}
+
+/// One entry of `/symbols`, a market such as
+/// `BINANCE_SPOT_BTC_USDT`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CoinApiSymbol {
+    pub symbol_id: String,
+    pub exchange_id: String,
+    pub symbol_type: String,
+    pub asset_id_base: Option<String>,
+    pub asset_id_quote: Option<String>,
+    pub data_start: Option<String>,
+    pub data_end: Option<String>,
+    pub volume_1day_usd: Option<f64>,
+    pub price: Option<f64>,
+}
+
+impl From<CoinApiAsset> for CoinMeta {
+    fn from(asset: CoinApiAsset) -> Self {
+        CoinMeta {
+            name: asset.name,
+            symbol: Some(asset.asset_id.clone()),
+            rank: None,
+            color: None,
+            png64: None,
+            webp64: None,
+            all_time_high_usd: None,
+            code: Some(asset.asset_id),
+            rate: asset.price_usd,
+            delta: None,
+        }
+    }
+}
+
+/// Query of `/exchangerate/{base}/{quote}`, the current rate when
+/// `time` is left out.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct ExchangeRateRequest {
+    /// ISO 8601 time of a historical rate.
+    pub time: Option<String>,
+}
+
+impl QueryItems for ExchangeRateRequest {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        if let Some(time) = &self.time {
+            queries.insert("time", time.clone());
+        }
+        queries
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExchangeRate {
+    pub time: String,
+    pub asset_id_base: String,
+    pub asset_id_quote: String,
+    pub rate: f64,
+}
+
+impl ExchangeRate {
+    /// The rate as a [`Quote`] of its base, `None` when `time`
+    /// isn't RFC 3339.
+    pub fn quote(&self) -> Option<Quote> {
+        let time = DateTime::parse_from_rfc3339(&self.time)
+            .ok()?
+            .timestamp_millis();
+        Some(Quote {
+            code: self.asset_id_base.to_uppercase(),
+            rate: self.rate,
+            time,
+            source: DataSource::CoinApi,
+        })
+    }
+}
+
+/// One entry of `/assets/icons/{size}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AssetIcon {
+    pub asset_id: String,
+    pub url: String,
+}
+
+/// Icons of the assets in `filter`, comma separated, or all of
+/// them when it's `None`. CoinAPI can't filter icons itself.
+pub fn filter_icons(
+    icons: Vec<AssetIcon>,
+    filter: Option<&str>,
+) -> Vec<AssetIcon> {
+    let Some(filter) = filter else {
+        return icons;
+    };
+    let wanted: Vec<String> = filter
+        .split(',')
+        .map(|id| id.trim().to_uppercase())
+        .collect();
+    icons
+        .into_iter()
+        .filter(|icon| {
+            wanted.contains(&icon.asset_id.to_uppercase())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSETS: &str = r#"[{
+        "asset_id": "BTC",
+        "name": "Bitcoin",
+        "type_is_crypto": 1,
+        "data_quote_start": "2014-02-24T17:43:05.0000000Z",
+        "data_start": "2010-07-17",
+        "data_end": "2024-03-27",
+        "data_symbols_count": 87540,
+        "volume_1hrs_usd": 1.1e9,
+        "volume_1day_usd": 2.5e10,
+        "price_usd": 69512.3,
+        "id_icon": "4caf2b16-a017-4e26-a348-2cea69c34cba"
+    }, {
+        "asset_id": "USD",
+        "name": "US Dollar",
+        "type_is_crypto": 0
+    }]"#;
+
+    const SYMBOLS: &str = r#"[{
+        "symbol_id": "BINANCE_SPOT_BTC_USDT",
+        "exchange_id": "BINANCE",
+        "symbol_type": "SPOT",
+        "asset_id_base": "BTC",
+        "asset_id_quote": "USDT",
+        "data_start": "2017-08-17",
+        "volume_1day_usd": 1.2e9,
+        "price": 69500.1
+    }]"#;
+
+    #[test]
+    fn deserialize_assets() {
+        let assets: Vec<CoinApiAsset> =
+            serde_json::from_str(ASSETS).unwrap();
+        assert!(assets[0].type_is_crypto);
+        assert_eq!(assets[0].price_usd, Some(69512.3));
+        assert!(!assets[1].type_is_crypto);
+        assert_eq!(assets[1].price_usd, None);
+    }
+
+    #[test]
+    fn asset_round_trips() {
+        let assets: Vec<CoinApiAsset> =
+            serde_json::from_str(ASSETS).unwrap();
+        let value =
+            serde_json::to_value(&assets[0]).unwrap();
+        assert_eq!(value["type_is_crypto"], true);
+        assert_eq!(
+            serde_json::from_value::<CoinApiAsset>(value)
+                .unwrap(),
+            assets[0]
+        );
+    }
+
+    #[test]
+    fn deserialize_symbols() {
+        let symbols: Vec<CoinApiSymbol> =
+            serde_json::from_str(SYMBOLS).unwrap();
+        assert_eq!(
+            symbols[0].asset_id_base,
+            Some("BTC".into())
+        );
+        assert_eq!(symbols[0].data_end, None);
+    }
+
+    #[test]
+    fn symbols_queries_skip_missing_filters() {
+        let request = SymbolsRequest {
+            filter_exchange_id: Some("binance".into()),
+            ..Default::default()
+        };
+        let queries = request.get_all_queries();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(
+            queries["filter_exchange_id"],
+            "BINANCE"
+        );
+    }
+
+    #[test]
+    fn filter_icons_by_asset_id() {
+        let icon = |asset_id: &str| AssetIcon {
+            asset_id: asset_id.into(),
+            url: format!(
+                "https://example.com/{asset_id}.png"
+            ),
+        };
+        let icons =
+            vec![icon("BTC"), icon("ETH"), icon("DOGE")];
+        assert_eq!(
+            filter_icons(icons.clone(), Some("eth, btc")),
+            vec![icon("BTC"), icon("ETH")]
+        );
+        assert_eq!(
+            filter_icons(icons.clone(), None),
+            icons
+        );
+    }
+
+    #[test]
+    fn asset_into_coin_meta() {
+        let assets: Vec<CoinApiAsset> =
+            serde_json::from_str(ASSETS).unwrap();
+        let meta = CoinMeta::from(assets[0].clone());
+        assert_eq!(meta.code, Some("BTC".into()));
+        assert_eq!(meta.name, Some("Bitcoin".into()));
+        assert_eq!(meta.rate, Some(69512.3));
+    }
+
+    #[test]
+    fn exchange_rate_as_quote() {
+        let rate = ExchangeRate {
+            time: "2024-03-28T12:00:00.0000000Z".into(),
+            asset_id_base: "btc".into(),
+            asset_id_quote: "USD".into(),
+            rate: 69512.3,
+        };
+        let quote = rate.quote().unwrap();
+        assert_eq!(quote.code, "BTC");
+        assert_eq!(quote.time, 1_711_627_200_000);
+        assert_eq!(quote.source, DataSource::CoinApi);
+        assert!(ExchangeRate {
+            time: "yesterday".into(),
+            ..rate
+        }
+        .quote()
+        .is_none());
+    }
+}
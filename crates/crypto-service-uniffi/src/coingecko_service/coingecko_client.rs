@@ -0,0 +1,172 @@
+use core::fmt::Debug;
+use std::{collections::HashMap, env};
+
+use serde::{Deserialize, Serialize};
+use uniffi::Enum;
+
+use crate::client_trait::Client;
+
+pub const PUBLIC_BASE_URL: &str =
+    "https://api.coingecko.com/api/v3";
+pub const PRO_BASE_URL: &str =
+    "https://pro-api.coingecko.com/api/v3";
+
+/// Which CoinGecko API a key belongs to. Public also covers demo
+/// keys and the keyless API.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Enum, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CoinGeckoPlan {
+    #[default]
+    Public,
+    Pro,
+}
+
+impl CoinGeckoPlan {
+    pub fn parse(plan: &str) -> Option<Self> {
+        match plan.to_lowercase().as_str() {
+            "public" | "demo" => {
+                Some(CoinGeckoPlan::Public)
+            }
+            "pro" => Some(CoinGeckoPlan::Pro),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct CoinGeckoClient {
+    pub headers: HashMap<String, String>,
+    pub base_url: String,
+    pub plan: CoinGeckoPlan,
+}
+
+impl Default for CoinGeckoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CoinGeckoClient {
+    /// Reads `COINGECKO_API_KEY` and `COINGECKO_PLAN`, falling back
+    /// to the keyless public API.
+    pub fn new() -> Self {
+        Self::new_with_key(
+            env::var("COINGECKO_API_KEY")
+                .unwrap_or_default(),
+            env::var("COINGECKO_PLAN")
+                .ok()
+                .and_then(|plan| {
+                    CoinGeckoPlan::parse(&plan)
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    /// An empty `key` on [`CoinGeckoPlan::Public`] uses the keyless
+    /// API.
+    pub fn new_with_key(
+        key: String,
+        plan: CoinGeckoPlan,
+    ) -> Self {
+        let mut headers = HashMap::new();
+        headers.insert(
+            "accept".to_string(),
+            "application/json".to_string(),
+        );
+        let base_url = match plan {
+            CoinGeckoPlan::Public => {
+                if !key.is_empty() {
+                    headers.insert(
+                        "x-cg-demo-api-key".to_string(),
+                        key,
+                    );
+                }
+                PUBLIC_BASE_URL
+            }
+            CoinGeckoPlan::Pro => {
+                headers.insert(
+                    "x-cg-pro-api-key".to_string(),
+                    key,
+                );
+                PRO_BASE_URL
+            }
+        };
+        Self {
+            headers,
+            base_url: base_url.to_string(),
+            plan,
+        }
+    }
+}
+
+impl Client for CoinGeckoClient {
+    fn get_base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn get_headers(&self) -> HashMap<String, String> {
+        self.headers.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyless_public_client() {
+        let client = CoinGeckoClient::new_with_key(
+            String::new(),
+            CoinGeckoPlan::Public,
+        );
+        assert_eq!(client.get_base_url(), PUBLIC_BASE_URL);
+        assert_eq!(client.get_headers().len(), 1);
+    }
+
+    #[test]
+    fn demo_key_on_public_api() {
+        let client = CoinGeckoClient::new_with_key(
+            "CG-demo".into(),
+            CoinGeckoPlan::Public,
+        );
+        assert_eq!(
+            client
+                .get_headers()
+                .get("x-cg-demo-api-key")
+                .unwrap(),
+            "CG-demo"
+        );
+    }
+
+    #[test]
+    fn pro_key_on_pro_api() {
+        let client = CoinGeckoClient::new_with_key(
+            "CG-pro".into(),
+            CoinGeckoPlan::Pro,
+        );
+        assert_eq!(client.get_base_url(), PRO_BASE_URL);
+        assert_eq!(
+            client
+                .get_headers()
+                .get("x-cg-pro-api-key")
+                .unwrap(),
+            "CG-pro"
+        );
+    }
+
+    #[test]
+    fn parse_plan() {
+        assert_eq!(
+            CoinGeckoPlan::parse("Demo"),
+            Some(CoinGeckoPlan::Public)
+        );
+        assert_eq!(
+            CoinGeckoPlan::parse("pro"),
+            Some(CoinGeckoPlan::Pro)
+        );
+        assert_eq!(
+            CoinGeckoPlan::parse("enterprise"),
+            None
+        );
+    }
+}
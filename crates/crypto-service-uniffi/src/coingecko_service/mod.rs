@@ -0,0 +1,2 @@
+pub mod coingecko_client;
+pub mod models;
@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_registry::{self, AssetProvider},
+    client_trait::QueryItems,
+    coin_watch_service::models::{
+        Coin, CoinHistory, CoinHistoryRequest, CoinMeta,
+        Delta, History, Links, ListOfCoinsRequest,
+    },
+    data_source::{DataSource, Quote},
+};
+
+/// Most coins CoinGecko returns per page of `/coins/markets`.
+pub const MAX_PER_PAGE: u32 = 250;
+
+/// CoinGecko id for a Live Coin Watch style code such as `BTC`,
+/// `None` for a known coin CoinGecko doesn't list.
+pub fn coin_id(code: &str) -> Option<String> {
+    asset_registry::identifier(code, AssetProvider::CoinGecko)
+}
+
+/// Canonical code of the coin CoinGecko calls `id`, its symbol when
+/// the registry doesn't know it.
+fn coin_code(id: &str, symbol: &str) -> String {
+    asset_registry::canonical(AssetProvider::CoinGecko, id)
+        .unwrap_or(symbol.to_uppercase())
+}
+
+/// CoinGecko reports change in percent, Live Coin Watch as a
+/// multiplier where `1.05` is up 5%.
+fn delta_from_percent(percent: Option<f64>) -> Option<f64> {
+    percent.map(|percent| 1.0 + percent / 100.0)
+}
+
+/// Query of `/coins/markets`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MarketsQuery {
+    pub vs_currency: String,
+    pub order: String,
+    pub per_page: u32,
+    pub page: u32,
+}
+
+impl MarketsQuery {
+    pub fn new(per_page: u32) -> Self {
+        Self {
+            vs_currency: "usd".into(),
+            order: "market_cap_desc".into(),
+            per_page: per_page.clamp(1, MAX_PER_PAGE),
+            page: 1,
+        }
+    }
+}
+
+/// CoinGecko pages instead of offsetting and can only order by
+/// market cap, volume or id, so price and age fall back to rank.
+impl From<&ListOfCoinsRequest> for MarketsQuery {
+    fn from(request: &ListOfCoinsRequest) -> Self {
+        let mut query = MarketsQuery::new(request.limit());
+        let descending = request.order() == "descending";
+        query.order = match (request.sort(), descending) {
+            ("volume", false) => "volume_asc",
+            ("volume", true) => "volume_desc",
+            ("code" | "name", false) => "id_asc",
+            ("code" | "name", true) => "id_desc",
+            ("rank", true) => "market_cap_asc",
+            _ => "market_cap_desc",
+        }
+        .into();
+        query.page =
+            request.offset() as u32 / query.per_page + 1;
+        query
+    }
+}
+
+impl QueryItems for MarketsQuery {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "vs_currency",
+            self.vs_currency.clone(),
+        );
+        queries.insert("order", self.order.clone());
+        queries
+            .insert("per_page", self.per_page.to_string());
+        queries.insert("page", self.page.to_string());
+        queries.insert(
+            "price_change_percentage",
+            "1h,24h,7d,30d,1y".to_string(),
+        );
+        queries
+    }
+}
+
+/// One entry of `/coins/markets`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Market {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub image: Option<String>,
+    pub current_price: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub market_cap_rank: Option<i64>,
+    pub total_volume: Option<f64>,
+    pub ath: Option<f64>,
+    pub price_change_percentage_1h_in_currency: Option<f64>,
+    pub price_change_percentage_24h_in_currency:
+        Option<f64>,
+    pub price_change_percentage_7d_in_currency: Option<f64>,
+    pub price_change_percentage_30d_in_currency:
+        Option<f64>,
+    pub price_change_percentage_1y_in_currency: Option<f64>,
+}
+
+impl Market {
+    fn delta(&self) -> Delta {
+        Delta::new(
+            delta_from_percent(
+                self.price_change_percentage_1h_in_currency,
+            ),
+            delta_from_percent(
+                self.price_change_percentage_24h_in_currency,
+            ),
+            delta_from_percent(
+                self.price_change_percentage_7d_in_currency,
+            ),
+            delta_from_percent(
+                self.price_change_percentage_30d_in_currency,
+            ),
+            None,
+            delta_from_percent(
+                self.price_change_percentage_1y_in_currency,
+            ),
+        )
+    }
+}
+
+impl From<Market> for Coin {
+    fn from(market: Market) -> Self {
+        Coin::new(
+            Some(coin_code(&market.id, &market.symbol)),
+            market.current_price,
+            market.total_volume.map(|volume| volume as i64),
+            market.market_cap.map(|cap| cap as i64),
+            market.delta(),
+        )
+    }
+}
+
+impl From<Market> for CoinMeta {
+    fn from(market: Market) -> Self {
+        let delta = market.delta();
+        CoinMeta {
+            name: Some(market.name),
+            symbol: Some(market.symbol.to_uppercase()),
+            rank: market.market_cap_rank,
+            color: None,
+            png64: market.image,
+            webp64: None,
+            all_time_high_usd: market.ath,
+            code: Some(coin_code(&market.id, &market.symbol)),
+            rate: market.current_price,
+            delta: Some(delta),
+        }
+    }
+}
+
+/// Query of `/coins/{id}`, leaving out everything but market data.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CoinDetailQuery {}
+
+impl QueryItems for CoinDetailQuery {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        for excluded in [
+            "localization",
+            "tickers",
+            "community_data",
+            "developer_data",
+            "sparkline",
+        ] {
+            queries.insert(excluded, "false".to_string());
+        }
+        queries.insert("market_data", "true".to_string());
+        queries
+    }
+}
+
+/// Response of `/coins/{id}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CoinDetail {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub market_cap_rank: Option<i64>,
+    pub image: Option<Image>,
+    pub links: Option<CoinLinks>,
+    pub market_data: Option<MarketData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Image {
+    pub thumb: Option<String>,
+    pub small: Option<String>,
+    pub large: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CoinLinks {
+    #[serde(default)]
+    pub homepage: Vec<String>,
+    pub whitepaper: Option<String>,
+}
+
+/// Prices and changes keyed by currency, e.g. `usd`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MarketData {
+    pub current_price: HashMap<String, f64>,
+    pub ath: HashMap<String, f64>,
+    pub price_change_percentage_1h_in_currency:
+        HashMap<String, f64>,
+    pub price_change_percentage_24h_in_currency:
+        HashMap<String, f64>,
+    pub price_change_percentage_7d_in_currency:
+        HashMap<String, f64>,
+    pub price_change_percentage_30d_in_currency:
+        HashMap<String, f64>,
+    pub price_change_percentage_1y_in_currency:
+        HashMap<String, f64>,
+}
+
+impl MarketData {
+    fn usd(values: &HashMap<String, f64>) -> Option<f64> {
+        values.get("usd").copied()
+    }
+
+    fn delta(&self) -> Delta {
+        Delta::new(
+            delta_from_percent(Self::usd(
+                &self.price_change_percentage_1h_in_currency,
+            )),
+            delta_from_percent(Self::usd(
+                &self.price_change_percentage_24h_in_currency,
+            )),
+            delta_from_percent(Self::usd(
+                &self.price_change_percentage_7d_in_currency,
+            )),
+            delta_from_percent(Self::usd(
+                &self.price_change_percentage_30d_in_currency,
+            )),
+            None,
+            delta_from_percent(Self::usd(
+                &self.price_change_percentage_1y_in_currency,
+            )),
+        )
+    }
+}
+
+impl CoinDetail {
+    pub fn links(&self) -> Links {
+        let links = self.links.clone();
+        Links {
+            website: links.as_ref().and_then(|links| {
+                links
+                    .homepage
+                    .iter()
+                    .find(|url| !url.is_empty())
+                    .cloned()
+            }),
+            whitepaper: links
+                .and_then(|links| links.whitepaper)
+                .filter(|url| !url.is_empty()),
+        }
+    }
+}
+
+impl From<CoinDetail> for CoinMeta {
+    fn from(detail: CoinDetail) -> Self {
+        let market_data =
+            detail.market_data.unwrap_or_default();
+        CoinMeta {
+            name: Some(detail.name),
+            symbol: Some(detail.symbol.to_uppercase()),
+            rank: detail.market_cap_rank,
+            color: None,
+            png64: detail
+                .image
+                .and_then(|image| image.small),
+            webp64: None,
+            all_time_high_usd: MarketData::usd(
+                &market_data.ath,
+            ),
+            code: Some(coin_code(&detail.id, &detail.symbol)),
+            rate: MarketData::usd(
+                &market_data.current_price,
+            ),
+            delta: Some(market_data.delta()),
+        }
+    }
+}
+
+/// Query of `/coins/{id}/market_chart/range`, which takes seconds
+/// where Live Coin Watch takes milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MarketChartQuery {
+    pub vs_currency: String,
+    pub from: u64,
+    pub to: u64,
+}
+
+impl From<&CoinHistoryRequest> for MarketChartQuery {
+    fn from(request: &CoinHistoryRequest) -> Self {
+        Self {
+            vs_currency: "usd".into(),
+            from: request.start() / 1000,
+            to: request.end() / 1000,
+        }
+    }
+}
+
+impl QueryItems for MarketChartQuery {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert(
+            "vs_currency",
+            self.vs_currency.clone(),
+        );
+        queries.insert("from", self.from.to_string());
+        queries.insert("to", self.to.to_string());
+        queries
+    }
+}
+
+/// Response of `/coins/{id}/market_chart/range`, as
+/// `[millis, value]` pairs sharing the same timestamps.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MarketChart {
+    pub prices: Vec<[f64; 2]>,
+    pub market_caps: Vec<[f64; 2]>,
+    pub total_volumes: Vec<[f64; 2]>,
+}
+
+impl MarketChart {
+    pub fn history(&self) -> Vec<History> {
+        self.prices
+            .iter()
+            .enumerate()
+            .map(|(idx, [date, rate])| History {
+                date: Some(*date as i64),
+                rate: Some(*rate),
+                volume: self
+                    .total_volumes
+                    .get(idx)
+                    .map(|[_, volume]| *volume as i64),
+                cap: self
+                    .market_caps
+                    .get(idx)
+                    .map(|[_, cap]| *cap as i64),
+                liquidity: None,
+            })
+            .collect()
+    }
+
+    /// History of `code`, with metadata from `detail` when it was
+    /// fetched.
+    pub fn into_coin_history(
+        self,
+        code: &str,
+        detail: Option<CoinDetail>,
+    ) -> CoinHistory {
+        let history = Some(self.history());
+        let links = detail.as_ref().map(CoinDetail::links);
+        match detail.map(CoinMeta::from) {
+            Some(meta) => CoinHistory {
+                code: Some(code.to_uppercase()),
+                name: meta.name,
+                symbol: meta.symbol,
+                rank: meta.rank,
+                color: meta.color,
+                png64: meta.png64,
+                webp64: meta.webp64,
+                all_time_high_usd: meta.all_time_high_usd,
+                links,
+                history,
+            },
+            None => CoinHistory {
+                code: Some(code.to_uppercase()),
+                name: None,
+                symbol: None,
+                rank: None,
+                color: None,
+                png64: None,
+                webp64: None,
+                all_time_high_usd: None,
+                links: None,
+                history,
+            },
+        }
+    }
+}
+
+/// Query of `/simple/price` for a single coin in USD.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimplePriceQuery {
+    pub ids: String,
+}
+
+impl SimplePriceQuery {
+    /// Price of the coin with the CoinGecko id `id`, see
+    /// [`coin_id`].
+    pub fn new(id: String) -> Self {
+        Self { ids: id }
+    }
+}
+
+impl QueryItems for SimplePriceQuery {
+    type Query = String;
+
+    fn get_all_queries(
+        &self,
+    ) -> HashMap<&str, Self::Query> {
+        let mut queries = HashMap::new();
+        queries.insert("ids", self.ids.clone());
+        queries.insert("vs_currencies", "usd".to_string());
+        queries.insert(
+            "include_last_updated_at",
+            "true".to_string(),
+        );
+        queries
+    }
+}
+
+/// Price of one coin in `/simple/price`, which keys them by id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimplePrice {
+    pub usd: Option<f64>,
+    /// Seconds.
+    pub last_updated_at: Option<i64>,
+}
+
+/// Quote of `code` in a `/simple/price` response, `None` when
+/// CoinGecko doesn't know the coin or has no USD price for it.
+pub fn simple_price_quote(
+    code: &str,
+    prices: &HashMap<String, SimplePrice>,
+) -> Option<Quote> {
+    let price = prices.get(&coin_id(code)?)?;
+    Some(Quote {
+        code: code.to_uppercase(),
+        rate: price.usd?,
+        time: price.last_updated_at? * 1000,
+        source: DataSource::CoinGecko,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coin_watch_service::models::{Order, Sort};
+
+    const MARKETS: &str = r#"[{
+        "id": "bitcoin",
+        "symbol": "btc",
+        "name": "Bitcoin",
+        "image": "https://assets.coingecko.com/coins/images/1/large/bitcoin.png",
+        "current_price": 64210.5,
+        "market_cap": 1265000000000,
+        "market_cap_rank": 1,
+        "total_volume": 31000000000.4,
+        "ath": 73738,
+        "price_change_percentage_24h": 2.0,
+        "price_change_percentage_1h_in_currency": -0.5,
+        "price_change_percentage_24h_in_currency": 2.0,
+        "price_change_percentage_7d_in_currency": 10.0,
+        "price_change_percentage_30d_in_currency": null
+    }]"#;
+
+    const DETAIL: &str = r#"{
+        "id": "bitcoin",
+        "symbol": "btc",
+        "name": "Bitcoin",
+        "market_cap_rank": 1,
+        "image": {
+            "thumb": "https://example.com/thumb.png",
+            "small": "https://example.com/small.png",
+            "large": "https://example.com/large.png"
+        },
+        "links": {
+            "homepage": ["http://www.bitcoin.org", "", ""],
+            "whitepaper": "https://bitcoin.org/bitcoin.pdf"
+        },
+        "market_data": {
+            "current_price": { "usd": 64210.5, "eur": 59000.1 },
+            "ath": { "usd": 73738 },
+            "price_change_percentage_24h_in_currency": { "usd": -1.0 }
+        }
+    }"#;
+
+    const CHART: &str = r#"{
+        "prices": [[1711929600000, 70000.5], [1712016000000, 71000.0]],
+        "market_caps": [[1711929600000, 1.3e12], [1712016000000, 1.4e12]],
+        "total_volumes": [[1711929600000, 2.5e10]]
+    }"#;
+
+    #[test]
+    fn coin_id_known_and_fallback() {
+        assert_eq!(coin_id("btc"), Some("bitcoin".into()));
+        assert_eq!(coin_id("AVAX"), Some("avalanche-2".into()));
+        assert_eq!(coin_id("PEPE"), Some("pepe".into()));
+    }
+
+    #[test]
+    fn markets_query_from_list_of_coins_request() {
+        let query = MarketsQuery::from(
+            &ListOfCoinsRequest::new(50)
+                .sorted(Sort::Volume, Order::Descending)
+                .with_offset(100),
+        );
+        assert_eq!(query.order, "volume_desc");
+        assert_eq!(query.per_page, 50);
+        assert_eq!(query.page, 3);
+    }
+
+    #[test]
+    fn markets_query_falls_back_to_market_cap() {
+        let query = MarketsQuery::from(
+            &ListOfCoinsRequest::new(10)
+                .sorted(Sort::Price, Order::Descending),
+        );
+        assert_eq!(query.order, "market_cap_desc");
+    }
+
+    #[test]
+    fn market_into_coin() {
+        let markets: Vec<Market> =
+            serde_json::from_str(MARKETS).unwrap();
+        let coin = Coin::from(markets[0].clone());
+        assert_eq!(coin.code, Some("BTC".into()));
+        assert_eq!(coin.rate, Some(64210.5));
+        assert_eq!(coin.volume, Some(31000000000));
+        assert_eq!(coin.delta.day, Some(1.02));
+        assert_eq!(coin.delta.week, Some(1.1));
+        assert_eq!(coin.delta.month, None);
+    }
+
+    #[test]
+    fn market_into_coin_meta() {
+        let markets: Vec<Market> =
+            serde_json::from_str(MARKETS).unwrap();
+        let meta = CoinMeta::from(markets[0].clone());
+        assert_eq!(meta.name, Some("Bitcoin".into()));
+        assert_eq!(meta.rank, Some(1));
+        assert_eq!(meta.all_time_high_usd, Some(73738.0));
+    }
+
+    #[test]
+    fn coin_detail_into_coin_meta() {
+        let detail: CoinDetail =
+            serde_json::from_str(DETAIL).unwrap();
+        assert_eq!(
+            detail.links(),
+            Links {
+                website: Some(
+                    "http://www.bitcoin.org".into()
+                ),
+                whitepaper: Some(
+                    "https://bitcoin.org/bitcoin.pdf"
+                        .into()
+                ),
+            }
+        );
+        let meta = CoinMeta::from(detail);
+        assert_eq!(meta.code, Some("BTC".into()));
+        assert_eq!(meta.rate, Some(64210.5));
+        assert_eq!(
+            meta.png64,
+            Some("https://example.com/small.png".into())
+        );
+        assert_eq!(meta.delta.unwrap().day, Some(0.99));
+    }
+
+    #[test]
+    fn market_chart_query_in_seconds() {
+        let query = MarketChartQuery::from(
+            &CoinHistoryRequest::new(
+                "BTC".into(),
+                1_711_929_600_000,
+                1_712_016_000_000,
+                false,
+            ),
+        );
+        assert_eq!(query.from, 1_711_929_600);
+        assert_eq!(query.to, 1_712_016_000);
+    }
+
+    #[test]
+    fn market_chart_into_coin_history() {
+        let chart: MarketChart =
+            serde_json::from_str(CHART).unwrap();
+        let history = chart.into_coin_history("btc", None);
+        let points = history.history.unwrap();
+        assert_eq!(history.code, Some("BTC".into()));
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].date, Some(1711929600000));
+        assert_eq!(points[0].volume, Some(25000000000));
+        assert_eq!(points[1].volume, None);
+        assert_eq!(points[1].cap, Some(1400000000000));
+    }
+
+    #[test]
+    fn simple_price_quote_by_code() {
+        let prices: HashMap<String, SimplePrice> =
+            serde_json::from_str(
+                r#"{"bitcoin": {"usd": 64000.5, "last_updated_at": 1711929600}}"#,
+            )
+            .unwrap();
+        let quote =
+            simple_price_quote("btc", &prices).unwrap();
+        assert_eq!(quote.code, "BTC");
+        assert_eq!(quote.rate, 64000.5);
+        assert_eq!(quote.time, 1_711_929_600_000);
+        assert_eq!(quote.source, DataSource::CoinGecko);
+        assert!(
+            simple_price_quote("ETH", &prices).is_none()
+        );
+    }
+}
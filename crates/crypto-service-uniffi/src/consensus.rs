@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use uniffi::Record;
+
+use crate::data_source::{DataSource, Quote};
+
+/// How far a source may stray from the median before it's left out,
+/// as a fraction of the median.
+pub const DEFAULT_MAX_DEVIATION: f64 = 0.02;
+
+/// Median of the rates several sources quote for a coin.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ConsensusQuote {
+    pub code: String,
+    /// Median of the accepted quotes.
+    pub rate: f64,
+    /// Highest minus lowest accepted rate, as a fraction of `rate`.
+    pub spread: f64,
+    /// Sources whose quotes went into `rate`.
+    pub sources: Vec<DataSource>,
+    pub quotes: Vec<Quote>,
+    /// Quotes further than the allowed deviation from the median.
+    pub outliers: Vec<Quote>,
+    /// Whether a source was left out or the rest are further apart
+    /// than the allowed deviation.
+    pub disagreement: bool,
+}
+
+pub fn median(rates: &[f64]) -> Option<f64> {
+    let mut rates = rates.to_vec();
+    rates.sort_by(f64::total_cmp);
+    let middle = rates.len() / 2;
+    match rates.len() {
+        0 => None,
+        len if len % 2 == 0 => {
+            Some((rates[middle - 1] + rates[middle]) / 2.0)
+        }
+        _ => Some(rates[middle]),
+    }
+}
+
+impl ConsensusQuote {
+    /// Consensus of `quotes`, leaving out those deviating more than
+    /// `max_deviation` from their median. When every quote would be
+    /// left out, as with two that are far apart, all of them are
+    /// kept and flagged instead. `None` without quotes.
+    pub fn from_quotes(
+        code: &str,
+        quotes: Vec<Quote>,
+        max_deviation: f64,
+    ) -> Option<Self> {
+        let rates: Vec<f64> =
+            quotes.iter().map(|quote| quote.rate).collect();
+        let first_median = median(&rates)?;
+        let (mut accepted, mut outliers): (Vec<_>, Vec<_>) =
+            quotes.into_iter().partition(|quote| {
+                relative(
+                    quote.rate - first_median,
+                    first_median,
+                ) <= max_deviation
+            });
+        if accepted.is_empty() {
+            accepted = std::mem::take(&mut outliers);
+        }
+
+        let rates: Vec<f64> = accepted
+            .iter()
+            .map(|quote| quote.rate)
+            .collect();
+        let rate = median(&rates)?;
+        let (low, high) = rates.iter().fold(
+            (f64::MAX, f64::MIN),
+            |(low, high), rate| {
+                (low.min(*rate), high.max(*rate))
+            },
+        );
+        let spread = relative(high - low, rate);
+        Some(Self {
+            code: code.to_uppercase(),
+            rate,
+            spread,
+            sources: accepted
+                .iter()
+                .map(|quote| quote.source)
+                .collect(),
+            disagreement: !outliers.is_empty()
+                || spread > max_deviation,
+            quotes: accepted,
+            outliers,
+        })
+    }
+}
+
+/// Size of `difference` as a fraction of `rate`.
+fn relative(difference: f64, rate: f64) -> f64 {
+    if rate == 0.0 {
+        return 0.0;
+    }
+    (difference / rate).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(source: DataSource, rate: f64) -> Quote {
+        Quote {
+            code: "BTC".into(),
+            rate,
+            time: 0,
+            source,
+        }
+    }
+
+    #[test]
+    fn median_of_odd_and_even() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(
+            median(&[4.0, 1.0, 2.0, 3.0]),
+            Some(2.5)
+        );
+        assert_eq!(median(&[]), None);
+    }
+
+    #[test]
+    fn agreeing_sources() {
+        let consensus = ConsensusQuote::from_quotes(
+            "btc",
+            vec![
+                quote(DataSource::LiveCoinWatch, 100.0),
+                quote(DataSource::CoinGecko, 100.5),
+                quote(DataSource::Binance, 99.5),
+            ],
+            DEFAULT_MAX_DEVIATION,
+        )
+        .unwrap();
+        assert_eq!(consensus.code, "BTC");
+        assert_eq!(consensus.rate, 100.0);
+        assert!((consensus.spread - 0.01).abs() < 1e-9);
+        assert_eq!(consensus.sources.len(), 3);
+        assert!(!consensus.disagreement);
+    }
+
+    #[test]
+    fn outlier_is_rejected_and_flagged() {
+        let consensus = ConsensusQuote::from_quotes(
+            "BTC",
+            vec![
+                quote(DataSource::LiveCoinWatch, 100.0),
+                quote(DataSource::CoinGecko, 101.0),
+                quote(DataSource::Binance, 120.0),
+            ],
+            DEFAULT_MAX_DEVIATION,
+        )
+        .unwrap();
+        assert_eq!(consensus.rate, 100.5);
+        assert_eq!(
+            consensus.sources,
+            vec![
+                DataSource::LiveCoinWatch,
+                DataSource::CoinGecko
+            ]
+        );
+        assert_eq!(
+            consensus.outliers,
+            vec![quote(DataSource::Binance, 120.0)]
+        );
+        assert!(consensus.disagreement);
+    }
+
+    #[test]
+    fn two_far_apart_are_kept_but_flagged() {
+        let consensus = ConsensusQuote::from_quotes(
+            "BTC",
+            vec![
+                quote(DataSource::LiveCoinWatch, 100.0),
+                quote(DataSource::CoinGecko, 110.0),
+            ],
+            DEFAULT_MAX_DEVIATION,
+        )
+        .unwrap();
+        assert_eq!(consensus.rate, 105.0);
+        assert!(consensus.outliers.is_empty());
+        assert!(consensus.disagreement);
+    }
+
+    #[test]
+    fn no_quotes_no_consensus() {
+        assert_eq!(
+            ConsensusQuote::from_quotes(
+                "BTC",
+                vec![],
+                DEFAULT_MAX_DEVIATION
+            ),
+            None
+        );
+    }
+}
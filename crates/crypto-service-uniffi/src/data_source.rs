@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use uniffi::{Enum, Record};
+
+/// Upstream that coin data is fetched from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DataSource {
+    #[default]
+    #[serde(rename = "livecoinwatch")]
+    LiveCoinWatch,
+    #[serde(rename = "coingecko")]
+    CoinGecko,
+    #[serde(rename = "binance")]
+    Binance,
+    #[serde(rename = "coinapi")]
+    CoinApi,
+}
+
+impl DataSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataSource::LiveCoinWatch => "livecoinwatch",
+            DataSource::CoinGecko => "coingecko",
+            DataSource::Binance => "binance",
+            DataSource::CoinApi => "coinapi",
+        }
+    }
+
+    pub fn parse(source: &str) -> Option<Self> {
+        match source.to_lowercase().as_str() {
+            "livecoinwatch" => {
+                Some(DataSource::LiveCoinWatch)
+            }
+            "coingecko" => Some(DataSource::CoinGecko),
+            "binance" => Some(DataSource::Binance),
+            "coinapi" => Some(DataSource::CoinApi),
+            _ => None,
+        }
+    }
+}
+
+/// Latest USD price of a coin and the source that reported it.
+#[derive(Serialize, Deserialize, Debug, Clone, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Quote {
+    pub code: String,
+    pub rate: f64,
+    /// When the source last updated the price, in milliseconds.
+    pub time: i64,
+    pub source: DataSource,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trips() {
+        for source in [
+            DataSource::LiveCoinWatch,
+            DataSource::CoinGecko,
+            DataSource::Binance,
+            DataSource::CoinApi,
+        ] {
+            assert_eq!(
+                DataSource::parse(source.as_str()),
+                Some(source)
+            );
+        }
+    }
+
+    #[test]
+    fn serializes_as_str() {
+        assert_eq!(
+            serde_json::to_value(DataSource::CoinGecko)
+                .unwrap(),
+            "coingecko"
+        );
+    }
+}
@@ -1,9 +1,18 @@
 #![feature(trait_upcasting)]
 
 pub mod alphavantage_service;
+pub mod asset;
+pub mod asset_registry;
+pub mod binance_service;
 pub mod client_trait;
 pub mod api_client;
 pub mod coin_watch_service;
+pub mod coinapi_service;
+pub mod coingecko_service;
+pub mod consensus;
+pub mod data_source;
 pub mod network_antenna;
+pub mod portfolio;
+pub mod tabular;
 
 uniffi::include_scaffolding!("crypto_service");
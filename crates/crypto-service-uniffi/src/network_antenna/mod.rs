@@ -1 +1,2 @@
-pub mod network_antenna;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod network_antenna;
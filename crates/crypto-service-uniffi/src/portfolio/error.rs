@@ -0,0 +1,25 @@
+use thiserror::Error as ThisError;
+use uniffi::Error;
+
+#[derive(Debug, PartialEq, Clone, Error, ThisError)]
+pub enum PortfolioError {
+    #[error("Transaction '{transaction_id}' disposes of {requested} {asset} but only {available} is held")]
+    InsufficientQuantity {
+        transaction_id: String,
+        asset: String,
+        requested: f64,
+        available: f64,
+    },
+
+    #[error("Transaction '{transaction_id}' has a negative or non-finite quantity, price or fee")]
+    InvalidTransaction { transaction_id: String },
+
+    #[error("Timestamp '{timestamp}' is out of range")]
+    InvalidTimestamp { timestamp: i64 },
+
+    #[error("Unrecognized CSV format, expected a Binance, Coinbase or Kraken trade history export")]
+    UnrecognizedCsvFormat,
+
+    #[error("Failed to read CSV: {message}")]
+    MalformedCsv { message: String },
+}
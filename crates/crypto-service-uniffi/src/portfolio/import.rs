@@ -0,0 +1,667 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDateTime};
+use csv::{ReaderBuilder, StringRecord};
+
+use super::{
+    error::PortfolioError,
+    models::{
+        ExchangeFormat, ImportReport, ImportRowError,
+        Transaction, TransactionKind,
+    },
+};
+
+/// Quote currencies we can value in USD without a conversion.
+const USD_QUOTES: [&str; 7] =
+    ["USD", "USDT", "USDC", "BUSD", "FDUSD", "TUSD", "DAI"];
+
+/// Quote currencies used to split pairs such as `ETHBTC`, longest
+/// first so that `USDT` wins over `USD`.
+const KNOWN_QUOTES: [&str; 13] = [
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "DAI", "USD",
+    "EUR", "GBP", "TRY", "BTC", "ETH", "BNB",
+];
+
+impl ExchangeFormat {
+    /// Columns that must all be present in the header row of an
+    /// export of this format.
+    fn required_columns(&self) -> &'static [&'static str] {
+        match self {
+            ExchangeFormat::Binance => &[
+                "Date(UTC)",
+                "Pair",
+                "Side",
+                "Price",
+                "Executed",
+                "Fee",
+            ],
+            ExchangeFormat::Coinbase => &[
+                "Timestamp",
+                "Transaction Type",
+                "Asset",
+                "Quantity Transacted",
+                "Fees and/or Spread",
+            ],
+            ExchangeFormat::Kraken => &[
+                "txid", "pair", "time", "type", "price",
+                "fee", "vol",
+            ],
+        }
+    }
+
+    fn id_prefix(&self) -> &'static str {
+        match self {
+            ExchangeFormat::Binance => "binance",
+            ExchangeFormat::Coinbase => "coinbase",
+            ExchangeFormat::Kraken => "kraken",
+        }
+    }
+
+    fn detect(header: &StringRecord) -> Option<Self> {
+        [
+            ExchangeFormat::Binance,
+            ExchangeFormat::Coinbase,
+            ExchangeFormat::Kraken,
+        ]
+        .into_iter()
+        .find(|format| {
+            format.required_columns().iter().all(|column| {
+                header.iter().any(|h| h.trim() == *column)
+            })
+        })
+    }
+}
+
+/// Parses a trade history export from Binance, Coinbase or Kraken
+/// into [`Transaction`]s, detecting the format from its header.
+///
+/// Rows that can't be parsed are reported in
+/// [`ImportReport::errors`] and don't abort the import. Every
+/// transaction gets an id that is stable across imports of the
+/// same file, rows whose id is in `existing_ids` (or appeared
+/// earlier in the file) are counted as duplicates and skipped.
+#[uniffi::export]
+pub fn import_transactions_csv(
+    bytes: Vec<u8>,
+    existing_ids: Vec<String>,
+) -> Result<ImportReport, PortfolioError> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(bytes.as_slice());
+    let mut records = reader.records();
+
+    // Coinbase exports start with a few lines of preamble before
+    // the actual header row, so skip ahead until one matches.
+    let (format, columns) = loop {
+        let Some(record) = records.next() else {
+            return Err(
+                PortfolioError::UnrecognizedCsvFormat,
+            );
+        };
+        let record = record.map_err(|e| {
+            PortfolioError::MalformedCsv {
+                message: e.to_string(),
+            }
+        })?;
+        if let Some(format) =
+            ExchangeFormat::detect(&record)
+        {
+            break (format, Columns::new(&record));
+        }
+    };
+
+    let mut seen: HashSet<String> =
+        existing_ids.into_iter().collect();
+    let mut occurrences: HashMap<u64, u32> = HashMap::new();
+    let mut report = ImportReport {
+        format,
+        transactions: vec![],
+        errors: vec![],
+        duplicates: 0,
+    };
+
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.errors.push(ImportRowError {
+                    row: e
+                        .position()
+                        .map(|p| p.line())
+                        .unwrap_or_default(),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if record
+            .iter()
+            .all(|field| field.trim().is_empty())
+        {
+            continue;
+        }
+        let row = record
+            .position()
+            .map(|p| p.line())
+            .unwrap_or_default();
+
+        let parsed = match format {
+            ExchangeFormat::Binance => {
+                parse_binance_row(&columns, &record)
+            }
+            ExchangeFormat::Coinbase => {
+                parse_coinbase_row(&columns, &record)
+            }
+            ExchangeFormat::Kraken => {
+                parse_kraken_row(&columns, &record)
+            }
+        };
+
+        match parsed {
+            Ok(mut transaction) => {
+                transaction.id =
+                    match transaction.id.is_empty() {
+                        true => row_id(
+                            format,
+                            &record,
+                            &mut occurrences,
+                        ),
+                        false => format!(
+                            "{}-{}",
+                            format.id_prefix(),
+                            transaction.id
+                        ),
+                    };
+                if seen.insert(transaction.id.clone()) {
+                    report.transactions.push(transaction);
+                } else {
+                    report.duplicates += 1;
+                }
+            }
+            Err(message) => report
+                .errors
+                .push(ImportRowError { row, message }),
+        }
+    }
+    Ok(report)
+}
+
+/// Column name to index lookup for the detected header row.
+struct Columns(HashMap<String, usize>);
+
+impl Columns {
+    fn new(header: &StringRecord) -> Self {
+        Self(
+            header
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    (name.trim().to_string(), i)
+                })
+                .collect(),
+        )
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Value of the first of `names` present in the header.
+    fn get<'r>(
+        &self,
+        record: &'r StringRecord,
+        names: &[&str],
+    ) -> Result<&'r str, String> {
+        names
+            .iter()
+            .find_map(|name| self.0.get(*name))
+            .and_then(|i| record.get(*i))
+            .map(str::trim)
+            .ok_or_else(|| {
+                format!("missing column '{}'", names[0])
+            })
+    }
+}
+
+fn parse_binance_row(
+    columns: &Columns,
+    record: &StringRecord,
+) -> Result<Transaction, String> {
+    let timestamp = parse_timestamp(
+        columns.get(record, &["Date(UTC)"])?,
+    )?;
+    let (base, quote) =
+        split_pair(columns.get(record, &["Pair"])?)?;
+    ensure_usd_quote(&quote)?;
+
+    let kind = match columns.get(record, &["Side"])? {
+        side if side.eq_ignore_ascii_case("buy") => {
+            TransactionKind::Buy
+        }
+        side if side.eq_ignore_ascii_case("sell") => {
+            TransactionKind::Sell
+        }
+        side => {
+            return Err(format!("unknown side '{side}'"))
+        }
+    };
+    let price =
+        parse_number(columns.get(record, &["Price"])?)?;
+    let (quantity, _) =
+        parse_amount(columns.get(record, &["Executed"])?)?;
+    let (fee, fee_asset) =
+        parse_amount(columns.get(record, &["Fee"])?)?;
+
+    // Binance charges fees in whatever asset was received, or in
+    // BNB when the discount is enabled.
+    let fee = if fee_asset.is_empty() || fee_asset == quote
+    {
+        fee
+    } else if fee_asset == base {
+        fee * price
+    } else {
+        return Err(format!(
+            "fee paid in {fee_asset} can't be valued in USD"
+        ));
+    };
+
+    Ok(Transaction::new(
+        String::new(),
+        timestamp,
+        base,
+        kind,
+        quantity,
+        price,
+        fee,
+    ))
+}
+
+fn parse_coinbase_row(
+    columns: &Columns,
+    record: &StringRecord,
+) -> Result<Transaction, String> {
+    let kind = match columns
+        .get(record, &["Transaction Type"])?
+    {
+        "Buy" | "Advanced Trade Buy" => {
+            TransactionKind::Buy
+        }
+        "Sell" | "Advanced Trade Sell" => {
+            TransactionKind::Sell
+        }
+        "Send" => TransactionKind::TransferOut,
+        "Receive" | "Rewards Income" | "Coinbase Earn"
+        | "Learning Reward" | "Staking Income" => {
+            TransactionKind::TransferIn
+        }
+        other => {
+            return Err(format!(
+                "unsupported transaction type '{other}'"
+            ))
+        }
+    };
+
+    let price_currency = columns.get(
+        record,
+        &["Price Currency", "Spot Price Currency"],
+    )?;
+    ensure_usd_quote(price_currency)?;
+
+    let id = match columns.has("ID") {
+        true => columns.get(record, &["ID"])?.to_string(),
+        false => String::new(),
+    };
+
+    Ok(Transaction::new(
+        id,
+        parse_timestamp(
+            columns.get(record, &["Timestamp"])?,
+        )?,
+        columns.get(record, &["Asset"])?.to_uppercase(),
+        kind,
+        parse_number(
+            columns
+                .get(record, &["Quantity Transacted"])?,
+        )?
+        .abs(),
+        parse_number(columns.get(
+            record,
+            &[
+                "Price at Transaction",
+                "Spot Price at Transaction",
+            ],
+        )?)?,
+        parse_number(
+            columns.get(record, &["Fees and/or Spread"])?,
+        )?
+        .abs(),
+    ))
+}
+
+fn parse_kraken_row(
+    columns: &Columns,
+    record: &StringRecord,
+) -> Result<Transaction, String> {
+    let (base, quote) =
+        split_kraken_pair(columns.get(record, &["pair"])?)?;
+    ensure_usd_quote(&quote)?;
+
+    let kind = match columns.get(record, &["type"])? {
+        "buy" => TransactionKind::Buy,
+        "sell" => TransactionKind::Sell,
+        other => {
+            return Err(format!("unknown type '{other}'"))
+        }
+    };
+
+    Ok(Transaction::new(
+        columns.get(record, &["txid"])?.to_string(),
+        parse_timestamp(columns.get(record, &["time"])?)?,
+        base,
+        kind,
+        parse_number(columns.get(record, &["vol"])?)?,
+        parse_number(columns.get(record, &["price"])?)?,
+        parse_number(columns.get(record, &["fee"])?)?,
+    ))
+}
+
+/// Id for rows without one of their own: a fingerprint of the
+/// row, plus how many times that exact row has been seen before
+/// in this file so that identical fills don't collapse into one.
+fn row_id(
+    format: ExchangeFormat,
+    record: &StringRecord,
+    occurrences: &mut HashMap<u64, u32>,
+) -> String {
+    let fingerprint = fingerprint(record);
+    let occurrence =
+        occurrences.entry(fingerprint).or_default();
+    *occurrence += 1;
+    format!(
+        "{}-{:016x}-{}",
+        format.id_prefix(),
+        fingerprint,
+        occurrence
+    )
+}
+
+/// FNV-1a over all fields. Unlike `DefaultHasher` it is stable
+/// across Rust versions, which ids persisted by clients rely on.
+fn fingerprint(record: &StringRecord) -> u64 {
+    record
+        .iter()
+        .flat_map(|field| {
+            field.trim().bytes().chain([0x1f])
+        })
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+fn parse_timestamp(value: &str) -> Result<i64, String> {
+    if let Ok(datetime) =
+        DateTime::parse_from_rfc3339(value)
+    {
+        return Ok(datetime.timestamp_millis());
+    }
+    let value = value.trim_end_matches(" UTC");
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|format| {
+            NaiveDateTime::parse_from_str(value, format)
+                .ok()
+        })
+        .map(|datetime| {
+            datetime.and_utc().timestamp_millis()
+        })
+        .ok_or_else(|| {
+            format!("invalid timestamp '{value}'")
+        })
+}
+
+/// Parses numbers as exported by exchanges, e.g. `$3,000.00`.
+fn parse_number(value: &str) -> Result<f64, String> {
+    value
+        .replace(['$', ','], "")
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("invalid number '{value}'"))
+}
+
+/// Splits amounts with an asset suffix such as `0.01000000BTC`.
+fn parse_amount(
+    value: &str,
+) -> Result<(f64, String), String> {
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, asset) = value.split_at(split);
+    Ok((parse_number(number)?, asset.to_uppercase()))
+}
+
+fn split_pair(
+    pair: &str,
+) -> Result<(String, String), String> {
+    let pair = pair.to_uppercase();
+    KNOWN_QUOTES
+        .iter()
+        .find(|quote| {
+            pair.len() > quote.len()
+                && pair.ends_with(*quote)
+        })
+        .map(|quote| {
+            let base = &pair[..pair.len() - quote.len()];
+            (base.to_string(), quote.to_string())
+        })
+        .ok_or_else(|| format!("unknown pair '{pair}'"))
+}
+
+/// Kraken uses `XBT`/`XDG` and, for older pairs, an `X`/`Z`
+/// prefixed four letter code per asset, e.g. `XXBTZUSD`.
+fn split_kraken_pair(
+    pair: &str,
+) -> Result<(String, String), String> {
+    let (base, quote) = match pair.split_once('/') {
+        Some((base, quote)) => (base.into(), quote.into()),
+        None if pair.len() == 8
+            && pair.starts_with(['X', 'Z'])
+            && pair[4..].starts_with(['X', 'Z']) =>
+        {
+            (pair[1..4].to_string(), pair[5..].to_string())
+        }
+        None => split_pair(pair)?,
+    };
+    let normalize = |code: String| match code.as_str() {
+        "XBT" => "BTC".to_string(),
+        "XDG" => "DOGE".to_string(),
+        _ => code,
+    };
+    Ok((normalize(base), normalize(quote)))
+}
+
+fn ensure_usd_quote(quote: &str) -> Result<(), String> {
+    match USD_QUOTES.contains(&quote) {
+        true => Ok(()),
+        false => Err(format!(
+            "quote currency {quote} is not supported, only USD"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINANCE: &str = include_str!(
+        "../../tests/fixtures/imports/binance_trade_history.csv"
+    );
+    const COINBASE: &str = include_str!(
+        "../../tests/fixtures/imports/coinbase_transaction_history.csv"
+    );
+    const KRAKEN: &str = include_str!(
+        "../../tests/fixtures/imports/kraken_trades.csv"
+    );
+
+    fn import(csv: &str) -> ImportReport {
+        import_transactions_csv(
+            csv.as_bytes().to_vec(),
+            vec![],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn detect_binance() {
+        assert_eq!(
+            import(BINANCE).format,
+            ExchangeFormat::Binance
+        );
+    }
+
+    #[test]
+    fn detect_coinbase_after_preamble() {
+        assert_eq!(
+            import(COINBASE).format,
+            ExchangeFormat::Coinbase
+        );
+    }
+
+    #[test]
+    fn detect_kraken() {
+        assert_eq!(
+            import(KRAKEN).format,
+            ExchangeFormat::Kraken
+        );
+    }
+
+    #[test]
+    fn unrecognized_format() {
+        assert_eq!(
+            import_transactions_csv(
+                b"a,b,c\n1,2,3\n".to_vec(),
+                vec![]
+            ),
+            Err(PortfolioError::UnrecognizedCsvFormat)
+        );
+    }
+
+    #[test]
+    fn binance_rows() {
+        let report = import(BINANCE);
+        assert_eq!(report.transactions.len(), 2);
+        assert_eq!(
+            report.transactions[0],
+            Transaction {
+                id: report.transactions[0].id.clone(),
+                timestamp: 1619870400000,
+                asset: "BTC".into(),
+                kind: TransactionKind::Buy,
+                quantity: 0.01,
+                price: 57000.0,
+                fee: report.transactions[0].fee,
+            }
+        );
+        assert!(
+            (report.transactions[0].fee - 0.57).abs()
+                < 1e-9
+        );
+        assert_eq!(report.transactions[1].fee, 0.29);
+    }
+
+    #[test]
+    fn binance_row_errors_do_not_abort() {
+        let errors = import(BINANCE).errors;
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.row)
+                .collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        assert!(errors[0].message.contains("BTC"));
+    }
+
+    #[test]
+    fn coinbase_rows() {
+        let report = import(COINBASE);
+        let kinds: Vec<TransactionKind> = report
+            .transactions
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TransactionKind::Buy,
+                TransactionKind::TransferOut,
+                TransactionKind::TransferIn
+            ]
+        );
+        assert_eq!(
+            report.transactions[0].id,
+            "coinbase-6091a1f0c0ffee0001"
+        );
+        assert_eq!(report.transactions[0].fee, 5.0);
+        assert_eq!(report.transactions[2].price, 3000.0);
+        assert_eq!(report.errors.len(), 1);
+    }
+
+    #[test]
+    fn kraken_pairs() {
+        let assets: Vec<String> = import(KRAKEN)
+            .transactions
+            .into_iter()
+            .map(|t| t.asset)
+            .collect();
+        assert_eq!(assets, vec!["BTC", "ETH", "DOT"]);
+    }
+
+    #[test]
+    fn kraken_fractional_timestamp() {
+        assert_eq!(
+            import(KRAKEN).transactions[0].timestamp,
+            1619870400123
+        );
+    }
+
+    #[test]
+    fn ids_are_stable_across_imports() {
+        assert_eq!(import(BINANCE), import(BINANCE));
+    }
+
+    #[test]
+    fn reimport_skips_existing_ids() {
+        let first = import(BINANCE);
+        let ids = first
+            .transactions
+            .iter()
+            .map(|t| t.id.clone())
+            .collect();
+        let second = import_transactions_csv(
+            BINANCE.as_bytes().to_vec(),
+            ids,
+        )
+        .unwrap();
+
+        assert!(second.transactions.is_empty());
+        assert_eq!(second.duplicates, 2);
+    }
+
+    #[test]
+    fn identical_rows_in_one_file_are_kept() {
+        let csv = "Date(UTC),Pair,Side,Price,Executed,Amount,Fee\n\
+            2021-05-01 12:00:00,BTCUSDT,BUY,1.0,1BTC,1USDT,0USDT\n\
+            2021-05-01 12:00:00,BTCUSDT,BUY,1.0,1BTC,1USDT,0USDT\n";
+        let report = import(csv);
+        assert_eq!(report.transactions.len(), 2);
+        assert_eq!(report.duplicates, 0);
+    }
+
+    #[test]
+    fn parse_number_with_currency_symbol() {
+        assert_eq!(parse_number("$3,000.50"), Ok(3000.5));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod error;
+pub mod import;
+pub mod models;
+pub mod tax_lots;
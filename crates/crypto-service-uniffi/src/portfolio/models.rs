@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use uniffi::{Enum, Record};
+
+/// A single entry in a user's transaction history.
+///
+/// `timestamp` is in unix milliseconds, same as the dates
+/// returned by Live Coin Watch. `price` and `fee` are
+/// denominated in the quote currency (USD).
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Transaction {
+    pub id: String,
+    pub timestamp: i64,
+    pub asset: String,
+    pub kind: TransactionKind,
+    pub quantity: f64,
+    pub price: f64,
+    pub fee: f64,
+}
+
+impl Transaction {
+    pub fn new(
+        id: String,
+        timestamp: i64,
+        asset: String,
+        kind: TransactionKind,
+        quantity: f64,
+        price: f64,
+        fee: f64,
+    ) -> Self {
+        Self {
+            id,
+            timestamp,
+            asset,
+            kind,
+            quantity,
+            price,
+            fee,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Buy,
+    Sell,
+    /// Asset arriving from another wallet owned by the user.
+    TransferIn,
+    /// Asset leaving to another wallet owned by the user.
+    TransferOut,
+}
+
+/// Which open lots a disposal consumes first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LotMethod {
+    /// First in, first out.
+    Fifo,
+    /// Last in, first out.
+    Lifo,
+    /// Highest cost basis per unit first.
+    Hifo,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum HoldingPeriod {
+    ShortTerm,
+    LongTerm,
+}
+
+/// An open position of an asset acquired at a single point in
+/// time. `cost_basis` covers the whole remaining `quantity` and
+/// includes any acquisition fees.
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+pub struct TaxLot {
+    pub asset: String,
+    pub acquired_at: i64,
+    pub quantity: f64,
+    pub cost_basis: f64,
+}
+
+impl TaxLot {
+    pub fn cost_per_unit(&self) -> f64 {
+        self.cost_basis / self.quantity
+    }
+}
+
+/// Gain or loss realized when (part of) a lot is disposed of.
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RealizedGain {
+    pub transaction_id: String,
+    pub asset: String,
+    pub quantity: f64,
+    pub acquired_at: i64,
+    pub disposed_at: i64,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub gain: f64,
+    pub holding_period: HoldingPeriod,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct YearlyGainsReport {
+    pub year: i32,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub short_term_gain: f64,
+    pub long_term_gain: f64,
+    pub total_gain: f64,
+    pub disposals: u32,
+}
+
+impl YearlyGainsReport {
+    pub fn new(year: i32) -> Self {
+        Self {
+            year,
+            proceeds: 0.0,
+            cost_basis: 0.0,
+            short_term_gain: 0.0,
+            long_term_gain: 0.0,
+            total_gain: 0.0,
+            disposals: 0,
+        }
+    }
+}
+
+/// Exchanges whose trade history exports can be imported.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Enum, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeFormat {
+    Binance,
+    Coinbase,
+    Kraken,
+}
+
+/// A row of an imported file that could not be turned into a
+/// [`Transaction`]. `row` is the 1-based line number in the file.
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportRowError {
+    pub row: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Record, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportReport {
+    pub format: ExchangeFormat,
+    pub transactions: Vec<Transaction>,
+    pub errors: Vec<ImportRowError>,
+    /// Rows skipped because a transaction with the same id was
+    /// already imported.
+    pub duplicates: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::portfolio::models::{
+        TaxLot, YearlyGainsReport,
+    };
+
+    #[test]
+    fn tax_lot_cost_per_unit() {
+        assert_eq!(
+            TaxLot {
+                asset: "BTC".into(),
+                acquired_at: 0,
+                quantity: 2.0,
+                cost_basis: 100.0,
+            }
+            .cost_per_unit(),
+            50.0
+        );
+    }
+
+    #[test]
+    fn new_yearly_gains_report_is_empty() {
+        assert_eq!(
+            YearlyGainsReport::new(2024).disposals,
+            0
+        );
+    }
+}
@@ -0,0 +1,649 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::{DateTime, Datelike, Months, Utc};
+
+use super::{
+    error::PortfolioError,
+    models::{
+        HoldingPeriod, LotMethod, RealizedGain, TaxLot,
+        Transaction, TransactionKind, YearlyGainsReport,
+    },
+};
+
+/// Quantities below this are treated as zero, so that lots
+/// emptied by a series of partial sells don't linger around
+/// because of floating point dust.
+const DUST: f64 = 1e-9;
+
+/// Replays a transaction history and keeps track of open tax
+/// lots per asset and the gains realized so far.
+///
+/// Transfers between the user's own wallets are not taxable:
+/// lots leaving with a `TransferOut` are parked in transit and
+/// picked up again, with their original acquisition date and
+/// cost basis, by the next `TransferIn` of the same asset.
+/// Transfer fees are added to the cost basis of the moved lots.
+#[derive(Debug, Clone)]
+pub struct TaxLotLedger {
+    method: LotMethod,
+    open_lots: HashMap<String, Vec<TaxLot>>,
+    in_transit: HashMap<String, VecDeque<TaxLot>>,
+    realized_gains: Vec<RealizedGain>,
+}
+
+impl TaxLotLedger {
+    pub fn new(method: LotMethod) -> Self {
+        Self {
+            method,
+            open_lots: HashMap::new(),
+            in_transit: HashMap::new(),
+            realized_gains: vec![],
+        }
+    }
+
+    /// Applies all `transactions` in chronological order.
+    /// Transactions sharing a timestamp keep their given order.
+    pub fn apply_all(
+        &mut self,
+        transactions: &[Transaction],
+    ) -> Result<(), PortfolioError> {
+        let mut sorted: Vec<&Transaction> =
+            transactions.iter().collect();
+        sorted.sort_by_key(|t| t.timestamp);
+        sorted.into_iter().try_for_each(|t| self.apply(t))
+    }
+
+    pub fn apply(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<(), PortfolioError> {
+        validate(transaction)?;
+        match transaction.kind {
+            TransactionKind::Buy => {
+                self.buy(transaction);
+                Ok(())
+            }
+            TransactionKind::Sell => self.sell(transaction),
+            TransactionKind::TransferOut => {
+                self.transfer_out(transaction)
+            }
+            TransactionKind::TransferIn => {
+                self.transfer_in(transaction);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn open_lots(&self, asset: &str) -> &[TaxLot] {
+        self.open_lots
+            .get(asset)
+            .map(|lots| lots.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn realized_gains(&self) -> &[RealizedGain] {
+        &self.realized_gains
+    }
+
+    pub fn into_realized_gains(self) -> Vec<RealizedGain> {
+        self.realized_gains
+    }
+
+    fn buy(&mut self, transaction: &Transaction) {
+        self.open_lots
+            .entry(transaction.asset.clone())
+            .or_default()
+            .push(TaxLot {
+                asset: transaction.asset.clone(),
+                acquired_at: transaction.timestamp,
+                quantity: transaction.quantity,
+                cost_basis: transaction.quantity
+                    * transaction.price
+                    + transaction.fee,
+            });
+    }
+
+    fn sell(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<(), PortfolioError> {
+        let disposed = self.take_lots(transaction)?;
+        let proceeds = transaction.quantity
+            * transaction.price
+            - transaction.fee;
+
+        for lot in disposed {
+            let share = lot.quantity / transaction.quantity;
+            let lot_proceeds = proceeds * share;
+            self.realized_gains.push(RealizedGain {
+                transaction_id: transaction.id.clone(),
+                asset: lot.asset,
+                quantity: lot.quantity,
+                acquired_at: lot.acquired_at,
+                disposed_at: transaction.timestamp,
+                proceeds: lot_proceeds,
+                cost_basis: lot.cost_basis,
+                gain: lot_proceeds - lot.cost_basis,
+                holding_period: holding_period(
+                    lot.acquired_at,
+                    transaction.timestamp,
+                )?,
+            });
+        }
+        Ok(())
+    }
+
+    fn transfer_out(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<(), PortfolioError> {
+        let moved = self.take_lots(transaction)?;
+        let in_transit = self
+            .in_transit
+            .entry(transaction.asset.clone())
+            .or_default();
+
+        for mut lot in moved {
+            lot.cost_basis += transaction.fee
+                * lot.quantity
+                / transaction.quantity;
+            in_transit.push_back(lot);
+        }
+        Ok(())
+    }
+
+    fn transfer_in(&mut self, transaction: &Transaction) {
+        let in_transit = self
+            .in_transit
+            .entry(transaction.asset.clone())
+            .or_default();
+        let mut arrived = vec![];
+        let mut remaining = transaction.quantity;
+
+        while remaining > DUST {
+            let Some(lot) = in_transit.front_mut() else {
+                break;
+            };
+            if lot.quantity <= remaining + DUST {
+                remaining -= lot.quantity;
+                arrived.extend(in_transit.pop_front());
+            } else {
+                arrived.push(split_lot(lot, remaining));
+                remaining = 0.0;
+            }
+        }
+
+        // Anything not matched by an earlier `TransferOut` comes
+        // from outside the tracked history and starts a new lot.
+        if remaining > DUST {
+            arrived.push(TaxLot {
+                asset: transaction.asset.clone(),
+                acquired_at: transaction.timestamp,
+                quantity: remaining,
+                cost_basis: remaining * transaction.price,
+            });
+        }
+
+        let open_lots = self
+            .open_lots
+            .entry(transaction.asset.clone())
+            .or_default();
+        for mut lot in arrived {
+            lot.cost_basis += transaction.fee
+                * lot.quantity
+                / transaction.quantity;
+            open_lots.push(lot);
+        }
+    }
+
+    /// Removes `transaction.quantity` worth of lots, in the order
+    /// given by the ledger's [`LotMethod`], splitting the last
+    /// lot if it is only partially consumed.
+    fn take_lots(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<Vec<TaxLot>, PortfolioError> {
+        let lots = self
+            .open_lots
+            .entry(transaction.asset.clone())
+            .or_default();
+
+        let available: f64 =
+            lots.iter().map(|lot| lot.quantity).sum();
+        if transaction.quantity > available + DUST {
+            return Err(
+                PortfolioError::InsufficientQuantity {
+                    transaction_id: transaction.id.clone(),
+                    asset: transaction.asset.clone(),
+                    requested: transaction.quantity,
+                    available,
+                },
+            );
+        }
+
+        let mut taken = vec![];
+        let mut remaining = transaction.quantity;
+        while remaining > DUST {
+            let Some(index) = next_lot(lots, self.method)
+            else {
+                break;
+            };
+            if lots[index].quantity <= remaining + DUST {
+                remaining -= lots[index].quantity;
+                taken.push(lots.remove(index));
+            } else {
+                taken.push(split_lot(
+                    &mut lots[index],
+                    remaining,
+                ));
+                remaining = 0.0;
+            }
+        }
+        Ok(taken)
+    }
+}
+
+/// Computes the realized gains of `transactions` using the given
+/// lot selection `method`.
+#[uniffi::export]
+pub fn compute_realized_gains(
+    transactions: Vec<Transaction>,
+    method: LotMethod,
+) -> Result<Vec<RealizedGain>, PortfolioError> {
+    let mut ledger = TaxLotLedger::new(method);
+    ledger.apply_all(&transactions)?;
+    Ok(ledger.into_realized_gains())
+}
+
+/// Computes realized gains and sums them up per calendar year
+/// (UTC) of disposal.
+#[uniffi::export]
+pub fn compute_yearly_gains_report(
+    transactions: Vec<Transaction>,
+    method: LotMethod,
+) -> Result<Vec<YearlyGainsReport>, PortfolioError> {
+    yearly_gains_report(&compute_realized_gains(
+        transactions,
+        method,
+    )?)
+}
+
+/// Groups `gains` by the year they were realized in, sorted by
+/// year.
+pub fn yearly_gains_report(
+    gains: &[RealizedGain],
+) -> Result<Vec<YearlyGainsReport>, PortfolioError> {
+    let mut reports: BTreeMap<i32, YearlyGainsReport> =
+        BTreeMap::new();
+
+    for gain in gains {
+        let year = datetime(gain.disposed_at)?.year();
+        let report =
+            reports.entry(year).or_insert_with(|| {
+                YearlyGainsReport::new(year)
+            });
+
+        report.proceeds += gain.proceeds;
+        report.cost_basis += gain.cost_basis;
+        match gain.holding_period {
+            HoldingPeriod::ShortTerm => {
+                report.short_term_gain += gain.gain
+            }
+            HoldingPeriod::LongTerm => {
+                report.long_term_gain += gain.gain
+            }
+        }
+        report.total_gain += gain.gain;
+        report.disposals += 1;
+    }
+    Ok(reports.into_values().collect())
+}
+
+/// A lot is held long term when it is disposed of more than one
+/// year after it was acquired.
+pub fn holding_period(
+    acquired_at: i64,
+    disposed_at: i64,
+) -> Result<HoldingPeriod, PortfolioError> {
+    let one_year_later = datetime(acquired_at)?
+        .checked_add_months(Months::new(12))
+        .ok_or(PortfolioError::InvalidTimestamp {
+            timestamp: acquired_at,
+        })?;
+
+    if datetime(disposed_at)? > one_year_later {
+        Ok(HoldingPeriod::LongTerm)
+    } else {
+        Ok(HoldingPeriod::ShortTerm)
+    }
+}
+
+fn datetime(
+    timestamp: i64,
+) -> Result<DateTime<Utc>, PortfolioError> {
+    DateTime::from_timestamp_millis(timestamp).ok_or(
+        PortfolioError::InvalidTimestamp { timestamp },
+    )
+}
+
+fn validate(
+    transaction: &Transaction,
+) -> Result<(), PortfolioError> {
+    let valid = transaction.quantity.is_finite()
+        && transaction.quantity > 0.0
+        && transaction.price.is_finite()
+        && transaction.price >= 0.0
+        && transaction.fee.is_finite()
+        && transaction.fee >= 0.0;
+
+    if valid {
+        Ok(())
+    } else {
+        Err(PortfolioError::InvalidTransaction {
+            transaction_id: transaction.id.clone(),
+        })
+    }
+}
+
+/// Index of the lot to consume next. Ties are broken by position,
+/// i.e. the order in which lots were opened.
+fn next_lot(
+    lots: &[TaxLot],
+    method: LotMethod,
+) -> Option<usize> {
+    let indexed = lots.iter().enumerate();
+    match method {
+        LotMethod::Fifo => indexed
+            .min_by_key(|(i, lot)| (lot.acquired_at, *i))
+            .map(|(i, _)| i),
+        LotMethod::Lifo => indexed
+            .max_by_key(|(i, lot)| (lot.acquired_at, *i))
+            .map(|(i, _)| i),
+        LotMethod::Hifo => indexed
+            .min_by(|(i, a), (j, b)| {
+                b.cost_per_unit()
+                    .total_cmp(&a.cost_per_unit())
+                    .then(a.acquired_at.cmp(&b.acquired_at))
+                    .then(i.cmp(j))
+            })
+            .map(|(i, _)| i),
+    }
+}
+
+/// Splits `quantity` off `lot`, moving a proportional part of the
+/// cost basis along with it.
+fn split_lot(lot: &mut TaxLot, quantity: f64) -> TaxLot {
+    let cost_basis =
+        lot.cost_basis * quantity / lot.quantity;
+    lot.quantity -= quantity;
+    lot.cost_basis -= cost_basis;
+    TaxLot {
+        asset: lot.asset.clone(),
+        acquired_at: lot.acquired_at,
+        quantity,
+        cost_basis,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// A golden file: a transaction history together with the
+    /// gains and yearly report it is expected to produce.
+    #[derive(Deserialize)]
+    struct Golden {
+        method: LotMethod,
+        transactions: Vec<Transaction>,
+        expected_gains: Vec<RealizedGain>,
+        expected_report: Vec<YearlyGainsReport>,
+    }
+
+    const GOLDEN_FILES: [(&str, &str); 4] = [
+        (
+            "fifo_partial_sells",
+            include_str!(
+                "../../tests/fixtures/tax_lots/fifo_partial_sells.json"
+            ),
+        ),
+        (
+            "lifo_partial_sell",
+            include_str!(
+                "../../tests/fixtures/tax_lots/lifo_partial_sell.json"
+            ),
+        ),
+        (
+            "hifo_partial_sell",
+            include_str!(
+                "../../tests/fixtures/tax_lots/hifo_partial_sell.json"
+            ),
+        ),
+        (
+            "fifo_transfers",
+            include_str!(
+                "../../tests/fixtures/tax_lots/fifo_transfers.json"
+            ),
+        ),
+    ];
+
+    fn assert_close(
+        name: &str,
+        actual: f64,
+        expected: f64,
+    ) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{name}: expected {expected}, got {actual}"
+        );
+    }
+
+    fn transaction(
+        id: &str,
+        kind: TransactionKind,
+        quantity: f64,
+        price: f64,
+    ) -> Transaction {
+        Transaction::new(
+            id.into(),
+            1609459200000,
+            "BTC".into(),
+            kind,
+            quantity,
+            price,
+            0.0,
+        )
+    }
+
+    #[test]
+    fn golden_files() {
+        for (name, json) in GOLDEN_FILES {
+            let golden: Golden =
+                serde_json::from_str(json).unwrap();
+            let gains = compute_realized_gains(
+                golden.transactions,
+                golden.method,
+            )
+            .unwrap();
+            let report =
+                yearly_gains_report(&gains).unwrap();
+
+            assert_eq!(
+                gains.len(),
+                golden.expected_gains.len(),
+                "{name}"
+            );
+            for (actual, expected) in
+                gains.iter().zip(&golden.expected_gains)
+            {
+                assert_eq!(
+                    (
+                        &actual.transaction_id,
+                        actual.acquired_at,
+                        actual.holding_period
+                    ),
+                    (
+                        &expected.transaction_id,
+                        expected.acquired_at,
+                        expected.holding_period
+                    ),
+                    "{name}"
+                );
+                assert_close(
+                    name,
+                    actual.quantity,
+                    expected.quantity,
+                );
+                assert_close(
+                    name,
+                    actual.proceeds,
+                    expected.proceeds,
+                );
+                assert_close(
+                    name,
+                    actual.cost_basis,
+                    expected.cost_basis,
+                );
+                assert_close(
+                    name,
+                    actual.gain,
+                    expected.gain,
+                );
+            }
+
+            assert_eq!(
+                report.len(),
+                golden.expected_report.len(),
+                "{name}"
+            );
+            for (actual, expected) in
+                report.iter().zip(&golden.expected_report)
+            {
+                assert_eq!(
+                    (actual.year, actual.disposals),
+                    (expected.year, expected.disposals),
+                    "{name}"
+                );
+                assert_close(
+                    name,
+                    actual.short_term_gain,
+                    expected.short_term_gain,
+                );
+                assert_close(
+                    name,
+                    actual.long_term_gain,
+                    expected.long_term_gain,
+                );
+                assert_close(
+                    name,
+                    actual.total_gain,
+                    expected.total_gain,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sell_more_than_held() {
+        assert_eq!(
+            compute_realized_gains(
+                vec![
+                    transaction(
+                        "1",
+                        TransactionKind::Buy,
+                        1.0,
+                        100.0
+                    ),
+                    transaction(
+                        "2",
+                        TransactionKind::Sell,
+                        2.0,
+                        100.0
+                    ),
+                ],
+                LotMethod::Fifo
+            ),
+            Err(PortfolioError::InsufficientQuantity {
+                transaction_id: "2".into(),
+                asset: "BTC".into(),
+                requested: 2.0,
+                available: 1.0
+            })
+        );
+    }
+
+    #[test]
+    fn negative_quantity_is_invalid() {
+        assert_eq!(
+            compute_realized_gains(
+                vec![transaction(
+                    "1",
+                    TransactionKind::Buy,
+                    -1.0,
+                    100.0
+                )],
+                LotMethod::Fifo
+            ),
+            Err(PortfolioError::InvalidTransaction {
+                transaction_id: "1".into()
+            })
+        );
+    }
+
+    #[test]
+    fn partial_sell_leaves_remainder_open() {
+        let mut ledger = TaxLotLedger::new(LotMethod::Fifo);
+        ledger
+            .apply_all(&[
+                transaction(
+                    "1",
+                    TransactionKind::Buy,
+                    1.0,
+                    100.0,
+                ),
+                transaction(
+                    "2",
+                    TransactionKind::Sell,
+                    0.25,
+                    200.0,
+                ),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            ledger.open_lots("BTC")[0].quantity,
+            0.75
+        );
+        assert_eq!(
+            ledger.open_lots("BTC")[0].cost_basis,
+            75.0
+        );
+    }
+
+    #[test]
+    fn unmatched_transfer_in_opens_new_lot() {
+        let mut ledger = TaxLotLedger::new(LotMethod::Fifo);
+        ledger
+            .apply(&transaction(
+                "1",
+                TransactionKind::TransferIn,
+                2.0,
+                50.0,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            ledger.open_lots("BTC")[0].cost_basis,
+            100.0
+        );
+    }
+
+    #[test]
+    fn holding_period_exactly_one_year_is_short_term() {
+        assert_eq!(
+            holding_period(1609459200000, 1640995200000),
+            Ok(HoldingPeriod::ShortTerm)
+        );
+    }
+}
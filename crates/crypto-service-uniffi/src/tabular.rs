@@ -0,0 +1,902 @@
+use serde_json::{json, Value};
+
+use crate::{
+    alphavantage_service::models::{
+        CurrencyExchangeRate, DigitalCurrencySeries,
+        FxSeries, MostActivelyTraded, StockQuote,
+        StockSeries, SymbolMatch, TopAndBottomTrades,
+    },
+    asset::AssetSummary,
+    binance_service::models::{
+        Candle, ExchangeInfo, OrderBook, Ticker,
+    },
+    coin_watch_service::models::{
+        AggregatedCoinInformation, Coin, CoinHistory,
+        CoinMeta, Delta,
+    },
+    data_source::Quote,
+    portfolio::models::{
+        RealizedGain, Transaction, YearlyGainsReport,
+    },
+};
+
+/// Rows of values, in the same order as [`Tabular::columns`].
+pub type Rows = Box<dyn Iterator<Item = Vec<Value>> + Send>;
+
+/// A model with a flat, stable column layout, used when exporting
+/// it as CSV or JSON Lines.
+///
+/// The column names are a contract with whoever loads the exports,
+/// so only ever append to them.
+pub trait Tabular {
+    fn columns() -> &'static [&'static str];
+
+    /// One row per record, each with a value per column. Nested
+    /// models such as [`CoinHistory`] expand into several rows.
+    fn into_rows(self) -> Rows;
+}
+
+impl<T> Tabular for Vec<T>
+where
+    T: Tabular + Send + 'static,
+{
+    fn columns() -> &'static [&'static str] {
+        T::columns()
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(self.into_iter().flat_map(T::into_rows))
+    }
+}
+
+/// Header line for `T`, including the trailing newline.
+pub fn csv_header<T: Tabular>() -> String {
+    csv_line(
+        T::columns()
+            .iter()
+            .map(|column| Value::from(*column))
+            .collect(),
+    )
+}
+
+/// Encodes `row` as a CSV line, quoting fields where needed.
+/// Missing values are written as empty fields.
+pub fn csv_line(row: Vec<Value>) -> String {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record(row.iter().map(|value| match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }))
+        .expect("Writing CSV to memory can't fail");
+    let bytes = writer
+        .into_inner()
+        .expect("Flushing CSV to memory can't fail");
+
+    String::from_utf8(bytes)
+        .expect("CSV of UTF-8 fields is UTF-8")
+}
+
+/// Encodes `row` as a JSON object with keys in column order,
+/// including the trailing newline.
+pub fn json_line<T: Tabular>(row: Vec<Value>) -> String {
+    let fields: Vec<String> = T::columns()
+        .iter()
+        .zip(row)
+        .map(|(column, value)| {
+            format!("{}:{}", json!(column), value)
+        })
+        .collect();
+    format!("{{{}}}\n", fields.join(","))
+}
+
+fn delta_values(delta: Option<&Delta>) -> [Value; 6] {
+    match delta {
+        Some(delta) => [
+            json!(delta.hour),
+            json!(delta.day),
+            json!(delta.week),
+            json!(delta.month),
+            json!(delta.quarter),
+            json!(delta.year),
+        ],
+        None => Default::default(),
+    }
+}
+
+impl Tabular for Coin {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "code",
+            "rate",
+            "volume",
+            "cap",
+            "delta_hour",
+            "delta_day",
+            "delta_week",
+            "delta_month",
+            "delta_quarter",
+            "delta_year",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let mut row = vec![
+            json!(self.code),
+            json!(self.rate),
+            json!(self.volume),
+            json!(self.cap),
+        ];
+        row.extend(delta_values(Some(&self.delta)));
+        Box::new(std::iter::once(row))
+    }
+}
+
+impl Tabular for CoinMeta {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "code",
+            "name",
+            "symbol",
+            "rank",
+            "rate",
+            "all_time_high_usd",
+            "color",
+            "delta_hour",
+            "delta_day",
+            "delta_week",
+            "delta_month",
+            "delta_quarter",
+            "delta_year",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let mut row = vec![
+            json!(self.code),
+            json!(self.name),
+            json!(self.symbol),
+            json!(self.rank),
+            json!(self.rate),
+            json!(self.all_time_high_usd),
+            json!(self.color),
+        ];
+        row.extend(delta_values(self.delta.as_ref()));
+        Box::new(std::iter::once(row))
+    }
+}
+
+impl Tabular for CoinHistory {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "code",
+            "date",
+            "rate",
+            "volume",
+            "cap",
+            "liquidity",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let code = json!(self.code);
+        Box::new(
+            self.history
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |point| {
+                    vec![
+                        code.clone(),
+                        json!(point.date),
+                        json!(point.rate),
+                        json!(point.volume),
+                        json!(point.cap),
+                        json!(point.liquidity),
+                    ]
+                }),
+        )
+    }
+}
+
+impl Tabular for AggregatedCoinInformation {
+    fn columns() -> &'static [&'static str] {
+        &["name", "symbol", "rank", "rate", "color"]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.name),
+            json!(self.symbol),
+            json!(self.rank),
+            json!(self.rate),
+            json!(self.color),
+        ]))
+    }
+}
+
+impl Tabular for TopAndBottomTrades {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "last_updated",
+            "category",
+            "ticker",
+            "price",
+            "change_amount",
+            "change_percentage",
+            "volume",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let last_updated = json!(self.last_updated);
+        let categories = [
+            ("top_gainers", self.top_gainers),
+            ("top_losers", self.top_losers),
+            (
+                "most_actively_traded",
+                self.most_actively_traded,
+            ),
+        ];
+        Box::new(categories.into_iter().flat_map(
+            move |(category, trades)| {
+                let last_updated = last_updated.clone();
+                trades.into_iter().map(
+                    move |trade: MostActivelyTraded| {
+                        vec![
+                            last_updated.clone(),
+                            json!(category),
+                            json!(trade.ticker),
+                            json!(trade.price),
+                            json!(trade.change_amount),
+                            json!(trade.change_percentage),
+                            json!(trade.volume),
+                        ]
+                    },
+                )
+            },
+        ))
+    }
+}
+
+impl Tabular for Transaction {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "timestamp",
+            "asset",
+            "kind",
+            "quantity",
+            "price",
+            "fee",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.id),
+            json!(self.timestamp),
+            json!(self.asset),
+            json!(self.kind),
+            json!(self.quantity),
+            json!(self.price),
+            json!(self.fee),
+        ]))
+    }
+}
+
+impl Tabular for RealizedGain {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "transaction_id",
+            "asset",
+            "quantity",
+            "acquired_at",
+            "disposed_at",
+            "proceeds",
+            "cost_basis",
+            "gain",
+            "holding_period",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.transaction_id),
+            json!(self.asset),
+            json!(self.quantity),
+            json!(self.acquired_at),
+            json!(self.disposed_at),
+            json!(self.proceeds),
+            json!(self.cost_basis),
+            json!(self.gain),
+            json!(self.holding_period),
+        ]))
+    }
+}
+
+impl Tabular for YearlyGainsReport {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "year",
+            "proceeds",
+            "cost_basis",
+            "short_term_gain",
+            "long_term_gain",
+            "total_gain",
+            "disposals",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.year),
+            json!(self.proceeds),
+            json!(self.cost_basis),
+            json!(self.short_term_gain),
+            json!(self.long_term_gain),
+            json!(self.total_gain),
+            json!(self.disposals),
+        ]))
+    }
+}
+
+impl Tabular for Ticker {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "symbol",
+            "last_price",
+            "price_change",
+            "price_change_percent",
+            "open_price",
+            "high_price",
+            "low_price",
+            "volume",
+            "quote_volume",
+            "open_time",
+            "close_time",
+            "count",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.symbol),
+            json!(self.last_price),
+            json!(self.price_change),
+            json!(self.price_change_percent),
+            json!(self.open_price),
+            json!(self.high_price),
+            json!(self.low_price),
+            json!(self.volume),
+            json!(self.quote_volume),
+            json!(self.open_time),
+            json!(self.close_time),
+            json!(self.count),
+        ]))
+    }
+}
+
+impl Tabular for Candle {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "open_time",
+            "close_time",
+            "open",
+            "high",
+            "low",
+            "close",
+            "volume",
+            "quote_volume",
+            "trades",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.open_time),
+            json!(self.close_time),
+            json!(self.open),
+            json!(self.high),
+            json!(self.low),
+            json!(self.close),
+            json!(self.volume),
+            json!(self.quote_volume),
+            json!(self.trades),
+        ]))
+    }
+}
+
+impl Tabular for OrderBook {
+    fn columns() -> &'static [&'static str] {
+        &["last_update_id", "side", "price", "quantity"]
+    }
+
+    fn into_rows(self) -> Rows {
+        let last_update_id = self.last_update_id;
+        let sides =
+            [("bid", self.bids), ("ask", self.asks)];
+        Box::new(sides.into_iter().flat_map(
+            move |(side, levels)| {
+                levels.into_iter().map(move |level| {
+                    vec![
+                        json!(last_update_id),
+                        json!(side),
+                        json!(level.price),
+                        json!(level.quantity),
+                    ]
+                })
+            },
+        ))
+    }
+}
+
+impl Tabular for ExchangeInfo {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "symbol",
+            "status",
+            "base_asset",
+            "quote_asset",
+            "base_asset_precision",
+            "quote_asset_precision",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(self.symbols.into_iter().map(|symbol| {
+            vec![
+                json!(symbol.symbol),
+                json!(symbol.status),
+                json!(symbol.base_asset),
+                json!(symbol.quote_asset),
+                json!(symbol.base_asset_precision),
+                json!(symbol.quote_asset_precision),
+            ]
+        }))
+    }
+}
+
+impl Tabular for Quote {
+    fn columns() -> &'static [&'static str] {
+        &["code", "rate", "time", "source"]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.code),
+            json!(self.rate),
+            json!(self.time),
+            json!(self.source.as_str()),
+        ]))
+    }
+}
+
+impl Tabular for AssetSummary {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "symbol",
+            "name",
+            "asset_class",
+            "quote",
+            "change_hour",
+            "change_day",
+            "change_week",
+            "change_month",
+            "change_year",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.id),
+            json!(self.symbol),
+            json!(self.name),
+            json!(self.asset_class.as_str()),
+            json!(self.quote),
+            json!(self.change.hour),
+            json!(self.change.day),
+            json!(self.change.week),
+            json!(self.change.month),
+            json!(self.change.year),
+        ]))
+    }
+}
+
+impl Tabular for StockQuote {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "symbol",
+            "open",
+            "high",
+            "low",
+            "price",
+            "volume",
+            "latest_trading_day",
+            "previous_close",
+            "change",
+            "change_percent",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.symbol),
+            json!(self.open),
+            json!(self.high),
+            json!(self.low),
+            json!(self.price),
+            json!(self.volume),
+            json!(self.latest_trading_day),
+            json!(self.previous_close),
+            json!(self.change),
+            json!(self.change_percent),
+        ]))
+    }
+}
+
+impl Tabular for StockSeries {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "symbol", "interval", "date", "open", "high",
+            "low", "close", "volume",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let symbol = self.symbol;
+        let interval = self.interval;
+        Box::new(self.bars.into_iter().map(move |bar| {
+            vec![
+                json!(symbol),
+                json!(interval),
+                json!(bar.date),
+                json!(bar.open),
+                json!(bar.high),
+                json!(bar.low),
+                json!(bar.close),
+                json!(bar.volume),
+            ]
+        }))
+    }
+}
+
+impl Tabular for SymbolMatch {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "symbol",
+            "name",
+            "asset_type",
+            "region",
+            "market_open",
+            "market_close",
+            "timezone",
+            "currency",
+            "match_score",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.symbol),
+            json!(self.name),
+            json!(self.asset_type),
+            json!(self.region),
+            json!(self.market_open),
+            json!(self.market_close),
+            json!(self.timezone),
+            json!(self.currency),
+            json!(self.match_score),
+        ]))
+    }
+}
+
+impl Tabular for CurrencyExchangeRate {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "from_code",
+            "from_name",
+            "to_code",
+            "to_name",
+            "rate",
+            "bid",
+            "ask",
+            "last_refreshed",
+            "time_zone",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        Box::new(std::iter::once(vec![
+            json!(self.from_code),
+            json!(self.from_name),
+            json!(self.to_code),
+            json!(self.to_name),
+            json!(self.rate),
+            json!(self.bid),
+            json!(self.ask),
+            json!(self.last_refreshed),
+            json!(self.time_zone),
+        ]))
+    }
+}
+
+impl Tabular for FxSeries {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "from_symbol",
+            "to_symbol",
+            "date",
+            "open",
+            "high",
+            "low",
+            "close",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let from_symbol = self.from_symbol;
+        let to_symbol = self.to_symbol;
+        Box::new(self.bars.into_iter().map(move |bar| {
+            vec![
+                json!(from_symbol),
+                json!(to_symbol),
+                json!(bar.date),
+                json!(bar.open),
+                json!(bar.high),
+                json!(bar.low),
+                json!(bar.close),
+            ]
+        }))
+    }
+}
+
+impl Tabular for DigitalCurrencySeries {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "symbol",
+            "market",
+            "date",
+            "open",
+            "high",
+            "low",
+            "close",
+            "volume",
+            "close_usd",
+            "market_cap_usd",
+        ]
+    }
+
+    fn into_rows(self) -> Rows {
+        let symbol = self.symbol;
+        let market = self.market;
+        Box::new(self.bars.into_iter().map(move |bar| {
+            vec![
+                json!(symbol),
+                json!(market),
+                json!(bar.date),
+                json!(bar.open),
+                json!(bar.high),
+                json!(bar.low),
+                json!(bar.close),
+                json!(bar.volume),
+                json!(bar.close_usd),
+                json!(bar.market_cap_usd),
+            ]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alphavantage_service::models::RawTrade,
+        coin_watch_service::models::History,
+    };
+
+    fn coin() -> Coin {
+        Coin::new(
+            Some("BTC".into()),
+            Some(64000.5),
+            Some(1000),
+            None,
+            Delta::new(
+                Some(1.01),
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+        )
+    }
+
+    fn history() -> CoinHistory {
+        let point = |date| History {
+            date: Some(date),
+            rate: Some(1.5),
+            volume: None,
+            cap: None,
+            liquidity: None,
+        };
+        CoinHistory {
+            code: Some("ETH".into()),
+            name: None,
+            symbol: None,
+            rank: None,
+            color: None,
+            png64: None,
+            webp64: None,
+            all_time_high_usd: None,
+            links: None,
+            history: Some(vec![point(1), point(2)]),
+        }
+    }
+
+    #[test]
+    fn coin_row_matches_columns() {
+        assert_eq!(
+            coin().into_rows().next().unwrap().len(),
+            Coin::columns().len()
+        );
+    }
+
+    #[test]
+    fn coin_meta_row_matches_columns() {
+        let meta = CoinMeta {
+            name: None,
+            symbol: None,
+            rank: None,
+            color: None,
+            png64: None,
+            webp64: None,
+            all_time_high_usd: None,
+            code: None,
+            rate: None,
+            delta: None,
+        };
+        assert_eq!(
+            meta.into_rows().next().unwrap().len(),
+            CoinMeta::columns().len()
+        );
+    }
+
+    #[test]
+    fn coin_history_expands_to_one_row_per_point() {
+        let rows: Vec<Vec<Value>> =
+            history().into_rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0], json!("ETH"));
+        assert_eq!(rows[1][1], json!(2));
+    }
+
+    #[test]
+    fn top_and_bottom_trades_rows_are_categorized() {
+        let trade = MostActivelyTraded::from_raw(
+            "AAPL".into(),
+            RawTrade {
+                price: "170.1".into(),
+                change_amount: "1.2".into(),
+                change_percentage: "0.7%".into(),
+                volume: "100".into(),
+            },
+        );
+        let trades = TopAndBottomTrades {
+            metadata: String::new(),
+            last_updated: "2024-03-28".into(),
+            top_gainers: vec![trade.clone()],
+            top_losers: vec![],
+            most_actively_traded: vec![trade],
+        };
+        let categories: Vec<Value> = trades
+            .into_rows()
+            .map(|row| row[1].clone())
+            .collect();
+        assert_eq!(
+            categories,
+            vec![
+                json!("top_gainers"),
+                json!("most_actively_traded")
+            ]
+        );
+    }
+
+    #[test]
+    fn order_book_rows_are_sided() {
+        use crate::binance_service::models::OrderBookLevel;
+
+        let level = OrderBookLevel {
+            price: 4.0,
+            quantity: 1.5,
+        };
+        let order_book = OrderBook {
+            last_update_id: 7,
+            bids: vec![level, level],
+            asks: vec![level],
+        };
+        let rows: Vec<Vec<Value>> =
+            order_book.into_rows().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[1],
+            vec![
+                json!(7),
+                json!("bid"),
+                json!(4.0),
+                json!(1.5)
+            ]
+        );
+        assert_eq!(rows[2][1], json!("ask"));
+    }
+
+    #[test]
+    fn stock_series_expands_to_one_row_per_bar() {
+        use crate::alphavantage_service::models::StockBar;
+
+        let bar = |date: &str| StockBar {
+            date: date.into(),
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 100,
+        };
+        let series = StockSeries {
+            symbol: "IBM".into(),
+            interval: Some("5min".into()),
+            last_refreshed: "2024-03-28 19:55:00".into(),
+            time_zone: "US/Eastern".into(),
+            bars: vec![
+                bar("2024-03-28 19:50:00"),
+                bar("2024-03-28 19:55:00"),
+            ],
+        };
+        let rows: Vec<Vec<Value>> =
+            series.into_rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1].len(),
+            StockSeries::columns().len()
+        );
+        assert_eq!(
+            rows[1][2],
+            json!("2024-03-28 19:55:00")
+        );
+    }
+
+    #[test]
+    fn csv_header_for_vec() {
+        assert_eq!(
+            csv_header::<Vec<CoinHistory>>(),
+            "code,date,rate,volume,cap,liquidity\n"
+        );
+    }
+
+    #[test]
+    fn csv_line_leaves_missing_values_empty() {
+        assert_eq!(
+            csv_line(coin().into_rows().next().unwrap()),
+            "BTC,64000.5,1000,,1.01,,,,,\n"
+        );
+    }
+
+    #[test]
+    fn csv_line_quotes_separators() {
+        assert_eq!(
+            csv_line(vec![json!("a,b"), json!(1)]),
+            "\"a,b\",1\n"
+        );
+    }
+
+    #[test]
+    fn json_line_keeps_column_order() {
+        assert_eq!(
+            json_line::<CoinHistory>(
+                history().into_rows().next().unwrap()
+            ),
+            "{\"code\":\"ETH\",\"date\":1,\"rate\":1.5,\"volume\":null,\"cap\":null,\"liquidity\":null}\n"
+        );
+    }
+}